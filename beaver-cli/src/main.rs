@@ -4,12 +4,17 @@ use std::collections::LinkedList;
 use std::ffi::OsString;
 use std::fmt::FormattingOptions;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
+use beaver::backend::Backend;
+use beaver::coverage::{CoverageFormat, CoverageOptions};
+use beaver::install::InstallOptions;
 use beaver::target::TargetRef;
-use beaver::{Beaver, BeaverError, OptimizationMode, PrintOptions};
+use beaver::testing::TestOptions;
+use beaver::{Beaver, BeaverError, OptimizationMode, PrintOptions, Sanitizer};
 use clap::{arg, Arg, ArgAction, ArgMatches, Command, ValueHint};
 use lazy_static::lazy_static;
 use log::*;
@@ -20,6 +25,7 @@ include!(concat!(env!("OUT_DIR"), "/rb_const_gen.rs"));
 lazy_static! {
     static ref default_opt_mode: OsString = Into::<OsString>::into(OptimizationMode::default());
     static ref release_opt_mode: OsString = Into::<OsString>::into(OptimizationMode::Release);
+    static ref default_backend: OsString = Into::<OsString>::into(Backend::default());
     static ref default_target: OsString = OsString::from(Triple::host().to_string());
 }
 
@@ -55,16 +61,27 @@ impl std::fmt::Display for TripleParseError {
     }
 }
 
+fn test_options(matches: &ArgMatches) -> TestOptions {
+    let mut options = TestOptions::default();
+    options.filter = matches.get_one::<String>("filter").cloned();
+    if let Some(jobs) = matches.get_one::<usize>("jobs") {
+        options.jobs = *jobs;
+    }
+    let timeout = *matches.get_one::<f64>("timeout").unwrap();
+    options.timeout = if timeout > 0.0 { Some(Duration::from_secs_f64(timeout)) } else { None };
+    options.junit = matches.get_one::<String>("junit").map(PathBuf::from);
+    return options;
+}
+
 fn main() -> Result<(), MainError> {
     let build_args = [
-        arg!(-o --opt [OPT] "Optimization mode")
+        arg!(-o --opt [OPT] "Optimization mode or profile")
             .default_value(default_opt_mode.as_os_str())
             .default_missing_value(release_opt_mode.as_os_str())
             .value_hint(ValueHint::Other)
-            .long_help("Optimization mode
+            .long_help("Optimization mode or profile
+Built-in optimization modes are debug, release, relwithdebinfo, size (-Os), minsize (-Oz) and fast (-Ofast). Other profiles can be defined in the script with `profile`.
 When the argument is provided, but without a value, then the optimization mode is set to release")
-            .value_parser(["debug", "release"])
-            .ignore_case(true)
             .help_heading("Build options"),
         Arg::new("target-triple")
             .short('t')
@@ -73,7 +90,27 @@ When the argument is provided, but without a value, then the optimization mode i
             .value_hint(ValueHint::Other)
             .help("The target to compile to")
             .help_heading("Build options")
-            .default_value(default_target.as_os_str())
+            .default_value(default_target.as_os_str()),
+        Arg::new("sanitize")
+            .long("sanitize")
+            .value_name("SANITIZERS")
+            .value_hint(ValueHint::Other)
+            .value_delimiter(',')
+            .help("Build C targets with runtime sanitizers, e.g. address,undefined")
+            .long_help("Build C targets with runtime sanitizers, separated by commas
+Available sanitizers are address (asan), undefined (ubsan), thread (tsan), memory (msan, clang only) and leak (lsan). Sanitized builds use their own build directory")
+            .help_heading("Build options")
+    ];
+
+    let test_args = [
+        arg!([filter] "Only run tests whose name contains FILTER"),
+        arg!(-j --jobs <N> "The number of tests to run in parallel (default: the number of CPUs)")
+            .value_parser(clap::value_parser!(usize)),
+        arg!(--timeout <SECONDS> "The timeout for tests that don't specify one. 0 disables the timeout")
+            .value_parser(clap::value_parser!(f64))
+            .default_value("300"),
+        arg!(--junit <FILE> "Write a JUnit XML report of the results to FILE")
+            .value_hint(ValueHint::FilePath)
     ];
 
     let matches = Command::new("beaver")
@@ -108,6 +145,19 @@ When the argument is provided, but without a value, then the optimization mode i
             .action(ArgAction::Count)
             .help("Sets the level of verbosity")
             .global(true))
+        .arg(Arg::new("backend")
+            .long("backend")
+            .value_name("BACKEND")
+            .value_hint(ValueHint::Other)
+            .help("The backend used to execute the build")
+            .long_help("The backend used to execute the build
+ninja: generate a build.ninja file and execute it using ninja
+native: execute the build graph in-process, without requiring ninja")
+            .value_parser(["ninja", "native"])
+            .ignore_case(true)
+            .default_value(default_backend.as_os_str())
+            .help_heading("Build options")
+            .global(true))
 
         // Build arguments
         .args(build_args.iter())
@@ -127,6 +177,50 @@ When the argument is provided, but without a value, then the optimization mode i
             .arg(arg!([args]... "Arguments passed to the executable to run"))
             .args(build_args.iter()))
 
+        .subcommand(Command::new("test")
+            .about("Build and run tests")
+            .long_about("Build and run all test targets. Tests of imported CMake projects (ctest) and `cargo test` for imported Cargo projects are run as well")
+            .args(test_args.iter())
+            .args(build_args.iter()))
+
+        .subcommand(Command::new("coverage")
+            .about("Build with coverage instrumentation, run tests and report the line coverage")
+            .long_about("Build all C targets with coverage instrumentation in a separate build directory, run the tests and report the line coverage of the project sources using gcov (or llvm-cov when compiling with clang). The lcov and html formats require lcov and genhtml")
+            .args(test_args.iter())
+            .arg(arg!(--format <FORMAT> "The format of the report")
+                .long_help("The format of the report
+text: print a summary of the line coverage per file
+lcov: also write an lcov tracefile to coverage/coverage.info in the build directory
+html: also generate an HTML report in coverage/html in the build directory")
+                .value_parser(["text", "lcov", "html"])
+                .ignore_case(true)
+                .default_value("text"))
+            .arg(arg!(--output <PATH> "Write the report to PATH instead of the build directory")
+                .value_hint(ValueHint::AnyPath))
+            .args(build_args.iter()))
+
+        .subcommand(Command::new("install")
+            .about("Build and install targets")
+            .long_about("Build and install targets. Executables are installed in PREFIX/bin, libraries in PREFIX/lib, public headers in PREFIX/include and pkg-config files in PREFIX/lib/pkgconfig. The installed files are recorded, so that they can be removed with `beaver uninstall`")
+            .arg(arg!([targets]... "Target(s) to install")
+                .long_help("Target(s) to install\nWhen no targets are passed, all targets in the current project are installed."))
+            .arg(arg!(--prefix <DIR> "The installation prefix")
+                .value_hint(ValueHint::DirPath)
+                .default_value("/usr/local"))
+            .arg(arg!(--destdir <DIR> "Stage the installation in DIR, e.g. for packaging. Defaults to the DESTDIR environment variable")
+                .value_hint(ValueHint::DirPath))
+            .arg(arg!(--bindir <DIR> "Directory for executables, relative to the prefix")
+                .default_value("bin"))
+            .arg(arg!(--libdir <DIR> "Directory for libraries, relative to the prefix")
+                .default_value("lib"))
+            .arg(arg!(--includedir <DIR> "Directory for header files, relative to the prefix")
+                .default_value("include"))
+            .arg(arg!(--rpath <PATH> "The rpath of installed executables, replacing the build directory. Defaults to the library directory, relative to the executable"))
+            .args(build_args.iter()))
+
+        .subcommand(Command::new("uninstall")
+            .about("Remove all files installed by `beaver install`"))
+
         .subcommand(Command::new("clean")
             .about("Clean the project")
             .long_about("Clean the project. By default this command will clean all projects, unless a specific project is passed as an argument to this command")
@@ -140,6 +234,23 @@ When the argument is provided, but without a value, then the optimization mode i
             .arg(arg!(-d --dependencies "Display the dependencies of each target"))
             .arg(arg!(-s --sources "Display the sources of each target")))
 
+        .subcommand(Command::new("export")
+            .about("Export the build graph to another build system")
+            .subcommand_required(true)
+            .subcommand(Command::new("makefile")
+                .about("Write a GNU Makefile which builds all projects without beaver")
+                .long_about("Write a GNU Makefile which builds all projects without beaver. Pre-phase hooks are not executed and custom targets can't be built from the Makefile")
+                .arg(arg!([file] "The file to write the Makefile to")
+                    .value_hint(ValueHint::FilePath)
+                    .default_value("Makefile"))
+                .args(build_args.iter())))
+
+        .subcommand(Command::new("callback")
+            .about("Send a request to the beaver process which started the build")
+            .long_about("Send a request to the beaver process which started the build. This is used by the build files to build custom targets")
+            .hide(true)
+            .arg(arg!(<request>... "The request")))
+
         .get_matches();
 
     run_cli(&matches)
@@ -181,6 +292,16 @@ fn run_cli(matches: &ArgMatches) -> Result<(), MainError> {
 
     clog.init();
 
+    // Called by the build files, so the script is not executed
+    if let Some(("callback", matches)) = matches.subcommand() {
+        let request = matches.get_many::<String>("request").unwrap()
+            .map(|str| str.as_str())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let status = beaver::callback::request(&request)?;
+        std::process::exit(status);
+    }
+
     // Look for any of these files, in this order
     let filenames = ["beaver.rb", "Beaverfile", "build.rb", "make.rb"];
     let script_file = match matches.get_one::<String>("script-file") {
@@ -217,27 +338,44 @@ fn run_cli(matches: &ArgMatches) -> Result<(), MainError> {
     };
 
     // build args
-    let opt = match matches.subcommand() {
-        None => OptimizationMode::try_from(matches.get_one::<String>("opt").unwrap().as_str())?,
-        Some(("run", matches)) => OptimizationMode::try_from(matches.get_one::<String>("opt").unwrap().as_str())?,
-        _ => OptimizationMode::default()
+    let build_matches = match matches.subcommand() {
+        None => Some(matches),
+        Some(("run" | "test" | "coverage" | "install", matches)) => Some(matches),
+        Some(("export", matches)) => matches.subcommand().map(|(_, matches)| matches),
+        _ => None
+    };
+    let profile = match build_matches {
+        Some(matches) => matches.get_one::<String>("opt").unwrap().as_str(),
+        None => default_opt_mode.to_str().unwrap()
+    };
+    let sanitizers = match build_matches.and_then(|matches| matches.get_many::<String>("sanitize")) {
+        Some(names) => names.map(|name| Sanitizer::try_from(name.as_str())).collect::<Result<Vec<Sanitizer>, BeaverError>>()?,
+        None => Vec::new()
     };
 
     let target = matches.get_one::<String>("target-triple").unwrap();
     let target = Triple::from_str(target).map_err(|err| TripleParseError { inner: err })?;
 
+    let backend = Backend::try_from(matches.get_one::<String>("backend").unwrap().as_str())?;
+
     let subcommand_match = matches.subcommand();
     let subcommand = subcommand_match.map(|(subcommand, _)| subcommand).unwrap_or("build");
 
     // Execute script
-    let beaver = Arc::new(Beaver::new(
+    let mut beaver = Beaver::new(
         Some(color),
-        opt,
+        OptimizationMode::default(),
         verbosity != 0,
         *debug,
         // Triple::host()
-        target
-    )?);
+        target,
+        backend
+    )?;
+    // Custom profiles are defined by the script
+    beaver.select_profile(profile)?;
+    beaver.set_sanitizers(sanitizers)?;
+    beaver.set_coverage(subcommand == "coverage");
+    let beaver = Arc::new(beaver);
     let beaver_weak = Arc::downgrade(&beaver);
     let ctx = unsafe { beaver_ruby::execute_script(script_file, script_args, &beaver_weak, subcommand)? };
 
@@ -293,6 +431,41 @@ fn run_cli(matches: &ArgMatches) -> Result<(), MainError> {
                 }
             }
         },
+        Some(("test", _)) if beaver.has_command("test")? => {
+            // Scripts defining a `test` command take precedence
+            beaver.run_command("test")?;
+        },
+        Some(("test", matches)) => {
+            beaver.test(&test_options(matches))?;
+        },
+        Some(("coverage", matches)) => {
+            let options = CoverageOptions {
+                format: CoverageFormat::try_from(matches.get_one::<String>("format").unwrap().as_str())?,
+                output: matches.get_one::<String>("output").map(PathBuf::from)
+            };
+            beaver.coverage(&test_options(matches), &options)?;
+        },
+        Some(("install", matches)) => {
+            let mut options = InstallOptions::new(PathBuf::from(matches.get_one::<String>("prefix").unwrap()));
+            options.destdir = matches.get_one::<String>("destdir").map(PathBuf::from)
+                .or_else(|| std::env::var_os("DESTDIR").filter(|dir| !dir.is_empty()).map(PathBuf::from));
+            options.dirs.bindir = PathBuf::from(matches.get_one::<String>("bindir").unwrap());
+            options.dirs.libdir = PathBuf::from(matches.get_one::<String>("libdir").unwrap());
+            options.dirs.includedir = PathBuf::from(matches.get_one::<String>("includedir").unwrap());
+            options.rpath = matches.get_one::<String>("rpath").cloned();
+
+            match matches.get_many::<String>("targets") {
+                Some(targets) => {
+                    beaver.install(&targets.map(|target_name| {
+                        beaver.parse_target_ref(target_name)
+                    }).collect::<Result<Vec<TargetRef>, BeaverError>>()?, &options)?;
+                },
+                None => beaver.install_current_project(&options)?
+            }
+        },
+        Some(("uninstall", _)) => {
+            beaver.uninstall()?;
+        },
         Some(("list", _)) => {
             let matches = matches.subcommand_matches("list").unwrap();
             let artifacts = matches.get_flag("artifacts");
@@ -314,6 +487,16 @@ fn run_cli(matches: &ArgMatches) -> Result<(), MainError> {
                 beaver.clean()?
             }
         },
+        Some(("export", matches)) => {
+            match matches.subcommand() {
+                Some(("makefile", matches)) => {
+                    let file = matches.get_one::<String>("file").unwrap();
+                    beaver.export_makefile(Path::new(file))?;
+                },
+                Some((format, _)) => unreachable!("Invalid export format {format}"),
+                None => unreachable!("Export format is required")
+            }
+        },
         Some((subcommand_name, _)) => {
             unreachable!("Invalid subcommand {subcommand_name}")
         }
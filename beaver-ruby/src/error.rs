@@ -70,7 +70,7 @@ pub enum BeaverRubyError {
     BeaverError(#[from] beaver::BeaverError),
     #[error("Cannot convert {} to {}", {
         let ctx = &CTX.get().unwrap();
-        if ctx.thread_id == std::thread::current().id() {
+        if ctx.on_ruby_thread() {
             if .0.is_nil() {
                 String::from("nil")
             } else {
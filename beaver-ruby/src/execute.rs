@@ -1,7 +1,9 @@
 use std::cell::RefCell;
 use std::collections::LinkedList;
+use std::ffi::c_void;
 use std::ops::Deref;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{self, Arc, OnceLock, mpsc};
 use std::thread::ThreadId;
 
@@ -41,8 +43,14 @@ impl<'a> BeaverRubyContext<'a> {
         self.args.borrow()
     }
 
+    /// Whether the current thread can call into Ruby: the Ruby thread, or a thread created by Ruby
+    /// (e.g. for custom targets built in parallel)
+    pub(crate) fn on_ruby_thread(&self) -> bool {
+        self.thread_id == std::thread::current().id() || magnus::Ruby::get().is_ok()
+    }
+
     pub fn block_execute_on(&self, worker: RubyThreadWorker<'a>) -> Result<(), BeaverRubyError> {
-        if self.thread_id == std::thread::current().id() {
+        if self.on_ruby_thread() {
             worker()
         } else {
             block_execute_on(&self.sender, worker)
@@ -50,7 +58,7 @@ impl<'a> BeaverRubyContext<'a> {
     }
 
     pub fn async_execute_on(&self, worker: RubyThreadWorker<'a>) {
-        if self.thread_id == std::thread::current().id() {
+        if self.on_ruby_thread() {
             worker().unwrap();
         } else {
             async_execute_on(&self.sender, worker)
@@ -101,6 +109,29 @@ pub(crate) fn async_execute_on<'a>(sender: &RubyThreadSender<'a>, worker: RubyTh
     sender.send((worker, None)).unwrap()
 }
 
+/// The number of Ruby threads running the build commands of parallel custom targets. While there
+/// are any, the Ruby thread releases the global VM lock while it waits for work. When the last
+/// one finishes, it wakes up the Ruby thread so that it holds the lock again (e.g. when Ruby is
+/// cleaned up).
+pub(crate) static PARALLEL_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Run `func` on a Ruby thread without holding the global VM lock, so that other Ruby threads
+/// can run in the meantime. `func` must not call into Ruby.
+pub(crate) fn without_gvl<F: FnOnce() -> R, R>(func: F) -> R {
+    unsafe extern "C" fn call<F: FnOnce() -> R, R>(data: *mut c_void) -> *mut c_void {
+        let data = unsafe { &mut *(data as *mut (Option<F>, Option<R>)) };
+        let func = data.0.take().unwrap();
+        data.1 = Some(func());
+        std::ptr::null_mut()
+    }
+
+    let mut data: (Option<F>, Option<R>) = (Some(func), None);
+    unsafe {
+        rb_sys::rb_thread_call_without_gvl(Some(call::<F, R>), &mut data as *mut _ as *mut c_void, None, std::ptr::null_mut());
+    }
+    return data.1.unwrap();
+}
+
 /// This function is not thread safe and should only be called once
 pub unsafe fn execute_script<P: AsRef<Path>>(script_file: P, args: LinkedList<String>, context: &sync::Weak<Beaver>, subcommand: &str) -> crate::Result<Arc<BeaverRubyContext<'static>>> {
     let (tx, rx) = mpsc::channel::<(RubyThreadWorker, Option<mpsc::Sender<Result<(), BeaverRubyError>>>)>();
@@ -109,7 +140,13 @@ pub unsafe fn execute_script<P: AsRef<Path>>(script_file: P, args: LinkedList<St
     let ruby_thread = std::thread::spawn(move || {
         thread_tx.send(std::thread::current().id()).unwrap();
         loop {
-            match rx.recv() {
+            // Let parallel custom targets run on their Ruby threads while waiting for work
+            let task = if PARALLEL_THREADS.load(Ordering::SeqCst) > 0 {
+                without_gvl(|| rx.recv())
+            } else {
+                rx.recv()
+            };
+            match task {
                 Ok((task, done)) => {
                     let res = task();
                     if let Some(done) = done {
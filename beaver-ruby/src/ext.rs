@@ -3,17 +3,19 @@ use std::hash::Hash;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 
-use beaver::target::custom::BuildCommand;
-use beaver::target::parameters::{Files, Flags, Headers};
-use beaver::target::{c, Dependency, Language, LibraryArtifactType, LibraryTargetDependency, TArtifactType, Version};
+use beaver::target::custom::{BuildCommand, BuildOutput};
+use beaver::target::parameters::{Dependencies, Files, Flags, Headers};
+use beaver::target::{c, Dependency, DependencyVisibility, Language, LibraryArtifactType, LibraryTargetDependency, TArtifactType, Version};
 use beaver::traits::{AnyTarget, Library};
 use beaver::{Beaver, BeaverError};
 use magnus::value::ReprValue;
 use utils::UnsafeSendable;
 
+use crate::ruby_lib::build_rule::GeneratedWrapper;
 use crate::ruby_lib::dependency::DependencyWrapper;
-use crate::{BeaverRubyError, block_execute_on};
+use crate::{async_execute_on, block_execute_on, BeaverRubyError, PARALLEL_THREADS};
 
 /// Parses a ruby string or array into a vector of strings
 pub(crate) fn parse_to_string_vec(value: magnus::Value) -> crate::Result<Vec<String>> {
@@ -38,10 +40,13 @@ fn parse_dependency(value: magnus::Value, context: &Beaver) -> crate::Result<Dep
     } else if let Some(symbol) = magnus::Symbol::from_value(value) {
         return parse_lib_dependency_from_str(symbol.name()?.as_ref(), None, context).map(|libdep| Dependency::Library(libdep));
     } else if let Some(dep) = magnus::RTypedData::from_value(value) {
+        if let Ok(generated) = dep.get::<GeneratedWrapper>() {
+            return Ok(generated.dependency());
+        }
         let w: &DependencyWrapper = dep.get()?;
         return Ok(w.0.clone());
     } else {
-        return Err(BeaverRubyError::IncompatibleType(value, "String, Symbol or Dependency class"))
+        return Err(BeaverRubyError::IncompatibleType(value, "String, Symbol, Dependency or Generated class"))
     }
 }
 
@@ -166,22 +171,32 @@ impl MagnusFilesConvertExt for Files {
        if let Some(str) = magnus::RString::from_value(value) {
             return Files::from_pat(unsafe { str.as_str()? }, base_dir)
                 .map_err(|err| BeaverRubyError::from(err)).map_err(Into::<magnus::Error>::into);
+        } else if let Some(generated) = GeneratedWrapper::from_value(value) {
+            return Ok(Files::from_generated(generated.0.clone()));
         } else if let Some(arr) = magnus::RArray::from_value(value) {
-            let rstrarr: Vec<(Option<magnus::RString>, magnus::Value)> = arr.into_iter()
-                    .map(|value| (magnus::RString::from_value(value), value))
-                    .collect();
+            let mut generated_files: Vec<PathBuf> = Vec::new();
+            let mut rstrarr: Vec<magnus::RString> = Vec::new();
+            for value in arr.into_iter() {
+                if let Some(str) = magnus::RString::from_value(value) {
+                    rstrarr.push(str);
+                } else if let Some(generated) = GeneratedWrapper::from_value(value) {
+                    generated_files.extend(generated.0.iter().cloned());
+                } else {
+                    return Err(BeaverRubyError::IncompatibleType(value, "String or Generated").into());
+                }
+            }
+            if rstrarr.is_empty() {
+                return Ok(Files::from_generated(generated_files));
+            }
             let pats = rstrarr.iter()
-                // .map(|value| (magnus::RString::from_value(value), value))
-                .map(|(string, value)| {
-                    match string {
-                        Some(str) => unsafe { str.as_str() },
-                        None => Err(BeaverRubyError::IncompatibleType(*value, "String").into()),
-                    }
-                }).collect::<Result<Vec<&str>, magnus::Error>>()?;
-            return Files::from_pats(&pats, base_dir)
-                .map_err(|err| BeaverRubyError::from(err)).map_err(Into::<magnus::Error>::into);
+                .map(|str| unsafe { str.as_str() })
+                .collect::<Result<Vec<&str>, magnus::Error>>()?;
+            let mut files = Files::from_pats(&pats, base_dir)
+                .map_err(|err| BeaverRubyError::from(err))?;
+            files.extend_generated(generated_files);
+            return Ok(files);
         } else {
-            return Err(BeaverRubyError::IncompatibleType(value, "Array or String").into());
+            return Err(BeaverRubyError::IncompatibleType(value, "Array, String or Generated").into());
         }
     }
 }
@@ -210,26 +225,65 @@ impl MagnusConvertExt for Flags {
     }
 }
 
+/// Include directories and include directories generated by other targets
+fn parse_header_dirs(value: magnus::Value) -> crate::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    if let Some(str) = magnus::RString::from_value(value) {
+        return Ok((vec![PathBuf::from(str.to_string()?)], Vec::new()));
+    } else if let Some(generated) = GeneratedWrapper::from_value(value) {
+        return Ok((Vec::new(), generated.0.clone()));
+    } else if let Some(arr) = magnus::RArray::from_value(value) {
+        let mut dirs = Vec::new();
+        let mut generated_dirs = Vec::new();
+        for value in arr.into_iter() {
+            if let Some(str) = magnus::RString::from_value(value) {
+                dirs.push(PathBuf::from(str.to_string()?));
+            } else if let Some(generated) = GeneratedWrapper::from_value(value) {
+                generated_dirs.extend(generated.0.iter().cloned());
+            } else {
+                return Err(BeaverRubyError::IncompatibleType(value, "String or Generated"));
+            }
+        }
+        return Ok((dirs, generated_dirs));
+    } else {
+        return Err(BeaverRubyError::IncompatibleType(value, "Array, String or Generated"));
+    }
+}
+
 impl MagnusConvertExt for Headers {
     fn try_from_value(value: magnus::Value) -> Result<Self, magnus::Error> where Self: Sized {
-        let ruby = magnus::Ruby::get().unwrap();
-
-        if value.is_kind_of(ruby.class_string()) || value.is_kind_of(ruby.class_array()) {
-            Ok(Headers::new(parse_to_string_vec(value)?.into_iter().map(|str| PathBuf::from(str)).collect(), Vec::new()))
-        } else if let Some(hash) = magnus::RHash::from_value(value) {
-            let public_headers = if let Some(value) = hash.get(magnus::Symbol::new("public")) {
-                parse_to_string_vec(value)?.into_iter().map(|str| PathBuf::from(str)).collect()
+        if let Some(hash) = magnus::RHash::from_value(value) {
+            let (public_headers, public_generated) = if let Some(value) = hash.get(magnus::Symbol::new("public")) {
+                parse_header_dirs(value)?
             } else {
-                Vec::new()
+                (Vec::new(), Vec::new())
             };
-            let private_headers = if let Some(value) = hash.get(magnus::Symbol::new("private")) {
-                parse_to_string_vec(value)?.into_iter().map(|str| PathBuf::from(str)).collect()
+            let (private_headers, private_generated) = if let Some(value) = hash.get(magnus::Symbol::new("private")) {
+                parse_header_dirs(value)?
             } else {
-                Vec::new()
+                (Vec::new(), Vec::new())
             };
-            Ok(Headers::new(public_headers, private_headers))
+            let mut headers = Headers::new(public_headers, private_headers);
+            headers.extend_generated(public_generated, true);
+            headers.extend_generated(private_generated, false);
+            Ok(headers)
         } else {
-            Err(BeaverRubyError::IncompatibleType(value, "Array, Hash or String").into())
+            let (public_headers, public_generated) = parse_header_dirs(value)?;
+            let mut headers = Headers::new(public_headers, Vec::new());
+            headers.extend_generated(public_generated, true);
+            Ok(headers)
+        }
+    }
+}
+
+/// An array of symbols, or the path of a version script or `.def` file
+impl MagnusConvertExt for c::Exports {
+    fn try_from_value(value: magnus::Value) -> Result<Self, magnus::Error> where Self: Sized {
+        if let Some(value) = magnus::RString::from_value(value) {
+            Ok(c::Exports::File(PathBuf::from(value.to_string()?)))
+        } else if magnus::RArray::from_value(value).is_some() {
+            Ok(c::Exports::Symbols(parse_to_string_vec(value)?))
+        } else {
+            Err(BeaverRubyError::IncompatibleType(value, "Array or String").into())
         }
     }
 }
@@ -261,33 +315,165 @@ impl<ArtifactType: TArtifactType + Eq + Hash> MagnusConvertExt for HashMap<Artif
     }
 }
 
-impl MagnusConvertExt for BuildCommand {
-    fn try_from_value(value: magnus::Value) -> Result<Self, magnus::Error> where Self: Sized {
-        let Some(proc) = magnus::block::Proc::from_value(value) else {
-            return Err(BeaverRubyError::IncompatibleType(value, "Proc").into());
+/// The instructions of a block, so that custom targets are rebuilt when their build block changes.
+/// Falls back to the location of the block on interpreters without `RubyVM`.
+fn proc_definition(proc: magnus::block::Proc) -> Result<String, magnus::Error> {
+    let ruby = magnus::Ruby::get().unwrap();
+    let has_iseq: bool = ruby.eval("defined?(RubyVM::InstructionSequence) ? true : false")?;
+    if has_iseq {
+        let iseq_class: magnus::Value = ruby.eval("RubyVM::InstructionSequence")?;
+        let iseq: magnus::Value = iseq_class.funcall("of", (proc,))?;
+        if !iseq.is_nil() {
+            let disasm: String = iseq.funcall("disasm", ())?;
+            return Ok(strip_disasm_locations(&disasm));
+        }
+    }
+    let location: magnus::Value = proc.funcall("source_location", ())?;
+    location.funcall("inspect", ())
+}
+
+/// Removes the locations from the disassembly of a block: the `== disasm:` headers
+/// (`#<ISeq:block in <main>@beaver.rb:3 (3,5)-(5,3)>`) and the line number of each instruction
+/// (`(   4)[Li]`). Otherwise editing an unrelated line above the block would rebuild its target.
+fn strip_disasm_locations(disasm: &str) -> String {
+    disasm.lines()
+        .filter(|line| !line.starts_with("== disasm:"))
+        .map(|line| {
+            let Some(start) = line.rfind('(') else { return line };
+            let Some(end) = line[start..].find(')').map(|end| start + end) else { return line };
+            let number = line[start + 1..end].trim();
+            let is_line_number = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+            if is_line_number && (line[end + 1..].is_empty() || line[end + 1..].starts_with('[')) {
+                line[..start].trim_end()
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Calls a block with `$stdout` and `$stderr` redirected for the current thread. The redirected
+/// streams are closed when the block finishes.
+///
+/// Custom targets built in parallel run on their own threads, so `$stdout` and `$stderr` are
+/// replaced by objects writing to the output of the current thread's target.
+const CALL_WITH_OUTPUT: &str = r#"
+unless defined?(BeaverThreadOutput)
+  class BeaverThreadOutput
+    def initialize(key, default)
+      @key, @default = key, default
+    end
+
+    def __target
+      Thread.current[@key] || @default
+    end
+
+    def respond_to_missing?(name, include_private = false)
+      __target.respond_to?(name, include_private)
+    end
+
+    def method_missing(name, *args, **kwargs, &block)
+      __target.public_send(name, *args, **kwargs, &block)
+    end
+  end
+
+  $stdout = BeaverThreadOutput.new(:beaver_stdout, $stdout)
+  $stderr = BeaverThreadOutput.new(:beaver_stderr, $stderr)
+end
+
+proc do |stdout, stderr, block|
+  Thread.current[:beaver_stdout], Thread.current[:beaver_stderr] = stdout, stderr
+  begin
+    block.call
+  ensure
+    Thread.current[:beaver_stdout], Thread.current[:beaver_stderr] = nil, nil
+    stdout.close
+    stderr.close
+  end
+end
+"#;
+
+/// A Ruby `IO` writing to `pipe`
+#[cfg(unix)]
+fn ruby_io(ruby: &magnus::Ruby, pipe: std::io::PipeWriter) -> Result<magnus::Value, magnus::Error> {
+    use std::os::fd::IntoRawFd;
+
+    let io: magnus::Value = ruby.class_io().funcall("new", (pipe.into_raw_fd(), "w"))?;
+    let _: magnus::Value = io.funcall("sync=", (true,))?;
+    return Ok(io);
+}
+
+#[cfg(unix)]
+fn call_with_output(ruby: &magnus::Ruby, proc: magnus::block::Proc, output: BuildOutput) -> Result<(), BeaverError> {
+    let call = || -> Result<magnus::Value, magnus::Error> {
+        let call_with_output: magnus::Value = ruby.eval(CALL_WITH_OUTPUT)?;
+        let stdout = ruby_io(ruby, output.stdout)?;
+        let stderr = ruby_io(ruby, output.stderr)?;
+        call_with_output.funcall("call", (stdout, stderr, proc))
+    };
+    call()
+        .map(|_| ())
+        .map_err(|err| BeaverError::AnyError(err.to_string()))
+}
+
+/// The build command of a custom target. When `parallel` is set, the block runs on its own Ruby
+/// thread, so that other blocks can run while it doesn't need Ruby (e.g. in `sh`).
+pub(crate) fn build_command(value: magnus::Value, parallel: bool) -> Result<BuildCommand, magnus::Error> {
+    let Some(proc) = magnus::block::Proc::from_value(value) else {
+        return Err(BeaverRubyError::IncompatibleType(value, "Proc").into());
+    };
+
+    let definition = proc_definition(proc)?;
+
+    let proc = Arc::new(UnsafeSendable::new(proc));
+    let weak_ctx = Arc::downgrade(crate::CTX.get().unwrap());
+    return Ok(BuildCommand { definition, run: Box::new(move |output: BuildOutput| {
+        let Some(ctx) = weak_ctx.upgrade() else {
+             return Err(BeaverError::AnyError("Ruby context has been dropped".to_string()));
         };
 
-        let proc = Arc::new(UnsafeSendable::new(proc));
-        let weak_ctx = Arc::downgrade(crate::CTX.get().unwrap());
-        return Ok(BuildCommand(Box::new(move || {
-            let Some(ctx) = weak_ctx.upgrade() else {
-                 return Err(BeaverError::AnyError("Ruby context has been dropped".to_string()));
-            };
+        #[cfg(not(unix))] {
+            _ = (output, parallel, proc, ctx);
+            return Err(BeaverError::CallbackUnsupportedPlatform);
+        }
 
+        #[cfg(unix)] {
             let proc = proc.clone();
-            block_execute_on(&ctx.sender.clone(), Box::new(move || {
-                    unsafe { proc.value().call([] as [magnus::Value; 0]) }
-                        .map(|_: magnus::Value| ())
-                        .map_err(|err| BeaverError::AnyError(err.to_string()))
+            if !parallel {
+                return block_execute_on(&ctx.sender.clone(), Box::new(move || {
+                    let ruby = magnus::Ruby::get().unwrap();
+                    call_with_output(&ruby, *unsafe { proc.value() }, output)
                         .map_err(|err| BeaverRubyError::BeaverError(err))
-            })).map_err(|err| match err {
-                BeaverRubyError::BeaverError(err) => err,
-                err => BeaverError::AnyError(err.to_string())
-            })
-            // ctx.sender.send(Box::new(move || {
-            // }))
-            // TODO: run on ruby thread
-        })));
+                })).map_err(|err| match err {
+                    BeaverRubyError::BeaverError(err) => err,
+                    err => BeaverError::AnyError(err.to_string())
+                });
+            }
+
+            let (tx, rx) = std::sync::mpsc::channel::<Result<(), BeaverError>>();
+            let sender = ctx.sender.clone();
+            block_execute_on(&ctx.sender.clone(), Box::new(move || {
+                let ruby = magnus::Ruby::get().unwrap();
+                PARALLEL_THREADS.fetch_add(1, Ordering::SeqCst);
+                ruby.thread_create_from_fn(move |ruby| {
+                    _ = tx.send(call_with_output(ruby, *unsafe { proc.value() }, output));
+                    if PARALLEL_THREADS.fetch_sub(1, Ordering::SeqCst) == 1 {
+                        async_execute_on(&sender, Box::new(|| Ok(())));
+                    }
+                });
+                Ok(())
+            })).map_err(|err| BeaverError::AnyError(err.to_string()))?;
+
+            return rx.recv()
+                .unwrap_or_else(|_| Err(BeaverError::AnyError("The build command's thread stopped".to_string())));
+        }
+    }) });
+}
+
+impl MagnusConvertExt for BuildCommand {
+    fn try_from_value(value: magnus::Value) -> Result<Self, magnus::Error> where Self: Sized {
+        build_command(value, false)
     }
 }
 
@@ -325,6 +511,25 @@ impl MagnusConvertContextExt for Vec<Dependency> {
     }
 }
 
+/// An array or a single dependency (public), or a hash of `public`, `private` and `interface` dependencies
+impl MagnusConvertContextExt for Dependencies {
+    fn try_from_value(value: magnus::Value, context: &Beaver) -> Result<Self, magnus::Error> where Self: Sized {
+        let Some(hash) = magnus::RHash::from_value(value) else {
+            return Vec::<Dependency>::try_from_value(value, context).map(Dependencies::from);
+        };
+
+        let mut dependencies = Dependencies::default();
+        hash.foreach(|key: magnus::Value, value: magnus::Value| {
+            let visibility = DependencyVisibility::parse(&String::from_string_or_sym(key)?)
+                .map_err(BeaverRubyError::from)?;
+            let value = Vec::<Dependency>::try_from_value(value, context)?;
+            dependencies.extend(value, visibility);
+            Ok(magnus::r_hash::ForEach::Continue)
+        })?;
+        return Ok(dependencies);
+    }
+}
+
 impl MagnusConvertContextExt for Dependency {
     fn try_from_value(value: magnus::Value, context: &Beaver) -> Result<Self, magnus::Error> where Self: Sized {
         parse_dependency(value, context).map_err(Into::into)
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use beaver::target::custom::GeneratedKind;
+use beaver::target::Dependency;
+use magnus::value::ReprValue;
+use magnus::{Module, TryConvert};
+
+use crate::ext::{MagnusConvertContextExt, MagnusStringConvertExt};
+use crate::{BeaverRubyError, CTX};
+
+use super::Arg;
+
+/// Files generated by a build step or a custom target. Can be used as sources or headers of a
+/// target, as inputs of another build step and as a dependency.
+#[magnus::wrap(class = "Generated")]
+pub struct GeneratedWrapper(pub(crate) Vec<PathBuf>);
+
+impl GeneratedWrapper {
+    pub(crate) fn from_value<'a>(value: magnus::Value) -> Option<&'a GeneratedWrapper> {
+        <&GeneratedWrapper as TryConvert>::try_convert(value).ok()
+    }
+
+    pub(crate) fn dependency(&self) -> Dependency {
+        if self.0.len() == 1 {
+            Dependency::File(self.0[0].clone())
+        } else {
+            Dependency::Multi(self.0.iter().cloned().map(Dependency::File).collect())
+        }
+    }
+
+    fn files(&self) -> Vec<String> {
+        self.0.iter().map(|path| path.display().to_string()).collect()
+    }
+}
+
+/// Value of a rule option or build step variable
+fn variable_value(value: magnus::Value) -> crate::Result<String> {
+    let ruby = magnus::Ruby::get().unwrap();
+    if let Some(str) = magnus::RString::from_value(value) {
+        return Ok(str.to_string()?);
+    } else if let Some(sym) = magnus::Symbol::from_value(value) {
+        return Ok(sym.name()?.to_string());
+    } else if let Some(arr) = magnus::RArray::from_value(value) {
+        return arr.into_iter()
+            .map(variable_value)
+            .collect::<crate::Result<Vec<String>>>()
+            .map(|values| values.join(" "));
+    } else if value.is_kind_of(ruby.class_true_class()) {
+        return Ok("1".to_string());
+    } else if value.is_kind_of(ruby.class_integer()) || value.is_kind_of(ruby.class_float()) {
+        return Ok(unsafe { value.to_s()? }.to_string());
+    } else {
+        return Err(BeaverRubyError::IncompatibleType(value, "String, Symbol, Array, Integer or true"));
+    }
+}
+
+/// Paths and generated files
+fn parse_paths(value: magnus::Value) -> crate::Result<Vec<PathBuf>> {
+    if let Some(str) = magnus::RString::from_value(value) {
+        return Ok(vec![PathBuf::from(str.to_string()?)]);
+    } else if let Some(generated) = GeneratedWrapper::from_value(value) {
+        return Ok(generated.0.clone());
+    } else if let Some(arr) = magnus::RArray::from_value(value) {
+        let mut paths = Vec::new();
+        for value in arr.into_iter() {
+            paths.extend(parse_paths(value)?);
+        }
+        return Ok(paths);
+    } else {
+        return Err(BeaverRubyError::IncompatibleType(value, "String, Array or Generated"));
+    }
+}
+
+fn rule_name(value: magnus::Value) -> crate::Result<String> {
+    if let Some(sym) = magnus::Symbol::from_value(value) {
+        Ok(sym.name()?.to_string())
+    } else if let Some(str) = magnus::RString::from_value(value) {
+        Ok(str.to_string()?)
+    } else {
+        Err(BeaverRubyError::IncompatibleType(value, "Symbol or String"))
+    }
+}
+
+fn rule(args: &[magnus::Value]) -> Result<(), magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    let args = magnus::scan_args::scan_args::<
+        (magnus::Value,), // required
+        (), // optional
+        (), // splat
+        (), // trailing
+        magnus::RHash, // keyword
+        () // block
+    >(args)?;
+
+    let name = rule_name(args.required.0)?;
+    let mut options: Vec<(String, String)> = Vec::new();
+    args.keywords.foreach(|key: magnus::Symbol, value: magnus::Value| {
+        options.push((key.name()?.to_string(), variable_value(value)?));
+        Ok(magnus::r_hash::ForEach::Continue)
+    })?;
+
+    context.define_rule(&name, options).map_err(|err| BeaverRubyError::from(err).into())
+}
+
+fn build(args: &[magnus::Value]) -> Result<GeneratedWrapper, magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    let args = magnus::scan_args::scan_args::<
+        (magnus::Value,), // required
+        (), // optional
+        (), // splat
+        (), // trailing
+        magnus::RHash, // keyword
+        () // block
+    >(args)?;
+
+    let rule = rule_name(args.required.0)?;
+    let mut inputs = Arg::<Vec<PathBuf>>::new("inputs");
+    let mut outputs = Arg::<Vec<PathBuf>>::new("outputs");
+    let mut dependencies = Arg::<Vec<Dependency>>::new("deps");
+    let mut variables: Vec<(String, String)> = Vec::new();
+
+    args.keywords.foreach(|key: magnus::Symbol, value: magnus::Value| {
+        match key.name()?.as_ref() {
+            "inputs" | "input" => inputs.set(parse_paths(value)?)?,
+            "outputs" | "output" => outputs.set(parse_paths(value)?)?,
+            "deps" | "dependencies" => dependencies.set(Vec::<Dependency>::try_from_value(value, context)?)?,
+            variable => variables.push((variable.to_string(), variable_value(value)?)),
+        }
+
+        Ok(magnus::r_hash::ForEach::Continue)
+    })?;
+
+    let outputs = context.add_build_step(
+        &rule,
+        &inputs.get_opt().unwrap_or(Vec::new()),
+        &outputs.get()?,
+        dependencies.get_opt().unwrap_or(Vec::new()),
+        variables
+    ).map_err(BeaverRubyError::from)?;
+
+    return Ok(GeneratedWrapper(outputs));
+}
+
+/// `generated("Proto", :sources)`: the files a custom target generates
+fn generated(target: magnus::Value, kind: magnus::Symbol) -> Result<GeneratedWrapper, magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    let target = String::from_string_or_sym(target)?;
+    let kind = match kind.name()?.as_ref() {
+        "sources" => GeneratedKind::Sources,
+        "headers" => GeneratedKind::Headers,
+        _ => return Err(BeaverRubyError::IncompatibleType(kind.as_value(), ":sources or :headers").into()),
+    };
+
+    let target_ref = context.parse_target_ref(&target).map_err(BeaverRubyError::from)?;
+    let files = context.generated_files(&target_ref, kind).map_err(BeaverRubyError::from)?;
+    return Ok(GeneratedWrapper(files));
+}
+
+pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
+    let class = ruby.define_class("Generated", ruby.class_object())?;
+    class.define_method("files", magnus::method!(GeneratedWrapper::files, 0))?;
+    class.define_method("to_a", magnus::method!(GeneratedWrapper::files, 0))?;
+
+    ruby.define_global_function("rule", magnus::function!(rule, -1));
+    ruby.define_global_function("build", magnus::function!(build, -1));
+    ruby.define_global_function("generated", magnus::function!(generated, 2));
+
+    Ok(())
+}
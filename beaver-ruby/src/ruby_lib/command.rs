@@ -1,5 +1,5 @@
 use std::collections::LinkedList;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use log::*;
 use magnus::rb_sys::AsRawValue;
@@ -217,7 +217,32 @@ fn cmd(args: &[magnus::Value]) -> Result<(), magnus::Error> {
     Ok(())
 }
 
-fn sh(args: &[magnus::Value]) -> Result<(), magnus::Error> {
+/// The stream a child process writes to instead of `global` (`$stdout` or `$stderr`). While a
+/// custom target is built, these are redirected to the process which requested the build.
+fn child_stdio(ruby: &magnus::Ruby, global: &str, inherited_fd: i32) -> Result<Stdio, magnus::Error> {
+    let io: magnus::Value = ruby.eval(global)?;
+    if !io.respond_to("fileno", false)? {
+        return Ok(Stdio::inherit());
+    }
+    let _: magnus::Value = io.funcall("flush", ())?;
+    let Some(fd) = io.funcall::<_, _, Option<i32>>("fileno", ())? else {
+        return Ok(Stdio::inherit());
+    };
+    if fd == inherited_fd {
+        return Ok(Stdio::inherit());
+    }
+
+    #[cfg(unix)] {
+        use std::os::fd::BorrowedFd;
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned().map_err(BeaverRubyError::from)?;
+        return Ok(Stdio::from(fd));
+    }
+    #[cfg(not(unix))] {
+        return Ok(Stdio::inherit());
+    }
+}
+
+fn sh(ruby: &magnus::Ruby, args: &[magnus::Value]) -> Result<(), magnus::Error> {
     let args = magnus::scan_args::scan_args::<
         (String,), // required
         (),
@@ -228,14 +253,14 @@ fn sh(args: &[magnus::Value]) -> Result<(), magnus::Error> {
     >(args)?;
 
     let console_style = console::Style::new().fg(console::Color::Color256(8));
+    let stderr: magnus::Value = ruby.eval("$stderr")?;
 
-    let mut process = if args.splat.len() == 0 {
-        eprintln!("{}", console_style.apply_to(&args.required.0));
+    let mut command = if args.splat.len() == 0 {
+        let _: magnus::Value = stderr.funcall("puts", (console_style.apply_to(&args.required.0).to_string(),))?;
 
-        Command::new("sh")
-            .args(["-c", &args.required.0])
-            .spawn()
-            .map_err(BeaverRubyError::from)
+        let mut command = Command::new("sh");
+        command.args(["-c", &args.required.0]);
+        command
     } else {
         let cmd = args.required.0;
         let Some(paths) = utils::path() else {
@@ -250,15 +275,21 @@ fn sh(args: &[magnus::Value]) -> Result<(), magnus::Error> {
         let args = splat.map(|v| v.to_string()).collect::<Vec<_>>();
 
         let cmd_str = cmd.to_string_lossy().to_string() + " " + args.iter().map(|v| format!("\"{v}\"")).fold(String::new(), |acc, v| acc + v.as_str()).as_str();
-        eprintln!("{}", console_style.apply_to(cmd_str));
+        let _: magnus::Value = stderr.funcall("puts", (console_style.apply_to(cmd_str).to_string(),))?;
+
+        let mut command = Command::new(cmd);
+        command.args(args);
+        command
+    };
 
-        Command::new(cmd)
-            .args(args)
-            .spawn()
-            .map_err(BeaverRubyError::from)
-    }?;
+    let mut process = command
+        .stdout(child_stdio(ruby, "$stdout", 1)?)
+        .stderr(child_stdio(ruby, "$stderr", 2)?)
+        .spawn()
+        .map_err(BeaverRubyError::from)?;
 
-    let exit_status = process.wait().map_err(BeaverRubyError::from)?;
+    // Other Ruby threads (e.g. parallel custom targets) can run while the command runs
+    let exit_status = crate::without_gvl(|| process.wait()).map_err(BeaverRubyError::from)?;
 
     if exit_status.success() {
         Ok(())
@@ -0,0 +1,25 @@
+use magnus::value::ReprValue;
+
+use crate::{BeaverRubyError, CTX};
+
+/// `compile_commands symlink: true`
+fn compile_commands(args: magnus::RHash) -> Result<(), magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    args.foreach(|key: magnus::Symbol, value: magnus::Value| {
+        match key.name()?.as_ref() {
+            "symlink" => context.set_compile_commands_symlink(value.to_bool()),
+            keyname => return Err(BeaverRubyError::InvalidKey(keyname.to_string()).into()),
+        }
+
+        Ok(magnus::r_hash::ForEach::Continue)
+    })?;
+
+    return Ok(());
+}
+
+pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
+    ruby.define_global_function("compile_commands", magnus::function!(compile_commands, 1));
+
+    return Ok(());
+}
@@ -5,11 +5,14 @@ mod target_accessor;
 mod project;
 mod target;
 pub(crate) mod dependency;
+pub(crate) mod build_rule;
 mod build_dir;
 mod pre;
 mod command;
 mod target_triple;
 mod cache;
+mod compile_commands;
+mod profile;
 
 pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
     project_accessor::register(ruby)?;
@@ -17,11 +20,14 @@ pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
     project::register(ruby)?;
     target::register(ruby)?;
     dependency::register(ruby)?;
+    build_rule::register(ruby)?;
     build_dir::register(ruby)?;
     pre::register(ruby)?;
     command::register(ruby)?;
     target_triple::register(ruby)?;
     cache::register(ruby)?;
+    compile_commands::register(ruby)?;
+    profile::register(ruby)?;
 
     Ok(())
 }
@@ -0,0 +1,54 @@
+use beaver::{OptimizationMode, Profile};
+
+use crate::ext::{MagnusConvertExt, MagnusStringConvertExt};
+use crate::{BeaverRubyError, CTX};
+use super::Arg;
+
+/// `profile :asan, base: :debug, cflags: ["-fsanitize=address"], linker_flags: ["-fsanitize=address"]`
+fn profile(args: &[magnus::Value]) -> Result<(), magnus::Error> {
+    let args = magnus::scan_args::scan_args::<
+        (magnus::Value,), // required (profile name)
+        (Option<magnus::RHash>,), // optional (options)
+        (),
+        (),
+        (),
+        ()
+    >(args)?;
+
+    let context = &CTX.get().unwrap().context();
+
+    let name = String::from_string_or_sym(args.required.0)?;
+
+    let mut base = Arg::<OptimizationMode>::new("base");
+    let mut cflags = Arg::<Vec<String>>::new("cflags");
+    let mut linker_flags = Arg::<Vec<String>>::new("linker_flags");
+
+    if let Some(options) = args.optional.0 {
+        options.foreach(|key: magnus::Symbol, value: magnus::Value| {
+            match key.name()?.as_ref() {
+                "base" => {
+                    let mode = String::from_string_or_sym(value)?;
+                    base.set(OptimizationMode::try_from(mode.as_str()).map_err(BeaverRubyError::from)?)?;
+                },
+                "cflags" => cflags.set(Vec::<String>::try_from_value(value)?)?,
+                "linker_flags" | "ldflags" | "lflags" => linker_flags.set(Vec::<String>::try_from_value(value)?)?,
+                keyname => return Err(BeaverRubyError::InvalidKey(keyname.to_string()).into()),
+            }
+
+            Ok(magnus::r_hash::ForEach::Continue)
+        })?;
+    }
+
+    context.define_profile(Profile::new(
+        name,
+        base.get_opt().unwrap_or_default(),
+        cflags.get_opt().unwrap_or_default(),
+        linker_flags.get_opt().unwrap_or_default()
+    )).map_err(|err| BeaverRubyError::from(err).into())
+}
+
+pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
+    ruby.define_global_function("profile", magnus::function!(profile, -1));
+
+    return Ok(());
+}
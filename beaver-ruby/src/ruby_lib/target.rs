@@ -1,21 +1,24 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use beaver::target::custom::BuildCommand;
-use beaver::target::parameters::{DefaultArgument, Files, Flags, Headers};
+use beaver::target::parameters::{DefaultArgument, Dependencies, Files, Flags, Headers};
 use beaver::target::{self, c, Dependency, ExecutableArtifactType, Language, LibraryArtifactType, TArtifactType, Version};
+use beaver::testing::TestSettings;
 use beaver::traits::{AnyExecutable, AnyLibrary, AnyTarget, Project};
 use beaver::{Beaver, BeaverError};
 use magnus::Object;
 use url::Url;
 
-use crate::ext::{MagnusArtifactConvertExt, MagnusConvertContextExt, MagnusConvertExt, MagnusFilesConvertExt, MagnusStringConvertExt};
+use crate::ext::{build_command, parse_to_string_vec, MagnusArtifactConvertExt, MagnusConvertContextExt, MagnusConvertExt, MagnusFilesConvertExt, MagnusStringConvertExt};
 use crate::{BeaverRubyError, CTX};
 
 use super::target_accessor::TargetAccessor;
 use super::Arg;
 
-fn c_target_parse_ruby_args<ArtifactType: TArtifactType>(args: magnus::RHash, context: &Beaver) -> crate::Result<target::c::TargetDescriptor<ArtifactType>> {
+/// `sources_required` is false for targets without sources (interface libraries), these get no
+/// sources when the argument is omitted
+fn c_target_parse_ruby_args<ArtifactType: TArtifactType>(args: magnus::RHash, sources_required: bool, context: &Beaver) -> crate::Result<(target::c::TargetDescriptor<ArtifactType>, Dependencies)> {
     context.with_current_project(|project| {
         let project_base_dir = project.base_dir();
 
@@ -30,7 +33,7 @@ fn c_target_parse_ruby_args<ArtifactType: TArtifactType>(args: magnus::RHash, co
         let mut headers = Arg::<Headers>::new("headers");
         let mut linker_flags = Arg::<Vec<String>>::new("linker_flags");
         let mut artifacts = Arg::<DefaultArgument<Vec<ArtifactType>>>::new("artifacts");
-        let mut dependencies = Arg::<Vec<Dependency>>::new("dependencies");
+        let mut dependencies = Arg::<Dependencies>::new("dependencies");
         let mut settings = Arg::<Vec<c::Setting>>::new("settings");
 
         args.foreach(|key: magnus::Symbol, value: magnus::Value| {
@@ -85,7 +88,7 @@ fn c_target_parse_ruby_args<ArtifactType: TArtifactType>(args: magnus::RHash, co
                     artifacts.set(DefaultArgument::Some(value))?
                 },
                 "dependencies" => {
-                    let value = Vec::<Dependency>::try_from_value(value, context)?;
+                    let value = Dependencies::try_from_value(value, context)?;
                     dependencies.set(value)?;
                 },
                 "settings" => {
@@ -98,29 +101,124 @@ fn c_target_parse_ruby_args<ArtifactType: TArtifactType>(args: magnus::RHash, co
             Ok(magnus::r_hash::ForEach::Continue)
         })?;
 
-        Ok(target::c::TargetDescriptor {
+        Ok((target::c::TargetDescriptor {
             name: name.get()?,
             description: desc.get_opt(),
             homepage: homepage.get_opt(),
             version: version.get_opt(),
             license: license.get_opt(),
             language: language.get_opt().unwrap_or(Language::C),
-            sources: sources.get()?,
+            sources: if sources_required { sources.get()? } else { sources.get_opt().unwrap_or(Files::from_generated(Vec::new())) },
             cflags: cflags.get_opt().unwrap_or(Flags::new(Vec::new(), Vec::new())),
             headers: headers.get_opt().unwrap_or(Headers::new(Vec::new(), Vec::new())),
             linker_flags: linker_flags.get_opt().unwrap_or(Vec::new()),
             artifacts: artifacts.get_opt().unwrap_or(DefaultArgument::Default),
-            dependencies: dependencies.get_opt().unwrap_or(Vec::new()),
+            dependencies: Vec::new(),
             settings: settings.get_opt().unwrap_or(Vec::new())
-        })
+        }, dependencies.get_opt().unwrap_or_default()))
     })
 }
 
+/// Parses and removes the argument `key`, a hash of source patterns to a value parsed by `parse`.
+/// Used for `source_languages:` and `source_flags:`.
+fn c_target_parse_source_patterns<T>(
+    args: magnus::RHash,
+    key: &str,
+    parse: impl Fn(magnus::Value) -> Result<T, magnus::Error>,
+    context: &Beaver
+) -> crate::Result<Option<Vec<(Files, T)>>> {
+    let Some(value) = args.delete::<_, Option<magnus::Value>>(magnus::Symbol::new(key))? else {
+        return Ok(None);
+    };
+    let Some(value) = magnus::RHash::from_value(value) else {
+        return Err(BeaverRubyError::IncompatibleType(value, "Hash"));
+    };
+
+    context.with_current_project(|project| {
+        let mut patterns = Vec::new();
+        value.foreach(|pattern: magnus::Value, value: magnus::Value| {
+            patterns.push((Files::try_from_value(pattern, project.base_dir())?, parse(value)?));
+            Ok(magnus::r_hash::ForEach::Continue)
+        })?;
+        Ok(Some(patterns))
+    })
+}
+
+/// The arguments of C targets which are set after creating the target
+struct CTargetSourceArgs {
+    source_languages: Option<Vec<(Files, Language)>>,
+    source_flags: Option<Vec<(Files, Vec<String>)>>,
+}
+
+impl CTargetSourceArgs {
+    fn parse(args: magnus::RHash, context: &Beaver) -> crate::Result<CTargetSourceArgs> {
+        Ok(CTargetSourceArgs {
+            source_languages: c_target_parse_source_patterns(args, "source_languages", Language::try_from_value, context)?,
+            source_flags: c_target_parse_source_patterns(args, "source_flags", Vec::<String>::try_from_value, context)?,
+        })
+    }
+}
+
+/// A C library. Accepts the C target arguments, plus `abi_version:` to override the version in
+/// the soname of the dynamic library and `exports:` to limit the symbols it exports
 fn def_c_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
     let context = &CTX.get().unwrap().context();
 
-    let ctarget_desc: target::c::TargetDescriptor<LibraryArtifactType> = c_target_parse_ruby_args(args, &context)?;
-    let library = AnyLibrary::C(target::c::Library::new_desc(ctarget_desc).map_err(BeaverRubyError::from)?);
+    let source_args = CTargetSourceArgs::parse(args, &context)?;
+
+    let exports = match args.delete::<_, Option<magnus::Value>>(magnus::Symbol::new("exports"))? {
+        Some(value) => Some(c::Exports::try_from_value(value)?),
+        None => None
+    };
+
+    let abi_version = match args.delete::<_, Option<magnus::Value>>(magnus::Symbol::new("abi_version"))? {
+        Some(value) => match magnus::Integer::from_value(value) {
+            Some(value) => Some(value.to_u64()?.to_string()),
+            None => Some(String::from_string_or_sym(value)?)
+        },
+        None => None
+    };
+
+    let (ctarget_desc, dependencies): (target::c::TargetDescriptor<LibraryArtifactType>, _) = c_target_parse_ruby_args(args, true, &context)?;
+    let mut library = target::c::Library::new_desc(ctarget_desc).map_err(BeaverRubyError::from)?;
+    library.set_dependencies(dependencies);
+    if let Some(abi_version) = abi_version {
+        library.set_abi_version(abi_version);
+    }
+    if let Some(exports) = exports {
+        library.set_exports(exports);
+    }
+    if let Some(source_languages) = source_args.source_languages {
+        library.set_source_languages(source_languages).map_err(BeaverRubyError::from)?;
+    }
+    if let Some(source_flags) = source_args.source_flags {
+        library.set_source_flags(source_flags);
+    }
+    let library = AnyLibrary::C(library);
+
+    context.with_current_project_mut(|project| {
+        match project.as_mutable() {
+            Some(mutproject) => {
+                let target_id = mutproject.add_target(AnyTarget::Library(library))?;
+                let target_accessor = TargetAccessor {
+                    projid: project.id().unwrap(),
+                    id: target_id,
+                };
+                Ok(target_accessor)
+            },
+            None => Err(BeaverError::ProjectNotMutable(project.name().to_string())),
+        }
+    }).map_err(|err| BeaverRubyError::from(err).into())
+}
+
+/// A header-only library. Accepts the same arguments as `C::Library` except `sources`
+fn def_c_interface_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    let (ctarget_desc, dependencies): (target::c::TargetDescriptor<LibraryArtifactType>, _) = c_target_parse_ruby_args(args, false, &context)?;
+    let mut library = target::c::Library::new_interface_desc(ctarget_desc).map_err(BeaverRubyError::from)?;
+    library.set_dependencies(dependencies);
+    let library = AnyLibrary::C(library);
 
     context.with_current_project_mut(|project| {
         match project.as_mutable() {
@@ -140,8 +238,58 @@ fn def_c_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
 fn def_c_executable(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
     let context = &CTX.get().unwrap().context();
 
-    let ctarget_desc: target::c::TargetDescriptor<ExecutableArtifactType> = c_target_parse_ruby_args(args, &context)?;
-    let exe = AnyExecutable::C(target::c::Executable::new_desc(ctarget_desc).map_err(BeaverRubyError::from)?);
+    let source_args = CTargetSourceArgs::parse(args, &context)?;
+    let (ctarget_desc, dependencies): (target::c::TargetDescriptor<ExecutableArtifactType>, _) = c_target_parse_ruby_args(args, true, &context)?;
+    let mut exe = target::c::Executable::new_desc(ctarget_desc).map_err(BeaverRubyError::from)?;
+    exe.set_dependencies(dependencies);
+    if let Some(source_languages) = source_args.source_languages {
+        exe.set_source_languages(source_languages).map_err(BeaverRubyError::from)?;
+    }
+    if let Some(source_flags) = source_args.source_flags {
+        exe.set_source_flags(source_flags);
+    }
+    let exe = AnyExecutable::C(exe);
+
+    context.with_current_project_mut(|project| {
+        match project.as_mutable() {
+            Some(mutproject) => {
+                let target_id = mutproject.add_target(AnyTarget::Executable(exe))?;
+                let target_accessor = TargetAccessor {
+                    projid: project.id().unwrap(),
+                    id: target_id
+                };
+                Ok(target_accessor)
+            },
+            None => Err(BeaverError::ProjectNotMutable(project.name().to_string())),
+        }
+    }).map_err(|err| BeaverRubyError::from(err).into())
+}
+
+/// A C executable that is only built and run by `beaver test`. Accepts the same arguments as
+/// `C::Executable`, plus `args:` and `timeout:` (in seconds)
+fn def_c_test(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
+    let context = &CTX.get().unwrap().context();
+
+    let mut test = TestSettings::default();
+    if let Some(value) = args.delete::<_, Option<magnus::Value>>(magnus::Symbol::new("args"))? {
+        test.args = Vec::<String>::try_from_value(value)?;
+    }
+    if let Some(value) = args.delete::<_, Option<magnus::Value>>(magnus::Symbol::new("timeout"))? {
+        let timeout = <f64 as magnus::TryConvert>::try_convert(value)?;
+        test.timeout = Some(Duration::try_from_secs_f64(timeout).map_err(|err| BeaverRubyError::from(BeaverError::from(err)))?);
+    }
+
+    let source_args = CTargetSourceArgs::parse(args, &context)?;
+    let (ctarget_desc, dependencies): (target::c::TargetDescriptor<ExecutableArtifactType>, _) = c_target_parse_ruby_args(args, true, &context)?;
+    let mut exe = target::c::Executable::new_test_desc(ctarget_desc, test).map_err(BeaverRubyError::from)?;
+    exe.set_dependencies(dependencies);
+    if let Some(source_languages) = source_args.source_languages {
+        exe.set_source_languages(source_languages).map_err(BeaverRubyError::from)?;
+    }
+    if let Some(source_flags) = source_args.source_flags {
+        exe.set_source_flags(source_flags);
+    }
+    let exe = AnyExecutable::C(exe);
 
     context.with_current_project_mut(|project| {
         match project.as_mutable() {
@@ -158,12 +306,20 @@ fn def_c_executable(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error
     }).map_err(|err| BeaverRubyError::from(err).into())
 }
 
+/// Paths of files generated by a custom target, relative to the project's build directory
+fn generated_paths(value: magnus::Value, project_build_dir: &std::path::Path) -> crate::Result<Vec<PathBuf>> {
+    parse_to_string_vec(value)?.into_iter()
+        .map(|path| std::path::absolute(project_build_dir.join(path)).map_err(Into::into))
+        .collect()
+}
+
 fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Error> {
     // let context: &Arc<Beaver> = unsafe { &*RBCONTEXT.assume_init_ref() };
     let context = &CTX.get().unwrap().context();
 
     context.with_current_project_mut(|project| {
         let project_base_dir = project.base_dir();
+        let project_build_dir = project.build_dir();
 
         let mut name = Arg::<String>::new("name");
         let mut desc = Arg::<String>::new("description");
@@ -171,13 +327,15 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
         let mut version = Arg::<Version>::new("version");
         let mut license = Arg::<String>::new("license");
         let mut language = Arg::<Language>::new("language");
-        let mut sources = Arg::<Files>::new("sources");
+        let mut sources = Arg::<Vec<PathBuf>>::new("sources");
         let mut cflags = Arg::<Vec<String>>::new("cflags");
-        let mut headers = Arg::<Headers>::new("headers");
+        let mut headers = Arg::<Vec<PathBuf>>::new("headers");
         let mut linker_flags = Arg::<Vec<String>>::new("linker_flags");
         let mut artifacts = Arg::<HashMap<LibraryArtifactType, PathBuf>>::new("artifacts");
+        let mut inputs = Arg::<Files>::new("inputs");
         let mut dependencies = Arg::<Vec<Dependency>>::new("dependencies");
-        let mut build_cmd = Arg::<BuildCommand>::new("build");
+        let mut build_cmd = Arg::<magnus::Value>::new("build");
+        let mut parallel = Arg::<bool>::new("parallel");
 
         args.foreach(|key: magnus::Symbol, value: magnus::Value| {
             match key.name()?.as_ref() {
@@ -210,7 +368,7 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
                     language.set(langval)?;
                 },
                 "sources" => {
-                    let files = Files::try_from_value(value, project_base_dir)?;
+                    let files = generated_paths(value, project_build_dir)?;
                     sources.set(files)?;
                 },
                 "cflags" => {
@@ -218,8 +376,8 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
                     cflags.set(flags)?;
                 },
                 "headers" | "include" => {
-                    let value = Headers::try_from_value(value)?;
-                    headers.set(value)?;
+                    let dirs = generated_paths(value, project_build_dir)?;
+                    headers.set(dirs)?;
                 },
                 "linker_flags" | "ldflags" | "lflags" => {
                     let flags = Vec::<String>::try_from_value(value)?;
@@ -230,13 +388,19 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
                     let value = HashMap::<LibraryArtifactType, PathBuf>::try_from_value(value)?;
                     artifacts.set(value)?
                 },
+                "inputs" => {
+                    let files = Files::try_from_value(value, project_base_dir)?;
+                    inputs.set(files)?;
+                },
                 "dependencies" => {
                     let value = Vec::<Dependency>::try_from_value(value, context)?;
                     dependencies.set(value)?;
                 },
                 "build" => {
-                    let cmd = BuildCommand::try_from_value(value)?;
-                    build_cmd.set(cmd)?
+                    build_cmd.set(value)?
+                },
+                "parallel" => {
+                    parallel.set(<bool as magnus::TryConvert>::try_convert(value)?)?;
                 },
                 keyname => { return Err(BeaverRubyError::InvalidKey(keyname.to_string()).into()); }
             }
@@ -252,10 +416,13 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
             license.get_opt(),
             language.get()?,
             dependencies.get_opt().unwrap_or(Vec::new()),
+            inputs.get_opt(),
             artifacts.get()?,
+            sources.get_opt().unwrap_or(Vec::new()),
+            headers.get_opt().unwrap_or(Vec::new()),
             linker_flags.get_opt().unwrap_or(Vec::new()),
             cflags.get_opt().unwrap_or(Vec::new()),
-            build_cmd.get()?
+            build_command(build_cmd.get()?, parallel.get_opt().unwrap_or(false))?
         ));
 
         // context.with_current_project_mut(|project| {
@@ -276,7 +443,9 @@ fn def_custom_library(args: magnus::RHash) -> Result<TargetAccessor, magnus::Err
 pub fn register(ruby: &magnus::Ruby) -> crate::Result<()> {
     let c_mod = ruby.define_module("C")?;
     c_mod.define_singleton_method("Library", magnus::function!(def_c_library, 1))?;
+    c_mod.define_singleton_method("InterfaceLibrary", magnus::function!(def_c_interface_library, 1))?;
     c_mod.define_singleton_method("Executable", magnus::function!(def_c_executable, 1))?;
+    c_mod.define_singleton_method("Test", magnus::function!(def_c_test, 1))?;
 
     let custom_mod = ruby.define_module("Custom")?;
     custom_mod.define_singleton_method("Library", magnus::function!(def_custom_library, 1))?;
@@ -0,0 +1,64 @@
+use std::collections::LinkedList;
+use std::{fs, sync::Arc};
+
+use beaver::backend::Backend;
+use beaver::{Beaver, OptimizationMode};
+use target_lexicon::Triple;
+
+/// The blocks of two parallel custom targets run at the same time, each one's output going to the
+/// `beaver callback` process which requested it
+#[test]
+fn parallel_build_blocks() {
+    let tmpdir = tempdir::TempDir::new("be.jonaseveraert.beaver.tests.beaver-ruby.parallel_build").unwrap();
+    let tmpdir = tmpdir.into_path();
+
+    // Each block waits for the other one to start, so they can only finish when they run at once
+    fs::write(tmpdir.join("beaver.rb"), r##"
+build_dir "build"
+
+Project(name: "MyProject")
+
+$started = { "A" => Queue.new, "B" => Queue.new }
+
+[["A", "B"], ["B", "A"]].each do |name, other|
+  Custom::Library(
+    name: name,
+    language: :c,
+    artifacts: { staticlib: "build/lib#{name}.a" },
+    parallel: true,
+    build: proc {
+      puts "#{name} started"
+      $started[name] << true
+      raise "#{other} didn't start" if $started[other].pop(timeout: 10).nil?
+      sh "echo #{name} from sh"
+      $stderr.puts "#{name} finished"
+    }
+  )
+end
+"##).unwrap();
+
+    let beaver = Arc::new(Beaver::new(Some(false), OptimizationMode::Debug, false, false, Triple::host(), Backend::Native).unwrap());
+    let _ctx = unsafe { beaver_ruby::execute_script(tmpdir.join("beaver.rb"), LinkedList::new(), &Arc::downgrade(&beaver), "build").unwrap() };
+
+    beaver.create_build_file().unwrap();
+    let socket = beaver.communication_socket().unwrap().to_path_buf();
+
+    let requests = ["build 0:0", "build 0:1"].map(|request| {
+        let socket = socket.clone();
+        std::thread::spawn(move || {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let status = program_communicator::socket::request(&socket, request, &mut stdout, &mut stderr).unwrap();
+            (status, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+        })
+    });
+    let [a, b] = requests.map(|request| request.join().unwrap());
+
+    for (name, other, (status, stdout, stderr)) in [("A", "B", a), ("B", "A", b)] {
+        assert_eq!(status, 0, "{}", stderr);
+        assert_eq!(stdout, format!("{name} started\n{name} from sh\n"));
+        // `sh` also prints the command to `$stderr`
+        assert!(stderr.ends_with(&format!("{name} finished\n")), "{}", stderr);
+        assert!(!stderr.contains(other), "{}", stderr);
+    }
+}
@@ -62,5 +62,14 @@ pub enum BuildStep<'a> {
         name: &'a str,
         dependencies: &'a [&'a str],
         options: &'a [(&'a str, &'a str)],
+    },
+    /// A command producing multiple files. It is executed when one of the outputs is missing
+    /// or older than one of the inputs, or when the command changed.
+    Generate {
+        rule: &'a Rule,
+        outputs: &'a [&'a Path],
+        input: &'a [&'a Path],
+        dependencies: &'a [&'a str],
+        options: &'a [(&'a str, &'a str)],
     }
 }
@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{trace, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::BeaverError;
+
+/// An entry in a [JSON Compilation Database](https://clang.llvm.org/docs/JSONCompilationDatabase.html)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileCommand {
+    pub directory: PathBuf,
+    pub file: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<String>,
+    /// Some generators (e.g. CMake) only output `command` instead of `arguments`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+/// Collects the compile commands of all C targets while the build file is being created
+#[derive(Debug, Default, Clone)]
+pub struct CompilationDatabase {
+    entries: Vec<CompileCommand>,
+}
+
+impl CompilationDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, entry: CompileCommand) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[CompileCommand] {
+        &self.entries
+    }
+
+    /// Add the entries of a `compile_commands.json` generated by another build system
+    /// (e.g. CMake or Meson). Does nothing when the file doesn't exist.
+    pub fn merge_file(&mut self, file: &Path) -> crate::Result<()> {
+        if !file.exists() {
+            trace!("No compilation database at {:?}", file);
+            return Ok(());
+        }
+
+        let contents = fs::read_to_string(file)
+            .map_err(|err| BeaverError::io(err, format!("while reading {:?}", file)))?;
+        match serde_json::from_str::<Vec<CompileCommand>>(&contents) {
+            Ok(entries) => self.entries.extend(entries),
+            Err(err) => warn!("Couldn't parse compilation database {:?}: {}", file, err),
+        }
+
+        Ok(())
+    }
+
+    pub fn write(&self, file: &Path) -> crate::Result<()> {
+        let output = serde_json::to_string_pretty(&self.entries)
+            .map_err(|err| BeaverError::AnyError(err.to_string()))?;
+        fs::write(file, output)
+            .map_err(BeaverError::CompilationDatabaseWriteError)
+    }
+}
@@ -0,0 +1,50 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use crate::BeaverError;
+
+/// The program used to execute the build graph
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Write a `build.ninja` and invoke `ninja`
+    Ninja,
+    /// Execute the build graph in-process, without any external build tool
+    Native,
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Ninja => f.write_str("ninja"),
+            Backend::Native => f.write_str("native"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Backend {
+    type Error = BeaverError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "ninja" => Ok(Backend::Ninja),
+            "native" => Ok(Backend::Native),
+            _ => Err(BeaverError::TryFromStringError {
+                name: "backend".to_string(),
+                got: value.to_string(),
+                expected_values: vec!["ninja".to_string(), "native".to_string()]
+            })
+        }
+    }
+}
+
+impl Into<OsString> for Backend {
+    fn into(self) -> OsString {
+        OsString::from_str(&self.to_string()).unwrap()
+    }
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self::Ninja
+    }
+}
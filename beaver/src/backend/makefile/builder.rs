@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::backend::native::{expand, unescape};
+use crate::backend::{BackendBuilder, BackendBuilderScope, BuildStep, Rule};
+use crate::BeaverError;
+
+/// Renders the build graph as a GNU Makefile.
+///
+/// Ninja variables are expanded when the Makefile is generated, so that every recipe
+/// contains the complete command.
+#[derive(Debug)]
+pub struct MakefileBuilder<'a> {
+    buffer: String,
+    rules: HashMap<&'a str, &'a Rule>,
+    base_dir: PathBuf,
+    /// Names of all targets declared in the Makefile
+    targets: Vec<String>,
+    /// Targets that are a prerequisite of another target
+    referenced: HashSet<String>,
+    phony: Vec<String>,
+    /// Files removed by `make clean`
+    outputs: Vec<String>,
+}
+
+impl<'a> MakefileBuilder<'a> {
+    /// `base_dir` is the directory relative paths in build steps are resolved against
+    pub fn new(base_dir: &Path) -> Self {
+        MakefileBuilder {
+            buffer: String::new(),
+            rules: HashMap::new(),
+            base_dir: base_dir.to_path_buf(),
+            targets: Vec::new(),
+            referenced: HashSet::new(),
+            phony: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+impl<'a> BackendBuilder<'a> for MakefileBuilder<'a> {
+    fn add_rule(&mut self, rule: &'a Rule) {
+        // Rules are expanded into the recipe of each step
+        self.rules.insert(&rule.name, rule);
+    }
+
+    fn get_rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.get(&name).map(|val| *val)
+    }
+
+    fn has_rule(&self, name: &str) -> bool {
+        self.rules.contains_key(&name)
+    }
+
+    fn add_comment(&mut self, comment: &str) -> crate::Result<()> {
+        self.buffer.write_fmt(format_args!("# {}\n", comment))
+            .map_err(|err| BeaverError::BufferWriteError(err.to_string()))
+    }
+
+    type Scope = MakefileBuilderScope;
+
+    fn new_scope(&mut self) -> MakefileBuilderScope {
+        MakefileBuilderScope::new(self.base_dir.clone())
+    }
+
+    fn apply_scope(&mut self, scope: MakefileBuilderScope) {
+        self.buffer.push_str(&scope.buffer);
+        self.targets.extend(scope.targets);
+        self.referenced.extend(scope.referenced);
+        self.phony.extend(scope.phony);
+        self.outputs.extend(scope.outputs);
+    }
+
+    fn build(self) -> String {
+        // Like ninja, build all targets which aren't a prerequisite of another target by default
+        let default_targets = self.targets.iter()
+            .filter(|target| !self.referenced.contains(*target))
+            .map(|target| make_escape(target))
+            .collect::<Vec<String>>();
+
+        let mut out = String::from("# Generated by beaver. Do not edit.\n\n");
+        out.push_str(".SUFFIXES:\n");
+        out.push_str(".DELETE_ON_ERROR:\n");
+        out.push_str(".DEFAULT_GOAL := all\n\n");
+        out.push_str("ifeq ($(V),1)\nQ :=\nECHO := @true\nelse\nQ := @\nECHO := @echo\nendif\n\n");
+        out.push_str(&format!(".PHONY: all\nall: {}\n\n", default_targets.join(" ")));
+        out.push_str(&self.buffer);
+
+        if !self.phony.is_empty() {
+            out.push_str(&format!("\n.PHONY: {}\n", self.phony.iter().map(|name| make_escape(name)).collect::<Vec<String>>().join(" ")));
+        }
+
+        out.push_str("\n.PHONY: clean\nclean:\n");
+        for output in &self.outputs {
+            out.push_str(&format!("\trm -f {}\n", recipe_escape(&shell_quote(output))));
+        }
+
+        return out;
+    }
+}
+
+#[derive(Debug)]
+pub struct MakefileBuilderScope {
+    buffer: String,
+    base_dir: PathBuf,
+    targets: Vec<String>,
+    referenced: HashSet<String>,
+    phony: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl MakefileBuilderScope {
+    fn new(base_dir: PathBuf) -> Self {
+        MakefileBuilderScope {
+            buffer: String::new(),
+            base_dir,
+            targets: Vec::new(),
+            referenced: HashSet::new(),
+            phony: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    fn write_fmt(&mut self, args: std::fmt::Arguments<'_>) -> crate::Result<()> {
+        self.buffer.write_fmt(args)
+            .map_err(|err| BeaverError::BufferWriteError(err.to_string()))
+    }
+
+    fn path_node(&self, path: &Path) -> String {
+        self.base_dir.join(path).to_str().expect("Path is not UTF-8 encoded").to_string()
+    }
+
+    /// Write `target: prerequisites | order_only`
+    fn write_target(&mut self, target: &str, prerequisites: &[String], order_only: &[String]) -> crate::Result<()> {
+        self.targets.push(target.to_string());
+        self.referenced.extend(prerequisites.iter().cloned());
+        self.referenced.extend(order_only.iter().cloned());
+
+        let mut line = format!("{}:", make_escape(target));
+        for prerequisite in prerequisites {
+            line.push(' ');
+            line.push_str(&make_escape(prerequisite));
+        }
+        if !order_only.is_empty() {
+            line.push_str(" |");
+            for prerequisite in order_only {
+                line.push(' ');
+                line.push_str(&make_escape(prerequisite));
+            }
+        }
+        self.write_fmt(format_args!("{}\n", line))
+    }
+
+    /// Write the recipe for `rule`, with the variables of the step expanded
+    fn write_recipe(&mut self, rule: &Rule, inputs: &[String], outputs: &[String], options: &[(&str, &str)]) -> crate::Result<()> {
+        let evaluate = |name: &str| evaluate(rule, name, inputs, outputs, options, true);
+
+        if let Some(description) = evaluate("description") {
+            self.write_fmt(format_args!("\t$(ECHO) {}\n", recipe_escape(&shell_quote(&description))))?;
+        }
+        for output in outputs {
+            if let Some(parent) = Path::new(output).parent() {
+                self.write_fmt(format_args!("\t@mkdir -p {}\n", recipe_escape(&shell_quote(parent.to_str().unwrap()))))?;
+            }
+        }
+        if let Some(command) = evaluate("command") {
+            self.write_fmt(format_args!("\t$(Q){}\n", recipe_escape(&command)))?;
+        }
+        Ok(())
+    }
+}
+
+impl BackendBuilderScope for MakefileBuilderScope {
+    fn add_step(&mut self, step: &BuildStep) -> crate::Result<()> {
+        match step {
+            BuildStep::Phony { name, args, dependencies } => {
+                let name = unescape(name);
+                let args = args.iter().map(|arg| unescape(arg)).collect::<Vec<String>>();
+                let dependencies = dependencies.iter().map(|dep| unescape(dep)).collect::<Vec<String>>();
+                self.write_target(&name, &args, &dependencies)?;
+                // A phony step named after a file is an alias for that file
+                if !Path::new(&name).is_absolute() {
+                    self.phony.push(name);
+                }
+            },
+            BuildStep::Build { rule, output, input, dependencies, options } => {
+                let output = self.path_node(output);
+                let inputs = input.iter().map(|path| self.path_node(path)).collect::<Vec<String>>();
+                // Files are regular prerequisites, so that changes to them cause a rebuild.
+                // Other targets are phony, these only have to be built first.
+                let (mut prerequisites, order_only): (Vec<String>, Vec<String>) = dependencies.iter()
+                    .map(|dep| unescape(dep))
+                    .partition(|dep| Path::new(dep).is_absolute());
+                prerequisites.splice(0..0, inputs.iter().cloned());
+
+                self.write_target(&output, &prerequisites, &order_only)?;
+                self.write_recipe(rule, &inputs, &[output.clone()], options)?;
+                self.outputs.push(output.clone());
+                if let Some(depfile) = evaluate(rule, "depfile", &inputs, &[output], options, false) {
+                    self.write_fmt(format_args!("-include {}\n", make_escape(&depfile)))?;
+                    self.outputs.push(depfile);
+                }
+            },
+            BuildStep::Generate { rule, outputs, input, dependencies, options } => {
+                let outputs = outputs.iter().map(|path| self.path_node(path)).collect::<Vec<String>>();
+                let inputs = input.iter().map(|path| self.path_node(path)).collect::<Vec<String>>();
+                let (mut prerequisites, order_only): (Vec<String>, Vec<String>) = dependencies.iter()
+                    .map(|dep| unescape(dep))
+                    .partition(|dep| Path::new(dep).is_absolute());
+                prerequisites.splice(0..0, inputs.iter().cloned());
+
+                // The recipe is attached to the first output, the other outputs are created with it
+                self.write_target(&outputs[0], &prerequisites, &order_only)?;
+                self.write_recipe(rule, &inputs, &outputs, options)?;
+                for output in outputs[1..].iter() {
+                    self.write_target(output, &[outputs[0].clone()], &[])?;
+                }
+                self.outputs.extend(outputs);
+            },
+            BuildStep::Cmd { rule, name, dependencies, options } => {
+                let name = unescape(name);
+                let dependencies = dependencies.iter().map(|dep| unescape(dep)).collect::<Vec<String>>();
+                self.write_target(&name, &dependencies, &[])?;
+                self.write_recipe(rule, &[], &[], options)?;
+                self.phony.push(name);
+            },
+        }
+
+        return Ok(());
+    }
+
+    fn add_comment(&mut self, comment: &str) -> crate::Result<()> {
+        self.write_fmt(format_args!("# {}\n", comment))
+    }
+
+    fn format_path(&self, path: PathBuf) -> PathBuf {
+        self.base_dir.join(path)
+    }
+}
+
+/// Evaluate the rule variable `name` for a step, the same way ninja would. When `shell` is set,
+/// `$in` and `$out` are quoted for use in a command.
+fn evaluate(rule: &Rule, name: &str, inputs: &[String], outputs: &[String], options: &[(&str, &str)], shell: bool) -> Option<String> {
+    fn var(rule: &Rule, name: &str, inputs: &[String], outputs: &[String], options: &[(&str, &str)], shell: bool, depth: usize) -> Option<String> {
+        if let Some((_, value)) = options.iter().find(|(key, _)| *key == name) {
+            return Some(unescape(value));
+        }
+
+        let quote = |nodes: &[String]| nodes.iter()
+            .map(|node| if shell { shell_quote(node) } else { node.clone() })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        match name {
+            "in" => return Some(quote(inputs)),
+            "out" => return Some(quote(outputs)),
+            _ => {}
+        }
+
+        if depth > 8 { return None; }
+
+        rule.options.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| expand(value, &|name| var(rule, name, inputs, outputs, options, shell, depth + 1)))
+    }
+
+    rule.options.iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| expand(value, &|name| var(rule, name, inputs, outputs, options, shell, 0)))
+        .filter(|value| !value.is_empty())
+}
+
+fn shell_quote(str: &str) -> String {
+    shlex::try_quote(str).map(|str| str.to_string()).unwrap_or(str.to_string())
+}
+
+/// Escape a target or prerequisite name
+pub(crate) fn make_escape(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            ' ' | ':' | '#' | '\\' | '%' => { out.push('\\'); out.push(c); },
+            '$' => out.push_str("$$"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape a line in a recipe
+fn recipe_escape(line: &str) -> String {
+    line.replace('$', "$$").replace('\n', " \\\n\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::make_escape;
+
+    #[test]
+    fn escape() {
+        assert_eq!(make_escape("Project:Target"), "Project\\:Target");
+        assert_eq!(make_escape("/a b/$c"), "/a\\ b/$$c");
+    }
+}
@@ -0,0 +1,3 @@
+use utils::moduse;
+
+moduse!(builder);
@@ -1,5 +1,9 @@
 use utils::moduse;
 
 moduse!(builder);
+moduse!(compile_commands);
+moduse!(kind);
 pub mod rules;
 pub mod ninja;
+pub mod native;
+pub mod makefile;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{BackendBuilder, BackendBuilderScope, BuildStep, Rule};
+
+use super::unescape;
+
+/// A rule as stored in the serialized build graph
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GraphRule {
+    pub options: HashMap<String, String>,
+    /// Pool name and depth
+    pub pool: Option<(String, u32)>,
+}
+
+/// An edge in the build graph. Nodes are identified by name: files by their absolute path,
+/// phony and command steps by their unescaped name.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GraphEdge {
+    /// `None` for phony edges
+    pub rule: Option<String>,
+    pub outputs: Vec<String>,
+    pub inputs: Vec<String>,
+    /// Dependencies which are not passed as `$in`
+    pub implicit: Vec<String>,
+    pub options: HashMap<String, String>,
+    /// Outputs don't refer to files, so this edge will be executed every time it is requested
+    pub always: bool,
+}
+
+impl GraphEdge {
+    pub fn is_phony(&self) -> bool {
+        self.rule.is_none()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BuildGraph {
+    pub rules: HashMap<String, GraphRule>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Builds a graph of rules and build steps which is executed by the [`NativeRunner`](super::NativeRunner)
+#[derive(Debug)]
+pub struct NativeBuilder<'a> {
+    rules: HashMap<&'a str, &'a Rule>,
+    edges: Vec<GraphEdge>,
+    base_dir: PathBuf,
+}
+
+impl<'a> NativeBuilder<'a> {
+    /// `base_dir` is the directory relative paths in build steps are resolved against
+    pub fn new(base_dir: &Path) -> Self {
+        NativeBuilder {
+            rules: HashMap::new(),
+            edges: Vec::new(),
+            base_dir: base_dir.to_path_buf()
+        }
+    }
+}
+
+impl<'a> BackendBuilder<'a> for NativeBuilder<'a> {
+    fn add_rule(&mut self, rule: &'a Rule) {
+        self.rules.insert(&rule.name, rule);
+    }
+
+    fn get_rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.get(&name).map(|val| *val)
+    }
+
+    fn has_rule(&self, name: &str) -> bool {
+        self.rules.contains_key(&name)
+    }
+
+    fn add_comment(&mut self, _comment: &str) -> crate::Result<()> {
+        Ok(())
+    }
+
+    type Scope = NativeBuilderScope;
+
+    fn new_scope(&mut self) -> NativeBuilderScope {
+        NativeBuilderScope::new(self.base_dir.clone())
+    }
+
+    fn apply_scope(&mut self, scope: NativeBuilderScope) {
+        self.edges.extend(scope.edges);
+    }
+
+    /// Serializes the build graph
+    fn build(self) -> String {
+        let graph = BuildGraph {
+            rules: self.rules.iter().map(|(name, rule)| {
+                (name.to_string(), GraphRule {
+                    options: rule.options.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+                    pool: rule.pool.map(|pool| (pool.name.to_string(), pool.depth)),
+                })
+            }).collect(),
+            edges: self.edges,
+        };
+
+        serde_json::to_string(&graph).expect("Build graph should always be serializable")
+    }
+}
+
+#[derive(Debug)]
+pub struct NativeBuilderScope {
+    edges: Vec<GraphEdge>,
+    base_dir: PathBuf,
+}
+
+impl NativeBuilderScope {
+    fn new(base_dir: PathBuf) -> Self {
+        NativeBuilderScope { edges: Vec::new(), base_dir }
+    }
+
+    fn path_node(&self, path: &Path) -> String {
+        self.base_dir.join(path).to_str().expect("Path is not UTF-8 encoded").to_string()
+    }
+
+    fn options(options: &[(&str, &str)]) -> HashMap<String, String> {
+        options.iter().map(|(k, v)| (k.to_string(), unescape(v))).collect()
+    }
+}
+
+impl BackendBuilderScope for NativeBuilderScope {
+    fn add_step(&mut self, step: &BuildStep) -> crate::Result<()> {
+        let edge = match step {
+            BuildStep::Phony { name, args, dependencies } => GraphEdge {
+                rule: None,
+                outputs: vec![unescape(name)],
+                inputs: args.iter().map(|arg| unescape(arg)).collect(),
+                implicit: dependencies.iter().map(|dep| unescape(dep)).collect(),
+                options: HashMap::new(),
+                always: false,
+            },
+            BuildStep::Build { rule, output, input, dependencies, options } => GraphEdge {
+                rule: Some(rule.name.to_string()),
+                outputs: vec![self.path_node(output)],
+                inputs: input.iter().map(|path| self.path_node(path)).collect(),
+                implicit: dependencies.iter().map(|dep| unescape(dep)).collect(),
+                options: Self::options(options),
+                always: false,
+            },
+            BuildStep::Cmd { rule, name, dependencies, options } => GraphEdge {
+                rule: Some(rule.name.to_string()),
+                outputs: vec![unescape(name)],
+                inputs: Vec::new(),
+                implicit: dependencies.iter().map(|dep| unescape(dep)).collect(),
+                options: Self::options(options),
+                always: true,
+            },
+            BuildStep::Generate { rule, outputs, input, dependencies, options } => GraphEdge {
+                rule: Some(rule.name.to_string()),
+                outputs: outputs.iter().map(|path| self.path_node(path)).collect(),
+                inputs: input.iter().map(|path| self.path_node(path)).collect(),
+                implicit: dependencies.iter().map(|dep| unescape(dep)).collect(),
+                options: Self::options(options),
+                always: false,
+            },
+        };
+
+        self.edges.push(edge);
+
+        return Ok(());
+    }
+
+    fn add_comment(&mut self, _comment: &str) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn format_path(&self, path: PathBuf) -> PathBuf {
+        self.base_dir.join(path)
+    }
+}
@@ -0,0 +1,57 @@
+/// Parse the dependencies from a Makefile-style depfile as generated by `-MD -MF`.
+///
+/// Only the prerequisites are returned, targets are ignored.
+pub(crate) fn parse_depfile(contents: &str) -> Vec<String> {
+    let contents = contents.replace("\\\r\n", " ").replace("\\\n", " ");
+
+    let mut deps: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        let mut in_prerequisites = false;
+        let mut token = String::new();
+        let mut chars = line.chars().peekable();
+
+        let mut push_token = |token: &mut String, in_prerequisites: &mut bool| {
+            if token.is_empty() { return; }
+            if *in_prerequisites {
+                if !deps.contains(token) {
+                    deps.push(std::mem::take(token));
+                } else {
+                    token.clear();
+                }
+            } else if token.ends_with(':') {
+                *in_prerequisites = true;
+                token.clear();
+            } else {
+                token.clear();
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if matches!(chars.peek(), Some(' ') | Some('#')) => token.push(chars.next().unwrap()),
+                '$' if chars.peek() == Some(&'$') => token.push(chars.next().unwrap()),
+                ':' if !in_prerequisites && matches!(chars.peek(), None | Some(' ') | Some('\t')) => {
+                    token.push(':');
+                    push_token(&mut token, &mut in_prerequisites);
+                    in_prerequisites = true;
+                },
+                ' ' | '\t' => push_token(&mut token, &mut in_prerequisites),
+                c => token.push(c),
+            }
+        }
+        push_token(&mut token, &mut in_prerequisites);
+    }
+
+    deps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_depfile;
+
+    #[test]
+    fn parse() {
+        let depfile = "build/main.c.o: src/main.c include/a\\ b.h \\\n  include/c.h\ninclude/c.h:\n";
+        assert_eq!(parse_depfile(depfile), vec!["src/main.c", "include/a b.h", "include/c.h"]);
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::BeaverError;
+
+/// Information about an output from the last time it was built
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct LogEntry {
+    /// Modification time (in nanoseconds since the unix epoch) to compare inputs against
+    pub mtime: u128,
+    /// Hash of the command used to build the output
+    pub hash: u64,
+    /// Dependencies discovered through the depfile
+    pub deps: Vec<String>,
+}
+
+/// The on-disk log of built outputs, used for incremental builds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BuildLog {
+    pub entries: HashMap<String, LogEntry>,
+}
+
+impl BuildLog {
+    pub fn file(build_dir: &Path) -> PathBuf {
+        build_dir.join(".beaver_log")
+    }
+
+    /// Reads the build log, starting from an empty log if it doesn't exist or can't be read
+    pub fn load(build_dir: &Path) -> Self {
+        let file = Self::file(build_dir);
+        let Ok(contents) = fs::read_to_string(&file) else {
+            return BuildLog::default();
+        };
+        match serde_json::from_str(&contents) {
+            Ok(log) => log,
+            Err(err) => {
+                warn!("Build log {:?} is corrupt and will be recreated: {}", file, err);
+                BuildLog::default()
+            }
+        }
+    }
+
+    pub fn write(&self, build_dir: &Path) -> crate::Result<()> {
+        let file = Self::file(build_dir);
+        let output = serde_json::to_string(self)
+            .map_err(|err| BeaverError::AnyError(err.to_string()))?;
+        fs::write(&file, output)
+            .map_err(|err| BeaverError::io(err, format!("while writing build log {:?}", file)))
+    }
+}
+
+pub(crate) fn mtime_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+}
@@ -0,0 +1,67 @@
+//! An in-process build executor, used when beaver is run with `--backend native`.
+//!
+//! The build graph is created by the [`NativeBuilder`] and serialized to the build file. The
+//! [`NativeRunner`] then executes the requested part of the graph using the same semantics
+//! as ninja: outputs are rebuilt when they are missing, when their command changed or when
+//! one of their inputs or discovered dependencies (depfiles) is newer.
+
+use utils::moduse;
+
+moduse!(builder);
+moduse!(runner);
+mod depfile;
+mod log;
+
+/// Remove ninja escape sequences from a name or value
+pub(crate) fn unescape(str: &str) -> String {
+    let mut out = String::with_capacity(str.len());
+    let mut chars = str.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some(&(':' | ' ' | '$')) => out.push(chars.next().unwrap()),
+            Some('\n') => { _ = chars.next(); },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn is_var_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Expand variables (`$name` or `${name}`) in `template`. Variables which are not found
+/// expand to an empty string, like in ninja.
+pub(crate) fn expand(template: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some(&(':' | ' ' | '$')) => out.push(chars.next().unwrap()),
+            Some('\n') => { _ = chars.next(); },
+            Some('{') => {
+                _ = chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                out.push_str(&lookup(&name).unwrap_or_default());
+            },
+            Some(c) if is_var_char(*c) => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek() {
+                    if !is_var_char(*c) { break; }
+                    name.push(chars.next().unwrap());
+                }
+                out.push_str(&lookup(&name).unwrap_or_default());
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
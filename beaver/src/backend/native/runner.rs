@@ -0,0 +1,604 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::mpsc;
+use std::time::SystemTime;
+
+use log::trace;
+
+use crate::{tools, BeaverError};
+
+use super::builder::{BuildGraph, GraphEdge, GraphRule};
+use super::depfile::parse_depfile;
+use super::expand;
+use super::log::{mtime_nanos, BuildLog, LogEntry};
+
+/// Executes the build graph written by the [`NativeBuilder`](super::NativeBuilder)
+pub struct NativeRunner<'a> {
+    build_file: &'a Path,
+    verbose: bool,
+    debug: bool,
+    /// Environment variables for the executed commands
+    env: Vec<(&'a str, &'a OsStr)>,
+}
+
+impl<'a> NativeRunner<'a> {
+    pub fn new(build_file: &'a Path, verbose: bool, debug: bool) -> Self {
+        NativeRunner {
+            build_file,
+            verbose,
+            debug,
+            env: Vec::new(),
+        }
+    }
+
+    pub fn env(mut self, key: &'a str, value: &'a (impl AsRef<OsStr> + ?Sized)) -> Self {
+        self.env.push((key, value.as_ref()));
+        return self;
+    }
+
+    fn load_graph(&self) -> crate::Result<BuildGraph> {
+        let contents = fs::read_to_string(self.build_file)
+            .map_err(|err| BeaverError::io(err, format!("while reading build file {:?}", self.build_file)))?;
+        serde_json::from_str(&contents)
+            .map_err(|err| BeaverError::BuildGraphParseError(self.build_file.to_path_buf(), err.to_string()))
+    }
+
+    /// Build `targets` and all of their dependencies. Commands are executed in `build_dir`
+    pub fn build<S: AsRef<str>>(&self, targets: &[S], base_dir: &Path, build_dir: &Path) -> crate::Result<()> {
+        let graph = Graph::new(self.load_graph()?, build_dir);
+        let mut log = BuildLog::load(build_dir);
+
+        let result = Scheduler {
+            graph: &graph,
+            verbose: self.verbose,
+            debug: self.debug,
+            env: &self.env,
+        }.run(targets, &mut log);
+
+        // Also store the results of a failed build, so finished outputs aren't rebuilt
+        log.write(build_dir)?;
+        result?;
+
+        return self.cleandead(base_dir, build_dir);
+    }
+
+    /// Remove outputs from previous builds which are no longer part of the build graph
+    pub fn cleandead(&self, base_dir: &Path, build_dir: &Path) -> crate::Result<()> {
+        _ = base_dir;
+
+        let graph = self.load_graph()?;
+        let outputs: HashSet<&str> = graph.edges.iter()
+            .flat_map(|edge| edge.outputs.iter().map(|out| out.as_str()))
+            .collect();
+
+        let mut log = BuildLog::load(build_dir);
+        let dead: Vec<String> = log.entries.keys()
+            .filter(|output| !outputs.contains(output.as_str()))
+            .cloned()
+            .collect();
+
+        for output in dead.iter() {
+            trace!("Removing dead output {}", output);
+            match fs::remove_file(output) {
+                Ok(()) => {},
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+                Err(err) => return Err(BeaverError::io(err, format!("while removing {}", output))),
+            }
+            log.entries.remove(output);
+        }
+
+        if self.verbose {
+            eprintln!("Cleaning... {} files.", dead.len());
+        }
+
+        if !dead.is_empty() {
+            log.write(build_dir)?;
+        }
+
+        return Ok(());
+    }
+}
+
+struct Graph {
+    inner: BuildGraph,
+    /// The edge producing each node
+    producers: HashMap<String, usize>,
+    build_dir: PathBuf,
+}
+
+impl Graph {
+    fn new(graph: BuildGraph, build_dir: &Path) -> Self {
+        let mut producers = HashMap::new();
+        for (i, edge) in graph.edges.iter().enumerate() {
+            for output in edge.outputs.iter() {
+                producers.insert(output.clone(), i);
+            }
+        }
+
+        Graph { inner: graph, producers, build_dir: build_dir.to_path_buf() }
+    }
+
+    fn edge(&self, idx: usize) -> &GraphEdge {
+        &self.inner.edges[idx]
+    }
+
+    fn rule(&self, edge: &GraphEdge) -> Option<&GraphRule> {
+        edge.rule.as_ref().and_then(|rule| self.inner.rules.get(rule))
+    }
+
+    fn node_path(&self, node: &str) -> PathBuf {
+        self.build_dir.join(node)
+    }
+
+    /// All nodes an edge depends on
+    fn edge_dependencies<'b>(&'b self, edge: &'b GraphEdge) -> impl Iterator<Item = &'b String> + 'b {
+        edge.inputs.iter().chain(edge.implicit.iter())
+    }
+
+    /// Modification time of a node. Phony nodes which are not files take the most recent time
+    /// of their inputs.
+    fn node_mtime(&self, node: &str, depth: usize) -> Option<SystemTime> {
+        if let Ok(metadata) = fs::metadata(self.node_path(node)) {
+            return metadata.modified().ok();
+        }
+
+        let edge = self.edge(*self.producers.get(node)?);
+        if !edge.is_phony() || depth > 32 {
+            return None;
+        }
+
+        self.edge_dependencies(edge)
+            .filter_map(|dep| self.node_mtime(dep, depth + 1))
+            .max()
+    }
+
+    fn var(&self, edge: &GraphEdge, name: &str, shell: bool, depth: usize) -> Option<String> {
+        if let Some(value) = edge.options.get(name) {
+            return Some(value.clone());
+        }
+
+        let quote = |nodes: &[String]| nodes.iter()
+            .map(|node| if shell {
+                shlex::try_quote(node).map(|str| str.to_string()).unwrap_or(node.clone())
+            } else {
+                node.clone()
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        match name {
+            "in" => return Some(quote(&edge.inputs)),
+            "out" => return Some(quote(&edge.outputs)),
+            _ => {}
+        }
+
+        if depth > 8 { return None; }
+
+        self.rule(edge)
+            .and_then(|rule| rule.options.get(name))
+            .map(|value| expand(value, &|name| self.var(edge, name, shell, depth + 1)))
+    }
+
+    fn evaluate(&self, edge: &GraphEdge, name: &str, shell: bool) -> Option<String> {
+        self.rule(edge)
+            .and_then(|rule| rule.options.get(name))
+            .map(|value| expand(value, &|name| self.var(edge, name, shell, 0)))
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// A command to be executed on the thread pool
+struct Job {
+    idx: usize,
+    command: String,
+    outputs: Vec<PathBuf>,
+    create_dirs: bool,
+    build_dir: PathBuf,
+    env: Vec<(String, OsString)>,
+}
+
+struct JobResult {
+    idx: usize,
+    status: std::io::Result<ExitStatus>,
+    output: Vec<u8>,
+}
+
+impl Job {
+    fn run(self) -> JobResult {
+        if self.create_dirs {
+            for output in self.outputs.iter() {
+                if let Some(Err(err)) = output.parent().map(fs::create_dir_all) {
+                    return JobResult { idx: self.idx, status: Err(err), output: Vec::new() };
+                }
+            }
+        }
+
+        let output = Command::new(&*tools::sh)
+            .args(["-c", &self.command])
+            .envs(self.env)
+            .current_dir(&self.build_dir)
+            .output();
+
+        match output {
+            Ok(output) => {
+                let mut out = output.stdout;
+                out.extend(output.stderr);
+                JobResult { idx: self.idx, status: Ok(output.status), output: out }
+            },
+            Err(err) => JobResult { idx: self.idx, status: Err(err), output: Vec::new() },
+        }
+    }
+}
+
+/// Bookkeeping for a running edge
+struct Running {
+    command: String,
+    description: String,
+    hash: u64,
+    newest_input: u128,
+    mtimes_before: Vec<Option<SystemTime>>,
+}
+
+struct Scheduler<'g> {
+    graph: &'g Graph,
+    verbose: bool,
+    debug: bool,
+    env: &'g [(&'g str, &'g OsStr)],
+}
+
+impl<'g> Scheduler<'g> {
+    /// Collect all edges needed to build `targets`
+    fn needed_edges<S: AsRef<str>>(&self, targets: &[S]) -> crate::Result<Vec<usize>> {
+        // 0 = unvisited, 1 = visiting, 2 = done
+        let mut state: HashMap<usize, u8> = HashMap::new();
+        let mut order = Vec::new();
+
+        for target in targets {
+            let target = target.as_ref();
+            let Some(&idx) = self.graph.producers.get(target) else {
+                return Err(BeaverError::UnknownBuildTarget(target.to_string()));
+            };
+
+            let mut stack: Vec<(usize, bool)> = vec![(idx, false)];
+            while let Some((idx, expanded)) = stack.pop() {
+                if expanded {
+                    state.insert(idx, 2);
+                    order.push(idx);
+                    continue;
+                }
+                match state.get(&idx) {
+                    Some(2) => continue,
+                    Some(1) => continue,
+                    _ => {}
+                }
+                state.insert(idx, 1);
+                stack.push((idx, true));
+
+                let edge = self.graph.edge(idx);
+                for dep in self.graph.edge_dependencies(edge) {
+                    let Some(&dep_idx) = self.graph.producers.get(dep) else { continue };
+                    match state.get(&dep_idx) {
+                        Some(1) => return Err(BeaverError::BuildGraphCycle(dep.clone())),
+                        Some(2) => continue,
+                        _ => stack.push((dep_idx, false)),
+                    }
+                }
+            }
+        }
+
+        return Ok(order);
+    }
+
+    fn command_hash(command: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        command.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the reason an edge needs to be executed, or `None` if it is up to date
+    fn dirty_reason(&self, edge: &GraphEdge, hash: u64, log: &BuildLog, changed: &HashSet<String>) -> Option<String> {
+        if edge.always {
+            return Some(format!("{} is a command", edge.outputs.join(" ")));
+        }
+
+        for output in edge.outputs.iter() {
+            if !self.graph.node_path(output).exists() {
+                return Some(format!("output {} doesn't exist", output));
+            }
+        }
+
+        let Some(entry) = log.entries.get(&edge.outputs[0]) else {
+            return Some(format!("{} is not in the build log", edge.outputs[0]));
+        };
+
+        if entry.hash != hash {
+            return Some(format!("command line changed for {}", edge.outputs[0]));
+        }
+
+        for dep in self.graph.edge_dependencies(edge).chain(entry.deps.iter()) {
+            if changed.contains(dep) {
+                return Some(format!("{} was rebuilt", dep));
+            }
+            match self.graph.node_mtime(dep, 0) {
+                None => if !self.graph.producers.contains_key(dep) {
+                    return Some(format!("{} is missing", dep));
+                },
+                Some(mtime) => if mtime_nanos(mtime) > entry.mtime {
+                    return Some(format!("{} is newer than {}", dep, edge.outputs[0]));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn run<S: AsRef<str>>(&self, targets: &[S], log: &mut BuildLog) -> crate::Result<()> {
+        let needed = self.needed_edges(targets)?;
+        let needed_set: HashSet<usize> = needed.iter().cloned().collect();
+
+        let mut remaining: HashMap<usize, usize> = HashMap::new();
+        let mut dependents: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &idx in needed.iter() {
+            let edge = self.graph.edge(idx);
+            let producers: HashSet<usize> = self.graph.edge_dependencies(edge)
+                .filter_map(|dep| self.graph.producers.get(dep).cloned())
+                .filter(|dep_idx| needed_set.contains(dep_idx))
+                .collect();
+            remaining.insert(idx, producers.len());
+            for producer in producers {
+                dependents.entry(producer).or_default().push(idx);
+            }
+        }
+
+        let total = needed.iter().filter(|idx| !self.graph.edge(**idx).is_phony()).count();
+        let mut finished = 0;
+        let mut executed = 0;
+
+        let mut ready: VecDeque<usize> = needed.iter().cloned().filter(|idx| remaining[idx] == 0).collect();
+        let mut pool_waiting: HashMap<String, VecDeque<usize>> = HashMap::new();
+        let mut pool_running: HashMap<String, u32> = HashMap::new();
+        let mut running: HashMap<usize, Running> = HashMap::new();
+        let mut changed: HashSet<String> = HashSet::new();
+        let mut error: Option<BeaverError> = None;
+
+        let threads = std::thread::available_parallelism().map(|n| n.get() + 2).unwrap_or(4);
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|err| BeaverError::AnyError(err.to_string()))?;
+        let (tx, rx) = mpsc::channel::<JobResult>();
+
+        loop {
+            while error.is_none() {
+                let Some(idx) = ready.pop_front() else { break };
+                let edge = self.graph.edge(idx);
+
+                if edge.is_phony() {
+                    if self.graph.edge_dependencies(edge).any(|dep| changed.contains(dep)) {
+                        changed.extend(edge.outputs.iter().cloned());
+                    }
+                    Self::complete(idx, &dependents, &mut remaining, &mut ready);
+                    continue;
+                }
+
+                let command = self.graph.evaluate(edge, "command", true).unwrap_or_default();
+                let hash = Self::command_hash(&command);
+                let Some(reason) = self.dirty_reason(edge, hash, log, &changed) else {
+                    finished += 1;
+                    Self::complete(idx, &dependents, &mut remaining, &mut ready);
+                    continue;
+                };
+
+                if self.debug {
+                    eprintln!("beaver explain: {}", reason);
+                }
+
+                if let Some((pool, depth)) = self.graph.rule(edge).and_then(|rule| rule.pool.as_ref()) {
+                    let count = pool_running.entry(pool.clone()).or_insert(0);
+                    if *count >= *depth {
+                        pool_waiting.entry(pool.clone()).or_default().push_back(idx);
+                        continue;
+                    }
+                    *count += 1;
+                }
+
+                let description = self.graph.evaluate(edge, "description", false).unwrap_or(command.clone());
+                let newest_input = self.graph.edge_dependencies(edge)
+                    .filter_map(|dep| self.graph.node_mtime(dep, 0))
+                    .map(mtime_nanos)
+                    .max()
+                    .unwrap_or(0);
+                let outputs: Vec<PathBuf> = edge.outputs.iter().map(|out| self.graph.node_path(out)).collect();
+                let mtimes_before = outputs.iter()
+                    .map(|out| fs::metadata(out).and_then(|m| m.modified()).ok())
+                    .collect();
+
+                let job = Job {
+                    idx,
+                    command: command.clone(),
+                    outputs,
+                    create_dirs: !edge.always,
+                    build_dir: self.graph.build_dir.clone(),
+                    env: self.env.iter().map(|(key, value)| (key.to_string(), value.to_os_string())).collect(),
+                };
+                running.insert(idx, Running { command, description, hash, newest_input, mtimes_before });
+
+                let tx = tx.clone();
+                thread_pool.spawn(move || {
+                    _ = tx.send(job.run());
+                });
+            }
+
+            if running.is_empty() {
+                break;
+            }
+
+            let result = rx.recv().expect("Job channel closed unexpectedly");
+            let state = running.remove(&result.idx).unwrap();
+            let edge = self.graph.edge(result.idx);
+            finished += 1;
+            executed += 1;
+
+            if let Some((pool, _)) = self.graph.rule(edge).and_then(|rule| rule.pool.as_ref()) {
+                *pool_running.get_mut(pool).unwrap() -= 1;
+                if let Some(idx) = pool_waiting.get_mut(pool).and_then(|waiting| waiting.pop_front()) {
+                    ready.push_front(idx);
+                }
+            }
+
+            if self.verbose {
+                eprintln!("[{}/{}] {}", finished, total, state.command);
+            } else {
+                eprintln!("[{}/{}] {}", finished, total, state.description);
+            }
+
+            let status = match result.status {
+                Ok(status) => status,
+                Err(err) => {
+                    eprintln!("{} {}", console::style("FAILED:").red(), edge.outputs.join(" "));
+                    error.get_or_insert(BeaverError::io(err, format!("while running `{}`", state.command)));
+                    continue;
+                }
+            };
+
+            if !status.success() {
+                eprintln!("{} {}\n{}", console::style("FAILED:").red(), edge.outputs.join(" "), state.command);
+            }
+            if !result.output.is_empty() {
+                _ = std::io::stderr().write_all(&result.output);
+            }
+            if !status.success() {
+                error.get_or_insert(BeaverError::NonZeroExitStatus(status));
+                continue;
+            }
+
+            self.finish_edge(edge, &state, log, &mut changed)?;
+            Self::complete(result.idx, &dependents, &mut remaining, &mut ready);
+        }
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        if executed == 0 {
+            eprintln!("beaver: no work to do.");
+        }
+
+        return Ok(());
+    }
+
+    /// Mark an edge as finished, making its dependents ready when all of their dependencies are done
+    fn complete(idx: usize, dependents: &HashMap<usize, Vec<usize>>, remaining: &mut HashMap<usize, usize>, ready: &mut VecDeque<usize>) {
+        let Some(dependents) = dependents.get(&idx) else { return };
+        for dependent in dependents {
+            let count = remaining.get_mut(dependent).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push_back(*dependent);
+            }
+        }
+    }
+
+    /// Read the depfile, update the build log and determine if the outputs changed
+    fn finish_edge(&self, edge: &GraphEdge, state: &Running, log: &mut BuildLog, changed: &mut HashSet<String>) -> crate::Result<()> {
+        if edge.always {
+            changed.extend(edge.outputs.iter().cloned());
+            return Ok(());
+        }
+
+        let mut deps = Vec::new();
+        if let Some(depfile) = self.graph.evaluate(edge, "depfile", false) {
+            let depfile = self.graph.node_path(&depfile);
+            match fs::read_to_string(&depfile) {
+                Ok(contents) => {
+                    deps = parse_depfile(&contents).into_iter()
+                        .map(|dep| self.graph.node_path(&dep).to_string_lossy().to_string())
+                        .collect();
+                    if self.graph.evaluate(edge, "deps", false).is_some() {
+                        _ = fs::remove_file(&depfile);
+                    }
+                },
+                Err(err) => trace!("Couldn't read depfile {:?}: {}", depfile, err),
+            }
+        }
+
+        let mtimes_after: Vec<Option<SystemTime>> = edge.outputs.iter()
+            .map(|out| fs::metadata(self.graph.node_path(out)).and_then(|m| m.modified()).ok())
+            .collect();
+
+        let restat = self.graph.evaluate(edge, "restat", false).is_some();
+        if !restat || mtimes_after != state.mtimes_before {
+            changed.extend(edge.outputs.iter().cloned());
+        }
+
+        for (output, mtime) in edge.outputs.iter().zip(mtimes_after) {
+            let mtime = mtime.map(mtime_nanos).unwrap_or(0).max(state.newest_input);
+            log.entries.insert(output.clone(), LogEntry {
+                mtime,
+                hash: state.hash,
+                deps: deps.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    use crate::backend::native::NativeBuilder;
+    use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep};
+
+    use super::NativeRunner;
+
+    /// A step with multiple outputs only runs when an output is missing or an input changed
+    #[test]
+    fn generate_step() {
+        let dir = tempdir::TempDir::new("be.jonaseveraert.beaver.tests.native_runner").unwrap().into_path();
+        let input = dir.join("input.txt");
+        let outputs = [dir.join("gen/a.txt"), dir.join("gen/b.txt")];
+        let runs = dir.join("runs");
+        fs::write(&input, "contents\n").unwrap();
+
+        let mut builder = NativeBuilder::new(&dir);
+        builder.add_rule(&rules::CUSTOM);
+        let mut scope = builder.new_scope();
+        let cmd = format!("cp {0} {1} && cp {0} {2} && echo run >> {3}", input.display(), outputs[0].display(), outputs[1].display(), runs.display());
+        scope.add_step(&BuildStep::Generate {
+            rule: &rules::CUSTOM,
+            outputs: &[&outputs[0], &outputs[1]],
+            input: &[&input],
+            dependencies: &[],
+            options: &[("name", "gen"), ("cmd", &cmd)],
+        }).unwrap();
+        builder.apply_scope(scope);
+        let build_file = dir.join("build.native.json");
+        fs::write(&build_file, builder.build()).unwrap();
+
+        let runner = NativeRunner::new(&build_file, false, false);
+        let target = outputs[1].to_str().unwrap();
+        let run_count = |dir: &Path| fs::read_to_string(dir.join("runs")).unwrap().lines().count();
+
+        runner.build(&[target], &dir, &dir).unwrap();
+        runner.build(&[target], &dir, &dir).unwrap();
+        assert_eq!(run_count(&dir), 1);
+
+        fs::File::options().write(true).open(&input).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(2)).unwrap();
+        runner.build(&[target], &dir, &dir).unwrap();
+        assert_eq!(run_count(&dir), 2);
+
+        fs::remove_file(&outputs[0]).unwrap();
+        runner.build(&[target], &dir, &dir).unwrap();
+        assert_eq!(run_count(&dir), 3);
+    }
+}
@@ -169,6 +169,16 @@ impl BackendBuilderScope for NinjaBuilderScope {
                 self.write_dependencies(dependencies)?;
                 self.write_char('\n')?;
                 self.write_options(options)?;
+            },
+            BuildStep::Generate { rule, outputs, input, dependencies, options } => {
+                let paths = |paths: &[&Path]| paths.iter()
+                    .map(|path| self.rel_path.join(path).to_str().expect("Path is not UTF-8 encoded").replace(" ", "$ "))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                self.write_fmt(format_args!("build {}: {} {}", paths(outputs), rule.name, paths(input)))?;
+                self.write_dependencies(dependencies)?;
+                self.write_char('\n')?;
+                self.write_options(options)?;
             }
         }
 
@@ -1,3 +1,4 @@
+use std::ffi::OsStr;
 use std::path::Path;
 use std::process::Command;
 
@@ -9,6 +10,8 @@ pub struct NinjaRunner<'a> {
     build_file: &'a Path,
     verbose: bool,
     debug: bool,
+    /// Environment variables for the executed commands
+    env: Vec<(&'a str, &'a OsStr)>,
 }
 
 impl<'a> NinjaRunner<'a> {
@@ -16,10 +19,16 @@ impl<'a> NinjaRunner<'a> {
         NinjaRunner {
             build_file,
             verbose,
-            debug
+            debug,
+            env: Vec::new(),
         }
     }
 
+    pub fn env(mut self, key: &'a str, value: &'a (impl AsRef<OsStr> + ?Sized)) -> Self {
+        self.env.push((key, value.as_ref()));
+        return self;
+    }
+
     pub fn build<S: AsRef<str>>(&self, targets: &[S], base_dir: &Path, build_dir: &Path) -> crate::Result<()> {
         let mut args = vec![
             "-C", build_dir.to_str().expect("build dir path is not UTF-8 encoded"),
@@ -39,6 +48,7 @@ impl<'a> NinjaRunner<'a> {
 
         let mut process = Command::new(tools::ninja.as_os_str())
             .args(args)
+            .envs(self.env.iter().copied())
             .current_dir(base_dir)
             .spawn()
             .expect("Failed to start ninja");
@@ -68,6 +78,7 @@ impl<'a> NinjaRunner<'a> {
 
         let mut process = Command::new(tools::ninja.as_os_str())
             .args(args)
+            .envs(self.env.iter().copied())
             .current_dir(base_dir)
             .spawn()
             .expect("Failed to start ninja");
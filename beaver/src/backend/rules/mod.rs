@@ -55,7 +55,19 @@ lazy_static! {
         pool: None
     };
 
-    static ref LINK_CMD: String = format!("{} $linkerFlags $in -o $out", tools::cc.display());
+    /// Assembly is compiled by the C compiler, which runs the preprocessor for `.S` files
+    pub static ref ASM: Rule = Rule {
+        name: "asm",
+        options: vec![
+            ("description", "asm $in > $out"),
+            ("command", &CC_CMD),
+            ("deps", "gcc"),
+            ("depfile", "$out.d")
+        ],
+        pool: None
+    };
+
+    static ref LINK_CMD: String = format!("{} $in $linkerFlags -o $out", tools::cc.display());
     pub static ref LINK: Rule = Rule {
         name: "link",
         options: vec![
@@ -65,7 +77,7 @@ lazy_static! {
         pool: None
     };
 
-    static ref LINKXX_CMD: String = format!("{} $linkerFlags $in -o $out", tools::cxx.display());
+    static ref LINKXX_CMD: String = format!("{} $in $linkerFlags -o $out", tools::cxx.display());
     pub static ref LINKXX: Rule = Rule {
         name: "linkxx",
         options: vec![
@@ -75,7 +87,7 @@ lazy_static! {
         pool: None
     };
 
-    static ref LINKOBJC_CMD: String = format!("{} {} $linkerFlags $in -o $out", tools::objc.display(), language::objc::objc_linker_flags().join(" "));
+    static ref LINKOBJC_CMD: String = format!("{} {} $in $linkerFlags -o $out", tools::objc.display(), language::objc::objc_linker_flags().join(" "));
     pub static ref LINKOBJC: Rule = Rule {
         name: "link_objc",
         options: vec![
@@ -85,7 +97,7 @@ lazy_static! {
         pool: None
     };
 
-    static ref LINKOBJCXX_CMD: String = format!("{} {} $linkerFlags $in -o $out", tools::objcxx.display(), language::objc::objcxx_linker_flags().join(" "));
+    static ref LINKOBJCXX_CMD: String = format!("{} {} $in $linkerFlags -o $out", tools::objcxx.display(), language::objc::objcxx_linker_flags().join(" "));
     pub static ref LINKOBJCXX: Rule = Rule {
         name: "link_objcxx",
         options: vec![
@@ -105,7 +117,17 @@ lazy_static! {
         pool: None
     };
 
-    static ref JSLIB_C_CMD: String = format!("{} $linkerFlags $in -o $out", tools::cc.display());
+    /// Create a symbolic link `$out` pointing to `$target`, relative to the directory of `$out`
+    pub static ref SYMLINK: Rule = Rule {
+        name: "symlink",
+        options: vec![
+            ("description", "linking $out"),
+            ("command", "ln -sf $target $out")
+        ],
+        pool: None
+    };
+
+    static ref JSLIB_C_CMD: String = format!("{} $in $linkerFlags -o $out", tools::cc.display());
     pub static ref JSLIB_C: Rule = Rule {
         name: "jslib_c",
         options: vec![
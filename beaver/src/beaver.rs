@@ -10,22 +10,28 @@ use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU8, Ordering};
 
 use console::style;
 use log::*;
-use program_communicator::socket::ReceiveResult;
+use program_communicator::protocol::Frame;
+use program_communicator::socket::{Connection, ReceiveResult};
 use target_lexicon::Triple;
-use zerocopy::IntoBytes;
 
+use crate::backend::makefile::MakefileBuilder;
+use crate::backend::native::{NativeBuilder, NativeRunner};
 use crate::backend::ninja::{NinjaBuilder, NinjaRunner};
-use crate::backend::BackendBuilder;
+use crate::backend::{Backend, BackendBuilder, BackendBuilderScope, CompilationDatabase, CompileCommand, Rule};
 use crate::cache::Cache;
 use crate::command::Commands;
+use crate::install::{self, InstallFile, InstallOptions};
 use crate::traits::{AnyExecutable, AnyLibrary, AnyProject};
-use crate::{tools, OptimizationMode};
+use crate::coverage::{self, CoverageFormat, CoverageOptions};
+use crate::target::custom::{BuildOutput, GeneratedKind};
+use crate::{callback, tools, OptimizationMode, Profile, RuleBuildStep, Sanitizer};
 use crate::phase_hook::{Phase, PhaseHook, PhaseHooks};
 use crate::error::BeaverError;
 use crate::project::traits::Project;
 use crate::target::traits::{AnyTarget, Target};
 use crate::target::{ArtifactType, Dependency, ExecutableArtifactType, TargetRef};
 use crate::target::cmake::Library as CMakeLibrary;
+use crate::testing::{self, TestCase, TestOptions};
 use crate::project::cmake::Project as CMakeProject;
 
 #[derive(PartialEq, Eq, Debug)]
@@ -76,7 +82,15 @@ impl std::fmt::Debug for CommunicationSocket {
 pub struct Beaver {
     projects: RwLock<Vec<AnyProject>>,
     project_index: AtomicIsize,
-    pub(crate) optimize_mode: OptimizationMode,
+    /// The name of the selected profile, also used as the name of the build directory
+    profile_name: String,
+    /// The selected profile. When a custom profile is selected, this is set once the
+    /// script defines it
+    profile: OnceLock<Profile>,
+    sanitizers: Vec<Sanitizer>,
+    /// Instrument C targets for coverage reports (`--coverage`)
+    coverage: bool,
+    backend: Backend,
     build_dirs: OnceLock<BuildDirs>,
     enable_color: bool,
     pub(crate) target_triple: Triple,
@@ -88,9 +102,19 @@ pub struct Beaver {
     phase_hook_build: Mutex<PhaseHooks>,
     phase_hook_run: Mutex<PhaseHooks>,
     phase_hook_clean: Mutex<PhaseHooks>,
+    phase_hook_test: Mutex<PhaseHooks>,
+    phase_hook_install: Mutex<PhaseHooks>,
     commands: Mutex<Commands>,
+    /// Rules defined by the script, by name
+    rules: Mutex<HashMap<String, &'static Rule>>,
+    /// Build steps defined by the script using the `rules`
+    build_steps: Mutex<Vec<RuleBuildStep>>,
     /// Indicates wether the symlink to the last built target has been created
     symlink_created: AtomicBool,
+    /// Compile commands of all C targets, written to `compile_commands.json` together with the build file
+    compile_commands: Mutex<CompilationDatabase>,
+    /// Symlink `compile_commands.json` into the project root
+    compile_commands_symlink: AtomicBool,
 
     pub(crate) comm_socket: CommunicationSocket,
 }
@@ -100,7 +124,8 @@ impl Beaver {
         enable_color: Option<bool>,
         optimize_mode: OptimizationMode,
         verbose: bool, debug: bool,
-        target: Triple
+        target: Triple,
+        backend: Backend
     ) -> crate::Result<Beaver> {
         if target != Triple::host() {
             warn!("Cross-compilation is in early development, expect bugs");
@@ -111,7 +136,11 @@ impl Beaver {
         Ok(Beaver {
             projects: RwLock::new(Vec::new()),
             project_index: AtomicIsize::new(-1),
-            optimize_mode,
+            profile_name: optimize_mode.to_string(),
+            profile: OnceLock::from(Profile::from(optimize_mode)),
+            sanitizers: Vec::new(),
+            coverage: false,
+            backend,
             build_dirs: OnceLock::new(),
             enable_color: enable_color.unwrap_or(true), // TODO: derive from isatty or set instance var to optional
             target_triple: target,
@@ -122,8 +151,14 @@ impl Beaver {
             phase_hook_build: Mutex::new(PhaseHooks(Vec::new())),
             phase_hook_run: Mutex::new(PhaseHooks(Vec::new())),
             phase_hook_clean: Mutex::new(PhaseHooks(Vec::new())),
+            phase_hook_test: Mutex::new(PhaseHooks(Vec::new())),
+            phase_hook_install: Mutex::new(PhaseHooks(Vec::new())),
             commands: Mutex::new(Commands(HashMap::new())),
+            rules: Mutex::new(HashMap::new()),
+            build_steps: Mutex::new(Vec::new()),
             symlink_created: AtomicBool::new(false),
+            compile_commands: Mutex::new(CompilationDatabase::new()),
+            compile_commands_symlink: AtomicBool::new(false),
             comm_socket: CommunicationSocket(OnceLock::new())
             // lock_builddir: AtomicBool::new(false),
             // build_file_create_result: OnceLock::new()
@@ -135,8 +170,115 @@ impl Beaver {
         &self.target_triple
     }
 
-    pub fn opt_mode(&self) -> &OptimizationMode {
-        &self.optimize_mode
+    /// The optimization mode of the selected profile
+    pub fn opt_mode(&self) -> crate::Result<OptimizationMode> {
+        self.profile().map(|profile| profile.base)
+    }
+
+    pub fn profile(&self) -> crate::Result<&Profile> {
+        self.profile.get().ok_or_else(|| BeaverError::UndefinedProfile(self.profile_name.clone()))
+    }
+
+    pub fn profile_name(&self) -> &str {
+        &self.profile_name
+    }
+
+    /// Select the profile to build with, either a built-in optimization mode or a profile
+    /// which will be defined by the script with `define_profile`
+    pub fn select_profile(&mut self, name: &str) -> crate::Result<()> {
+        match OptimizationMode::try_from(name) {
+            Ok(mode) => {
+                self.profile_name = mode.to_string();
+                self.profile = OnceLock::from(Profile::from(mode));
+            },
+            Err(_) => {
+                Self::validate_profile_name(name)?;
+                self.profile_name = name.to_string();
+                self.profile = OnceLock::new();
+            }
+        }
+        return Ok(());
+    }
+
+    /// Define a custom profile. Definitions of profiles other than the selected one are ignored.
+    pub fn define_profile(&self, profile: Profile) -> crate::Result<()> {
+        if self.status.load(Ordering::SeqCst) != BeaverState::Initialized as u8 {
+            return Err(BeaverError::AlreadyFinalized);
+        }
+        if profile.is_builtin() {
+            return Err(BeaverError::ProfileShadowsOptimizationMode(profile.name));
+        }
+        Self::validate_profile_name(&profile.name)?;
+
+        if profile.name != self.profile_name {
+            return Ok(());
+        }
+        self.profile.set(profile).map_err(|profile| BeaverError::ProfileAlreadyDefined(profile.name))
+    }
+
+    /// Profile names are used as directory names
+    fn validate_profile_name(name: &str) -> crate::Result<()> {
+        if name.len() == 0 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(BeaverError::InvalidProfileName(name.to_string()));
+        }
+        return Ok(());
+    }
+
+    /// Build all C targets with `sanitizers`
+    pub fn set_sanitizers(&mut self, sanitizers: Vec<Sanitizer>) -> crate::Result<()> {
+        self.sanitizers = Sanitizer::validate(sanitizers)?;
+        return Ok(());
+    }
+
+    pub fn sanitizers(&self) -> &[Sanitizer] {
+        &self.sanitizers
+    }
+
+    /// Build all C targets with coverage instrumentation
+    pub fn set_coverage(&mut self, coverage: bool) {
+        self.coverage = coverage;
+    }
+
+    pub fn coverage_enabled(&self) -> bool {
+        self.coverage
+    }
+
+    /// The compiler flags of the selected profile and instrumentation, passed to all C targets
+    pub(crate) fn cflags(&self) -> crate::Result<Vec<String>> {
+        let mut flags: Vec<String> = self.profile()?.cflags().map(String::from).collect();
+        flags.extend(Sanitizer::flags(&self.sanitizers));
+        if self.coverage {
+            flags.push("--coverage".to_string());
+        }
+        return Ok(flags);
+    }
+
+    /// The linker flags of the selected profile and instrumentation, passed to all C targets
+    pub(crate) fn linker_flags(&self) -> crate::Result<Vec<String>> {
+        let mut flags: Vec<String> = self.profile()?.linker_flags().map(String::from).collect();
+        flags.extend(Sanitizer::flags(&self.sanitizers));
+        if self.coverage {
+            flags.push("--coverage".to_string());
+        }
+        return Ok(flags);
+    }
+
+    /// The name of the output directory, e.g. `debug-asan-ubsan`. Instrumented builds get
+    /// their own directory, so that switching between them doesn't rebuild everything.
+    pub fn output_dir_name(&self) -> String {
+        let mut name = self.profile_name.clone();
+        for sanitizer in &self.sanitizers {
+            name.push('-');
+            name.push_str(sanitizer.short_name());
+        }
+        if self.coverage {
+            name.push_str("-coverage");
+        }
+        return name;
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
     }
 
     pub fn color_enabled(&self) -> bool {
@@ -162,7 +304,7 @@ impl Beaver {
             fs::create_dir_all(base.as_path()).map_err(BeaverError::from)?;
         }
 
-        let outdir = base.join(&self.target_triple.to_string()).join(&self.optimize_mode.to_string());
+        let outdir = base.join(&self.target_triple.to_string()).join(self.output_dir_name());
         if !outdir.exists() {
             fs::create_dir_all(outdir.as_path()).map_err(BeaverError::from)?;
         }
@@ -245,7 +387,7 @@ impl Beaver {
                 _ => self.get_build_dir().map(|path| path.to_path_buf())
             }
         })?;
-        let to = self.get_base_build_dir()?.join(self.optimize_mode.to_string());
+        let to = self.get_base_build_dir()?.join(self.output_dir_name());
 
         if to.exists() {
             if to.is_symlink() {
@@ -505,123 +647,196 @@ impl Beaver {
     // TODO: build/triple/optimize mode & symlink current triple
 
     fn build_file(&self) -> crate::Result<PathBuf> {
+        let filename = match self.backend {
+            Backend::Ninja => "build.ninja",
+            Backend::Native => "build.native.json",
+        };
         self.get_build_dir()
-            .map(|path| path.join("build.ninja"))
+            .map(|path| path.join(filename))
     }
 
-    /// If `callback_path` is set, this indicates that a message should be sent to the caller of the command
-    fn handle_communication(self: &Arc<Self>, reader: &mut dyn io::Read, callback_path: &mut Option<PathBuf>) -> crate::Result<ReceiveResult> {
-        trace!(target: "communication", "Received message to communication socket");
+    fn compile_commands_file(&self) -> crate::Result<PathBuf> {
+        self.get_build_dir()
+            .map(|path| path.join("compile_commands.json"))
+    }
 
-        let mut str = String::new();
-        reader.read_to_string(&mut str)?;
+    /// When enabled, `compile_commands.json` will be symlinked into the project root after
+    /// the build file has been created
+    pub fn set_compile_commands_symlink(&self, enable: bool) {
+        self.compile_commands_symlink.store(enable, Ordering::SeqCst);
+    }
 
-        let i = str.chars().position(|c| c == ' ').unwrap_or(str.len());
-        let cmd = &str[..i];
+    pub(crate) fn add_compile_command(&self, entry: CompileCommand) -> crate::Result<()> {
+        self.compile_commands.lock()
+            .map_err(|err| BeaverError::LockError(err.to_string()))?
+            .add(entry);
+        Ok(())
+    }
 
-        match cmd {
-            "close" => Ok(ReceiveResult::Close),
-            "build" => {
-                #[cfg(unix)] {
-                    trace!(target: "communication", "message is a build message");
-                    let target_end = i+1 + str[i+1..].chars().position(|c| c == ' ').expect("invalid formatted command");
-                    let mut target = str[i+1..target_end].split(":");
-                    let project_id = target.next().unwrap().parse::<usize>().unwrap();
-                    let target_id = target.next().unwrap().parse::<usize>().unwrap();
-                    assert!(target.next().is_none());
+    /// Writes the compilation database next to the build file, merging the databases of
+    /// imported CMake and Meson projects
+    fn write_compilation_database(&self) -> crate::Result<()> {
+        // The database of the targets is kept, so the build file can be regenerated without
+        // merging the imported databases into it twice
+        let mut database = self.compile_commands.lock()
+            .map_err(|err| BeaverError::LockError(err.to_string()))?
+            .clone();
 
-                    *callback_path = Some(PathBuf::from(&str[target_end+1..].trim()));
+        let projects = self.projects()?;
+        for project in projects.iter() {
+            match project {
+                AnyProject::CMake(_) | AnyProject::Meson(_) => {
+                    database.merge_file(&project.build_dir().join("compile_commands.json"))?;
+                },
+                _ => {}
+            }
+        }
 
-                    trace!(target: "communication", "parameters: project_id={} target_id={} callback_path={:?}", project_id, target_id, callback_path);
+        let output_file = self.compile_commands_file()?;
+        database.write(&output_file)?;
 
-                    self.with_project(project_id, |proj| {
-                        let target = &proj.targets()?[target_id];
-                        trace!("{:?}", target);
+        if !self.compile_commands_symlink.load(Ordering::SeqCst) {
+            return Ok(());
+        }
 
-                        match target {
-                            AnyTarget::Library(lib) => match lib {
-                                AnyLibrary::Custom(library) => library.build()?,
-                                _ => return Err(BeaverError::TargetHasNoBuildCommand(target.name().to_string()))
-                            },
-                            AnyTarget::Executable(exe) => match exe {
-                                AnyExecutable::Custom(executable) => executable.build()?,
-                                _ => return Err(BeaverError::TargetHasNoBuildCommand(target.name().to_string()))
-                            },
-                        }
+        // The root project is the first project defined by a beaver script
+        let Some(root_project) = projects.iter().find(|project| matches!(project, AnyProject::Beaver(_))) else {
+            return Err(BeaverError::NoProjects);
+        };
+        let link = root_project.base_dir().join("compile_commands.json");
+        match fs::symlink_metadata(&link) {
+            Ok(metadata) => if metadata.is_symlink() {
+                if fs::read_link(&link)? == output_file {
+                    return Ok(());
+                }
+                fs::remove_file(&link)?;
+            } else {
+                return Err(BeaverError::SymlinkCreationExists(link));
+            },
+            Err(err) => if err.kind() != io::ErrorKind::NotFound {
+                return Err(BeaverError::SymlinkCreationError(err, output_file, link));
+            }
+        }
 
-                        Ok(())
-                    })?;
+        utils::fs::symlink_file(&output_file, &link)
+            .map_err(|err| BeaverError::SymlinkCreationError(err, output_file, link))
+    }
 
-                    Ok(ReceiveResult::Continue)
-                }
+    /// Handle a request sent by `beaver callback`. Requests are answered with the output of
+    /// the request, the exit status to answer with is returned.
+    fn handle_communication(self: &Arc<Self>, connection: &Connection) -> crate::Result<(ReceiveResult, Option<i32>)> {
+        trace!(target: "communication", "Received message to communication socket");
 
-                #[cfg(not(unix))] {
-                    panic!("Custom targets are not supported on this platform");
+        let Some(Frame::Request(request)) = connection.read_frame()? else {
+            error!(target: "communication", "Expected a request");
+            return Ok((ReceiveResult::Continue, None));
+        };
+
+        let mut args = request.split(' ');
+        let result = match args.next() {
+            Some("close") => return Ok((ReceiveResult::Close, None)),
+            Some("build") => {
+                trace!(target: "communication", "message is a build message");
+                let target = args.next()
+                    .and_then(|target| target.split_once(':'))
+                    .and_then(|(project_id, target_id)| Some((project_id.parse::<usize>().ok()?, target_id.parse::<usize>().ok()?)));
+                match target {
+                    Some((project_id, target_id)) if args.next().is_none() => self.run_build_command(project_id, target_id, connection),
+                    _ => Err(BeaverError::InvalidCallbackRequest(request.clone()))
                 }
+            },
+            _ => Err(BeaverError::InvalidCallbackRequest(request.clone()))
+        };
+
+        let status = match result {
+            Ok(()) => 0,
+            Err(err) => {
+                connection.send(&Frame::Stderr(format!("{}\n", err).into_bytes()))?;
+                1
+            }
+        };
+        trace!(target: "communication", "request {:?} finished with status {}", request, status);
 
-                // // let target_ref = unsafe { &(**self_ptr.value()) }.parse_target_ref(target).map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
-                // match self2.build(target_ref) {
-                //     Ok(()) => {
-                //         #[cfg(unix)] {
-                //             program_communicator::callback::unix::send_message(&callback_path, '0'.as_bytes()).map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
-                //         }
-                //         #[cfg(not(unix))] {
-                //             panic!("Custom targets not supported on this platform");
-                //         }
-                //     },
-                //     Err(err) => {
-                //         error!("{err}");
-                //         #[cfg(unix)] {
-                //             program_communicator::callback::unix::send_message(&callback_path, '1'.as_bytes()).map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
-                //         }
-                //         #[cfg(not(unix))] {
-                //             panic!("Custom targets not supported on this platform");
-                //         }
-                //     },
-                // }
+        Ok((ReceiveResult::Continue, Some(status)))
+    }
 
-            },
-            _ => {
-                error!("Invalid command {cmd}");
-                Ok(ReceiveResult::Continue)
+    /// Run the build command of a custom target, forwarding its output to `connection`
+    fn run_build_command(self: &Arc<Self>, project_id: usize, target_id: usize, connection: &Connection) -> crate::Result<()> {
+        trace!(target: "communication", "parameters: project_id={} target_id={}", project_id, target_id);
+
+        let (stdout_reader, stdout) = io::pipe()?;
+        let (stderr_reader, stderr) = io::pipe()?;
+        let forward = |reader: io::PipeReader, frame: fn(Vec<u8>) -> Frame| {
+            let connection = connection.clone();
+            std::thread::spawn(move || connection.forward(reader, frame))
+        };
+        let forward_stdout = forward(stdout_reader, Frame::Stdout);
+        let forward_stderr = forward(stderr_reader, Frame::Stderr);
+
+        // The output is moved into the build command, so that the pipes are closed when it finishes
+        let output = BuildOutput { stdout, stderr };
+        let result = self.with_project(project_id, |proj| {
+            let target = &proj.targets()?[target_id];
+            trace!("{:?}", target);
+
+            match target {
+                AnyTarget::Library(lib) => match lib {
+                    AnyLibrary::Custom(library) => library.build(output),
+                    _ => Err(BeaverError::TargetHasNoBuildCommand(target.name().to_string()))
+                },
+                AnyTarget::Executable(exe) => match exe {
+                    AnyExecutable::Custom(executable) => executable.build(output),
+                    _ => Err(BeaverError::TargetHasNoBuildCommand(target.name().to_string()))
+                },
             }
-        }
+        });
+
+        // All output is sent before the exit status
+        forward_stdout.join().expect("output forwarding thread panicked")?;
+        forward_stderr.join().expect("output forwarding thread panicked")?;
+
+        return result;
     }
 
     pub(crate) fn enable_communication(self: &Arc<Self>) -> crate::Result<()> {
+        if self.comm_socket.0.get().is_some() {
+            return Ok(());
+        }
+
         let self2 = Arc::downgrade(self);
-        let _ = self.comm_socket.0.set(program_communicator::socket::listen("beaver_custom_targets", move |reader| {
-            let mut callback_path: Option<PathBuf> = None;
+        let _ = self.comm_socket.0.set(program_communicator::socket::listen("beaver_custom_targets", move |connection| {
             let Some(self2) = self2.upgrade() else {
                 return Ok(ReceiveResult::Close);
             };
-            match self2.handle_communication(reader, &mut callback_path) {
-                Ok(res) => {
-                    trace!(target: "communication", "message result = {:?}", res);
-                    #[cfg(unix)] {
-                        if let Some(callback_path) = callback_path {
-                            program_communicator::callback::unix::send_message(&callback_path, '0'.as_bytes())
-                                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
-                        }
-                    }
-                    Ok(res)
-                },
+            let (res, status) = match self2.handle_communication(connection) {
+                Ok(res) => res,
+                // Failing to communicate with one client shouldn't stop the others
                 Err(err) => {
                     error!(target: "communication", "{}", err);
-                    #[cfg(unix)] {
-                        if let Some(callback_path) = callback_path {
-                            program_communicator::callback::unix::send_message(&callback_path, '1'.as_bytes())
-                                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send>)?;
-                        }
-                    }
-                    Err(Box::new(err) as Box<dyn std::error::Error + Send>)
+                    (ReceiveResult::Continue, None)
                 },
+            };
+            // The client may drop the last reference to beaver once it's answered. Dropping it
+            // here would wait for the socket's threads, including this one.
+            drop(self2);
+
+            if let Some(status) = status {
+                if let Err(err) = connection.send(&Frame::Exit(status)) {
+                    error!(target: "communication", "{}", err);
+                }
             }
+            trace!(target: "communication", "message result = {:?}", res);
+            Ok(res)
         }).map_err(|err| BeaverError::AnyError(err.to_string()))?);
 
         Ok(())
     }
 
+    /// The socket `beaver callback` connects to, once a custom target has been registered
+    pub fn communication_socket(&self) -> Option<&Path> {
+        self.comm_socket.0.get().map(|socket| socket.path())
+    }
+
     pub fn create_build_file(self: &Arc<Self>) -> crate::Result<()> {
         if self.status.load(Ordering::SeqCst) != BeaverState::Initialized as u8 {
             return Err(BeaverError::AlreadyFinalized);
@@ -629,12 +844,52 @@ impl Beaver {
         self.status.store(BeaverState::Build as u8, Ordering::SeqCst);
 
         let build_dir = self.get_build_dir()?;
-        let ninja_builder: Arc<RwLock<NinjaBuilder>> = Arc::new(RwLock::new(NinjaBuilder::new(&env::current_dir()?, &build_dir))); // TODO: Mutex
+        let output = match self.backend {
+            Backend::Ninja => self.register_projects(NinjaBuilder::new(&env::current_dir()?, &build_dir))?,
+            Backend::Native => self.register_projects(NativeBuilder::new(&env::current_dir()?))?,
+        };
+        let output_file = self.build_file()?;
+
+        let mut file = fs::File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(output_file)
+            .map_err(|err| BeaverError::BuildFileWriteError(err))?;
+        file.write(output.as_bytes())
+            .map_err(|err| BeaverError::BuildFileWriteError(err))?;
+
+        self.write_compilation_database()?;
+
+        return Ok(());
+    }
+
+    /// Write the build graph as a GNU Makefile to `file`, so that the projects can be built
+    /// without beaver or ninja
+    pub fn export_makefile(self: &Arc<Self>, file: &Path) -> crate::Result<()> {
+        let output = self.register_projects(MakefileBuilder::new(&env::current_dir()?))?;
+
+        if self.comm_socket.0.get().is_some() {
+            warn!("Custom targets communicate with a running beaver process and can't be built from the exported Makefile");
+        }
+
+        fs::write(file, output)
+            .map_err(|err| BeaverError::BuildFileWriteError(err))?;
+
+        return Ok(());
+    }
+
+    /// Register all projects to `builder` and return the contents of the build file
+    fn register_projects<Builder: BackendBuilder<'static>>(self: &Arc<Self>, builder: Builder) -> crate::Result<String> {
+        // A selected custom profile must have been defined by now
+        self.profile()?;
+
+        let builder: Arc<RwLock<Builder>> = Arc::new(RwLock::new(builder)); // TODO: Mutex
         let mut error: OnceLock<BeaverError> = OnceLock::new();
         let projects = self.projects()?;
         rayon::scope(|s| {
             for project in projects.iter() {
-                s.spawn(|s| match project.register(s, &self.target_triple, ninja_builder.clone(), &self) {
+                s.spawn(|s| match project.register(s, &self.target_triple, builder.clone(), &self) {
                     Err(err) => {
                         match error.set(err) {
                             Ok(_) => {},
@@ -653,21 +908,10 @@ impl Beaver {
             return Err(err);
         }
 
-        let builder = Arc::try_unwrap(ninja_builder).unwrap_or_else(|_| panic!("Arc shouldn't be referenced anymore"));
-        let builder = builder.into_inner().map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
-        let output = builder.build();
-        let output_file = self.build_file()?;
-
-        let mut file = fs::File::options()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(output_file)
-            .map_err(|err| BeaverError::BuildFileWriteError(err))?;
-        file.write(output.as_bytes())
-            .map_err(|err| BeaverError::BuildFileWriteError(err))?;
-
-        return Ok(());
+        let builder = Arc::try_unwrap(builder).unwrap_or_else(|_| panic!("Arc shouldn't be referenced anymore"));
+        let mut builder = builder.into_inner().map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
+        self.register_build_steps(&mut builder)?;
+        return Ok(builder.build());
     }
 
     /// Retrieve the names to use when calling the backend
@@ -709,9 +953,23 @@ impl Beaver {
         self.run_phase_hook(Phase::Build)?;
 
         let build_file = self.build_file()?;
-        let ninja_runner = NinjaRunner::new(&build_file, self.verbose, self.debug);
         let build_dir = self.get_build_dir()?;
-        ninja_runner.build(target_names, &env::current_dir()?, &build_dir)?;
+        match self.backend {
+            Backend::Ninja => {
+                let mut ninja_runner = NinjaRunner::new(&build_file, self.verbose, self.debug);
+                if let Some(socket) = self.communication_socket() {
+                    ninja_runner = ninja_runner.env(callback::SOCKET_ENV, socket);
+                }
+                ninja_runner.build(target_names, &env::current_dir()?, &build_dir)?;
+            },
+            Backend::Native => {
+                let mut native_runner = NativeRunner::new(&build_file, self.verbose, self.debug);
+                if let Some(socket) = self.communication_socket() {
+                    native_runner = native_runner.env(callback::SOCKET_ENV, socket);
+                }
+                native_runner.build(target_names, &env::current_dir()?, &build_dir)?;
+            }
+        }
 
         self.create_symlink()?;
 
@@ -768,6 +1026,186 @@ impl Beaver {
         self.run(exe, args)
     }
 
+    /// Build and run all tests of all projects whose name contains the filter in `options`
+    pub fn test(self: &Arc<Self>, options: &TestOptions) -> crate::Result<()> {
+        if self.target_triple != Triple::host() {
+            return Err(BeaverError::CantRunTargetTriple(self.target_triple.to_string()));
+        }
+
+        let mut tests: Vec<TestCase> = Vec::new();
+        for project in self.projects()?.iter() {
+            tests.extend(project.tests(self)?);
+        }
+        if let Some(filter) = &options.filter {
+            tests.retain(|test| test.name.contains(filter.as_str()));
+        }
+
+        if tests.len() == 0 {
+            println!("No tests found");
+            return Ok(());
+        }
+
+        use itertools::Itertools;
+        let build_steps: Vec<&str> = tests.iter()
+            .filter_map(|test| test.build_step.as_ref().map(|step| step.as_str()))
+            .unique()
+            .collect();
+        if build_steps.len() > 0 {
+            self.build_all_named(&build_steps)?;
+        }
+
+        self.run_phase_hook(Phase::Test)?;
+
+        let start = std::time::Instant::now();
+        let results = testing::run_tests(&tests, options);
+        let duration = start.elapsed();
+
+        if let Some(file) = &options.junit {
+            testing::write_junit(&results, duration, file)?;
+        }
+
+        let failed = results.iter().filter(|result| !result.passed()).count();
+        let summary = format!("{} passed, {} failed", results.len() - failed, failed);
+        println!("\n{} {} ({:.2}s)",
+            if failed == 0 { style("test result: ok.").green() } else { style("test result: FAILED.").red() },
+            summary,
+            duration.as_secs_f64());
+
+        if failed > 0 {
+            return Err(BeaverError::TestsFailed { failed, total: results.len() });
+        } else {
+            return Ok(());
+        }
+    }
+
+    /// Run the tests of all projects and report the line coverage of their sources. Requires coverage
+    /// instrumentation to be enabled with `set_coverage`.
+    ///
+    /// A report is also written when tests fail, in which case the test error is returned afterwards.
+    pub fn coverage(self: &Arc<Self>, test_options: &TestOptions, options: &CoverageOptions) -> crate::Result<()> {
+        if !self.coverage {
+            return Err(BeaverError::CoverageNotEnabled);
+        }
+
+        let build_dir = self.get_build_dir()?.to_path_buf();
+        coverage::remove_gcda(&build_dir)?;
+
+        let test_result = self.test(test_options);
+        if let Err(err) = test_result {
+            if !matches!(err, BeaverError::TestsFailed { .. }) {
+                return Err(err);
+            }
+            return self.coverage_report(&build_dir, options).and(Err(err));
+        }
+
+        return self.coverage_report(&build_dir, options);
+    }
+
+    fn coverage_report(&self, build_dir: &Path, options: &CoverageOptions) -> crate::Result<()> {
+        let gcda_files = coverage::find_gcda(build_dir)?;
+        if gcda_files.len() == 0 {
+            return Err(BeaverError::NoCoverageData(build_dir.to_path_buf()));
+        }
+
+        let source_dirs: Vec<PathBuf> = self.projects()?.iter()
+            .map(|project| project.base_dir().to_path_buf())
+            .collect();
+        let base_build_dir = self.get_base_build_dir()?;
+
+        let mut files = coverage::gcov_summary(&gcda_files, build_dir)?;
+        files.retain(|file| {
+            source_dirs.iter().any(|dir| file.file.starts_with(dir)) && !file.file.starts_with(base_build_dir)
+        });
+        let base_dir = self.with_current_project(|project| Ok::<PathBuf, BeaverError>(project.base_dir().to_path_buf())).ok();
+        println!("\n{}", coverage::text_summary(&files, base_dir.as_deref()));
+
+        let coverage_dir = build_dir.join("coverage");
+        match options.format {
+            CoverageFormat::Text => {
+                if let Some(output) = &options.output {
+                    fs::write(output, coverage::text_summary(&files, base_dir.as_deref()))
+                        .map_err(|err| BeaverError::io(err, format!("while writing {}", output.display())))?;
+                }
+            },
+            CoverageFormat::Lcov => {
+                let output = options.output.clone().unwrap_or(coverage_dir.join("coverage.info"));
+                coverage::write_lcov(build_dir, &source_dirs, &output)?;
+                println!("Coverage data written to {}", output.display());
+            },
+            CoverageFormat::Html => {
+                let info = coverage_dir.join("coverage.info");
+                coverage::write_lcov(build_dir, &source_dirs, &info)?;
+                let output = options.output.clone().unwrap_or(coverage_dir.join("html"));
+                coverage::write_html(&info, &output)?;
+                println!("Coverage report written to {}", output.join("index.html").display());
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Build `targets` and copy their installable files into the prefix in `options`. The installed
+    /// files are recorded in the cache, so that they can be removed by `uninstall`.
+    pub fn install(self: &Arc<Self>, targets: &[TargetRef], options: &InstallOptions) -> crate::Result<()> {
+        self.build_all(targets)?;
+
+        self.run_phase_hook(Phase::Install)?;
+
+        let mut files = Vec::new();
+        for target in targets {
+            files.extend(self.with_project_and_target::<Vec<InstallFile>, BeaverError>(target, |project, target| {
+                target.install_files(project.base_dir(), project.build_dir(), &self.target_triple, options, self)
+            })?);
+        }
+
+        if files.len() == 0 {
+            println!("Nothing to install");
+            return Ok(());
+        }
+
+        let cache = self.cache()?;
+        for file in &files {
+            let destination = std::path::absolute(install::install_file(file, options)?)?;
+            println!("-- Installing: {}", destination.display());
+            cache.add_installed_file(&destination)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Install all targets of the current project, except for tests
+    pub fn install_current_project(self: &Arc<Self>, options: &InstallOptions) -> crate::Result<()> {
+        let targets = self.with_current_project::<Vec<TargetRef>, BeaverError>(|project| {
+            let project_id = project.id().unwrap();
+            Ok(project.targets()?.iter().enumerate()
+                .filter(|(_, target)| !target.is_test())
+                .map(|(idx, _)| TargetRef { project: project_id, target: idx })
+                .collect())
+        })?;
+        self.install(&targets, options)
+    }
+
+    /// Remove all files installed by `install`. Directories created by `install` are left in place.
+    pub fn uninstall(self: &Arc<Self>) -> crate::Result<()> {
+        let cache = self.cache()?;
+        let files = cache.installed_files()?;
+        if files.len() == 0 {
+            println!("Nothing to uninstall");
+            return Ok(());
+        }
+
+        for file in files {
+            match fs::remove_file(&file) {
+                Ok(()) => println!("-- Removing: {}", file.display()),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => warn!("{} was already removed", file.display()),
+                Err(err) => return Err(BeaverError::io(err, format!("while removing {}", file.display()))),
+            }
+            cache.remove_installed_file(&file)?;
+        }
+
+        return Ok(());
+    }
+
     pub fn clean(self: &Arc<Self>) -> crate::Result<()> {
         info!("Cleaning all projects...");
 
@@ -807,6 +1245,12 @@ impl Beaver {
             Phase::Clean => self.phase_hook_clean.lock()
                 .map_err(|err| BeaverError::LockError(err.to_string()))?
                 .0.push(hook),
+            Phase::Test => self.phase_hook_test.lock()
+                .map_err(|err| BeaverError::LockError(err.to_string()))?
+                .0.push(hook),
+            Phase::Install => self.phase_hook_install.lock()
+                .map_err(|err| BeaverError::LockError(err.to_string()))?
+                .0.push(hook),
         }
         Ok(())
     }
@@ -838,6 +1282,8 @@ impl Beaver {
             Phase::Build => Self::get_hook_ignore_block(&self.phase_hook_build),
             Phase::Run => Self::get_hook_ignore_block(&self.phase_hook_run),
             Phase::Clean => Self::get_hook_ignore_block(&self.phase_hook_clean),
+            Phase::Test => Self::get_hook_ignore_block(&self.phase_hook_test),
+            Phase::Install => Self::get_hook_ignore_block(&self.phase_hook_install),
         }?;
 
         let mut hooks = match hooks {
@@ -887,6 +1333,86 @@ impl Beaver {
         return Ok(guard.0.contains_key(name));
     }
 
+    /// Define a rule which can be used by build steps added with [`add_build_step`](Self::add_build_step).
+    /// `options` are the variables of the rule (`command`, `description`, `depfile`, ...).
+    pub fn define_rule(&self, name: &str, options: Vec<(String, String)>) -> crate::Result<()> {
+        if self.status.load(Ordering::SeqCst) != BeaverState::Initialized as u8 {
+            return Err(BeaverError::AlreadyFinalized);
+        }
+        let mut rules = self.rules.lock().map_err(|err| BeaverError::LockError(err.to_string()))?;
+        if rules.contains_key(name) {
+            return Err(BeaverError::RuleAlreadyDefined(name.to_string()));
+        }
+        let rule = crate::build_rule::new_rule(name, options)?;
+        _ = rules.insert(name.to_string(), rule);
+
+        Ok(())
+    }
+
+    /// Add a build step using the rule `rule` to the current project. Relative inputs are
+    /// relative to the project's directory, relative outputs to the project's build directory.
+    ///
+    /// Returns the absolute paths of the outputs
+    pub fn add_build_step(
+        &self,
+        rule: &str,
+        inputs: &[PathBuf],
+        outputs: &[PathBuf],
+        dependencies: Vec<Dependency>,
+        variables: Vec<(String, String)>
+    ) -> crate::Result<Vec<PathBuf>> {
+        if self.status.load(Ordering::SeqCst) != BeaverState::Initialized as u8 {
+            return Err(BeaverError::AlreadyFinalized);
+        }
+        let Some(rule) = self.rules.lock().map_err(|err| BeaverError::LockError(err.to_string()))?.get(rule).copied() else {
+            return Err(BeaverError::UndefinedRule(rule.to_string()));
+        };
+        let step = self.with_current_project(|project| {
+            RuleBuildStep::new(rule, inputs, outputs, dependencies, variables, project.base_dir(), project.build_dir())
+        })?;
+
+        let mut steps = self.build_steps.lock().map_err(|err| BeaverError::LockError(err.to_string()))?;
+        if let Some(output) = step.outputs.iter().find(|output| steps.iter().any(|other| other.outputs.contains(output))) {
+            return Err(BeaverError::DuplicateBuildOutput(output.clone()));
+        }
+        let outputs = step.outputs.clone();
+        steps.push(step);
+
+        return Ok(outputs);
+    }
+
+    /// The files of `kind` written by the build command of a custom target
+    pub fn generated_files(&self, target: &TargetRef, kind: GeneratedKind) -> crate::Result<Vec<PathBuf>> {
+        self.with_project_and_target::<Vec<PathBuf>, BeaverError>(target, |_, target| {
+            let files = match target {
+                AnyTarget::Library(AnyLibrary::Custom(library)) => library.generated(kind),
+                _ => &[],
+            };
+            if files.is_empty() {
+                return Err(BeaverError::NoGeneratedFiles { target: target.name().to_string(), kind });
+            }
+            return Ok(files.to_vec());
+        })
+    }
+
+    fn register_build_steps<Builder: BackendBuilder<'static>>(&self, builder: &mut Builder) -> crate::Result<()> {
+        let steps = self.build_steps.lock().map_err(|err| BeaverError::LockError(err.to_string()))?;
+        if steps.is_empty() {
+            return Ok(());
+        }
+
+        let mut scope = builder.new_scope();
+        #[cfg(debug_assertions)] {
+            scope.add_comment("Build steps")?;
+        }
+        for step in steps.iter() {
+            step.register(builder, &mut scope, self)?;
+        }
+        builder.apply_scope(scope);
+
+        return Ok(());
+    }
+
     /// Used by the CLI
     pub fn fmt_debug(&self, str: &mut String) -> crate::Result<()> {
         let current_project_index = self.current_project_index();
@@ -949,6 +1475,9 @@ impl Beaver {
 
             for target in project.targets().unwrap().iter() {
                 f.write_fmt(format_args!("  {}", target.name()))?;
+                if target.is_test() {
+                    f.write_str(" [test]")?;
+                }
                 if options.artifacts {
                     f.write_fmt(format_args!(" ({})", target.artifacts().iter().map(|artifact| match artifact {
                         ArtifactType::Library(library_artifact_type) => library_artifact_type.to_string(),
@@ -987,6 +1516,18 @@ impl Beaver {
                         f.write_fmt(format_args!("    Sources: {}\n", sources))?;
                     }
                 }
+                let custom_attributes = match target {
+                    AnyTarget::Library(AnyLibrary::Custom(library)) => library.debug_attributes(),
+                    AnyTarget::Executable(AnyExecutable::Custom(executable)) => executable.debug_attributes(),
+                    _ => Vec::new(),
+                };
+                for (key, value) in custom_attributes {
+                    match key {
+                        "inputs" => f.write_fmt(format_args!("    Inputs: {}\n", value))?,
+                        "outputs" => f.write_fmt(format_args!("    Outputs: {}\n", value))?,
+                        _ => {}
+                    }
+                }
             }
         }
 
@@ -1014,6 +1555,9 @@ impl Beaver {
                     headers.as_ref().map(|paths| paths.iter().map(|path| path.to_str().unwrap()).join(", ")).unwrap_or(String::from(""))
                 ))?;
             },
+            Dependency::PkgConfig { name, version_requirement, .. } => {
+                f.write_fmt(format_args!("      - pkgconfig: {}{}\n", name, version_requirement.as_ref().map(|req| format!(" {}", req)).unwrap_or_default()))?;
+            },
             Dependency::CMakeId(id) => {
                 let target_name = self.with_cmake_project_and_library(id, |_, target| {
                     Ok(target.unwrap().name().to_string())
@@ -1045,10 +1589,15 @@ impl Drop for Beaver {
     fn drop(&mut self) {
         if self.comm_socket.0.get().is_some() {
             if let Some(socket) = self.comm_socket.0.take() {
-                socket.send("close").unwrap();
+                // Once beaver is being dropped, the socket closes without reading the request
+                if let Err(err) = socket.send("close") {
+                    trace!(target: "communication", "close request: {}", err);
+                }
                 #[cfg(unix)] {
                     use program_communicator::socket::SocketUnixExt;
-                    socket.wait().unwrap();
+                    if let Err(err) = socket.wait() {
+                        error!(target: "communication", "{}", err);
+                    }
                 }
             }
         }
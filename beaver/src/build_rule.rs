@@ -0,0 +1,121 @@
+//! Rules and build steps defined by a script. They are added to the build file as they are, so
+//! that generating files becomes part of the build graph.
+
+use std::path::{Path, PathBuf};
+
+use crate::backend::{BackendBuilder, BackendBuilderScope, BuildStep, Rule};
+use crate::target::Dependency;
+use crate::{Beaver, BeaverError};
+
+/// Options which can be set on a rule. `command` is required.
+const RULE_OPTIONS: &[&str] = &[
+    "command",
+    "description",
+    "depfile",
+    "deps",
+    "generator",
+    "restat",
+    "rspfile",
+    "rspfile_content",
+];
+
+/// Names which can't be used for the variables of a build step, because they are set by the backend
+const RESERVED_VARIABLES: &[&str] = &["in", "out"];
+
+/// Create a rule from the options passed by a script. Rules are defined once per script, so
+/// their strings are leaked to satisfy the lifetime the backends require.
+pub(crate) fn new_rule(name: &str, options: Vec<(String, String)>) -> crate::Result<&'static Rule> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(BeaverError::InvalidRuleName(name.to_string()));
+    }
+
+    if let Some((option, _)) = options.iter().find(|(option, _)| !RULE_OPTIONS.contains(&option.as_str())) {
+        return Err(BeaverError::InvalidRuleOption { rule: name.to_string(), option: option.clone() });
+    }
+    if !options.iter().any(|(option, _)| option == "command") {
+        return Err(BeaverError::RuleWithoutCommand(name.to_string()));
+    }
+
+    let leak = |str: String| -> &'static str { Box::leak(str.into_boxed_str()) };
+    let rule = Rule {
+        // Prefixed so that scripts can't shadow the rules used by targets
+        name: leak(format!("rule_{}", name)),
+        options: options.into_iter()
+            .map(|(option, value)| (leak(option), leak(value)))
+            .collect(),
+        pool: None
+    };
+
+    return Ok(Box::leak(Box::new(rule)));
+}
+
+/// A build step defined by a script with `build`
+#[derive(Debug)]
+pub struct RuleBuildStep {
+    pub(crate) rule: &'static Rule,
+    /// absolute paths
+    pub(crate) inputs: Vec<PathBuf>,
+    /// absolute paths
+    pub(crate) outputs: Vec<PathBuf>,
+    /// Dependencies which have to be built before this step, but aren't passed as `$in`
+    pub(crate) dependencies: Vec<Dependency>,
+    /// Variables available to the command of the rule (e.g. `$outdir`)
+    pub(crate) variables: Vec<(String, String)>,
+}
+
+impl RuleBuildStep {
+    /// Relative inputs are resolved against `base_dir` and relative outputs against `build_dir`
+    pub(crate) fn new(
+        rule: &'static Rule,
+        inputs: &[PathBuf],
+        outputs: &[PathBuf],
+        dependencies: Vec<Dependency>,
+        variables: Vec<(String, String)>,
+        base_dir: &Path,
+        build_dir: &Path,
+    ) -> crate::Result<RuleBuildStep> {
+        if outputs.is_empty() {
+            return Err(BeaverError::BuildStepWithoutOutputs(rule.name.to_string()));
+        }
+        if let Some((variable, _)) = variables.iter().find(|(variable, _)| RESERVED_VARIABLES.contains(&variable.as_str())) {
+            return Err(BeaverError::ReservedBuildStepVariable(variable.clone()));
+        }
+
+        let resolve = |paths: &[PathBuf], dir: &Path| paths.iter()
+            .map(|path| {
+                let path = std::path::absolute(dir.join(path))?;
+                crate::path::path_to_str(&path)?;
+                Ok(path)
+            })
+            .collect::<crate::Result<Vec<PathBuf>>>();
+
+        return Ok(RuleBuildStep {
+            rule,
+            inputs: resolve(inputs, base_dir)?,
+            outputs: resolve(outputs, build_dir)?,
+            dependencies,
+            variables,
+        });
+    }
+
+    pub fn outputs(&self) -> &[PathBuf] {
+        &self.outputs
+    }
+
+    pub(crate) fn register<Builder: BackendBuilder<'static>>(&self, builder: &mut Builder, scope: &mut Builder::Scope, context: &Beaver) -> crate::Result<()> {
+        builder.add_rule_if_not_exists(self.rule);
+
+        let mut dependencies = Vec::new();
+        for dependency in self.dependencies.iter() {
+            dependency.ninja_names(context, &mut dependencies)?;
+        }
+
+        scope.add_step(&BuildStep::Generate {
+            rule: self.rule,
+            outputs: &self.outputs.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+            input: &self.inputs.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+            dependencies: &dependencies.iter().map(|str| str.as_str()).collect::<Vec<&str>>(),
+            options: &self.variables.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<Vec<(&str, &str)>>(),
+        })
+    }
+}
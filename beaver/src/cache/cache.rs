@@ -1,5 +1,5 @@
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 use log::*;
@@ -19,6 +19,8 @@ pub struct Cache {
     file_update_list: Mutex<HashSet<String>>,
     /// Custom key value store
     variables: sled::Tree,
+    /// Files written by `beaver install`, removed by `beaver uninstall`
+    installed_files: sled::Tree,
 }
 
 impl Cache {
@@ -32,13 +34,15 @@ impl Cache {
         let files = db.open_tree(b"files")?;
         let concrete_files = db.open_tree(b"concrete_files")?;
         let variables = db.open_tree(b"variables")?;
+        let installed_files = db.open_tree(b"installed_files")?;
 
         Ok(Self {
             db: db,
             files,
             concrete_files,
             file_update_list: Mutex::new(HashSet::new()),
-            variables
+            variables,
+            installed_files
         })
     }
 
@@ -230,7 +234,28 @@ impl Cache {
             .map_err(|err| err.into())
     }
 
-    /// Remove all data from the database
+    pub fn add_installed_file(&self, file: &Path) -> crate::Result<()> {
+        let file = file.to_str().ok_or(BeaverError::NonUTF8OsStr(file.as_os_str().to_os_string()))?;
+        self.installed_files.insert(file, &[])?;
+        Ok(())
+    }
+
+    pub fn remove_installed_file(&self, file: &Path) -> crate::Result<()> {
+        let file = file.to_str().ok_or(BeaverError::NonUTF8OsStr(file.as_os_str().to_os_string()))?;
+        self.installed_files.remove(file)?;
+        Ok(())
+    }
+
+    pub fn installed_files(&self) -> crate::Result<Vec<PathBuf>> {
+        self.installed_files.iter()
+            .map(|entry| {
+                let (key, _) = entry?;
+                Ok(PathBuf::from(String::from_utf8(key.to_vec())?))
+            })
+            .collect()
+    }
+
+    /// Remove all data from the database, except for the install manifest
     pub fn reset(&self) -> Result<(), BeaverError> {
         self.concrete_files.clear()?;
         self.files.clear()?;
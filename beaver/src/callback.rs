@@ -0,0 +1,25 @@
+//! Build commands of custom targets call back into the beaver process which started the build,
+//! using `beaver callback`.
+
+use crate::BeaverError;
+
+/// The environment variable containing the socket of the beaver process which started the build
+pub const SOCKET_ENV: &str = "BEAVER_CALLBACK_SOCKET";
+
+/// Send `request` to the beaver process which started the build and write its output to stdout
+/// and stderr. Returns the exit status of the request.
+pub fn request(request: &str) -> crate::Result<i32> {
+    let Some(socket) = std::env::var_os(SOCKET_ENV) else {
+        return Err(BeaverError::CallbackSocketNotSet);
+    };
+
+    #[cfg(unix)] {
+        let socket = std::path::PathBuf::from(socket);
+        return program_communicator::socket::request(&socket, request, &mut std::io::stdout(), &mut std::io::stderr())
+            .map_err(|err| BeaverError::io(err, format!("while communicating with {:?}", socket)));
+    }
+    #[cfg(not(unix))] {
+        _ = socket;
+        return Err(BeaverError::CallbackUnsupportedPlatform);
+    }
+}
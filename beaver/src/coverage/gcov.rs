@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::tools::{self, CCVersion};
+use crate::BeaverError;
+
+/// Line coverage of a single source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileCoverage {
+    pub file: PathBuf,
+    /// The number of executable lines
+    pub lines: usize,
+    pub executed_lines: usize,
+}
+
+impl FileCoverage {
+    pub fn percentage(&self) -> f64 {
+        if self.lines == 0 {
+            return 100.0;
+        }
+        return self.executed_lines as f64 / self.lines as f64 * 100.0;
+    }
+}
+
+/// The coverage data files (`.gcda`) written by instrumented programs in `dir`
+pub(crate) fn find_gcda(dir: &Path) -> crate::Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> crate::Result<()> {
+        let entries = fs::read_dir(dir)
+            .map_err(|err| BeaverError::io(err, format!("while reading {}", dir.display())))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, out)?;
+            } else if path.extension().is_some_and(|ext| ext == "gcda") {
+                out.push(path);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    if dir.exists() {
+        walk(dir, &mut files)?;
+    }
+    files.sort();
+    return Ok(files);
+}
+
+/// Remove the counters of previous runs, which would otherwise be added to the next run's
+pub(crate) fn remove_gcda(dir: &Path) -> crate::Result<()> {
+    for file in find_gcda(dir)? {
+        fs::remove_file(&file)
+            .map_err(|err| BeaverError::io(err, format!("while removing {}", file.display())))?;
+    }
+    return Ok(());
+}
+
+/// `gcov`, or its llvm equivalent when compiling with clang. The data files of both compilers
+/// aren't compatible.
+pub(crate) fn gcov_command() -> Command {
+    match &*tools::cc_version {
+        CCVersion::Clang(_) | CCVersion::Emscripten(_) => {
+            let mut command = Command::new(tools::llvm_cov.as_path());
+            command.arg("gcov");
+            command
+        },
+        _ => Command::new(tools::gcov.as_path())
+    }
+}
+
+/// Line coverage of all source files compiled into `gcda_files`
+pub(crate) fn gcov_summary(gcda_files: &[PathBuf], build_dir: &Path) -> crate::Result<Vec<FileCoverage>> {
+    let output = gcov_command()
+        // Only print the summary, don't write .gcov files
+        .arg("-n")
+        .args(gcda_files)
+        .current_dir(build_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(BeaverError::NonZeroExitStatus(output.status));
+    }
+
+    return Ok(parse_gcov_summary(&String::from_utf8(output.stdout)?));
+}
+
+/// Parse the output of `gcov -n`:
+///
+/// ```txt
+/// File '/path/to/source.c'
+/// Lines executed:75.00% of 4
+/// ```
+///
+/// Headers are reported once for every object file including them. These reports are merged by
+/// keeping the most covered one, as the summary doesn't contain line information.
+fn parse_gcov_summary(output: &str) -> Vec<FileCoverage> {
+    let mut files: Vec<FileCoverage> = Vec::new();
+    let mut current_file: Option<PathBuf> = None;
+    for line in output.lines() {
+        if let Some(file) = line.strip_prefix("File '").and_then(|line| line.strip_suffix("'")) {
+            current_file = Some(PathBuf::from(file));
+        } else if let Some(summary) = line.strip_prefix("Lines executed:") {
+            // The last summary line is the total of all files
+            let Some(file) = current_file.take() else { continue };
+            let Some((percentage, lines)) = summary.split_once("% of ") else { continue };
+            let (Ok(percentage), Ok(lines)) = (percentage.trim().parse::<f64>(), lines.trim().parse::<usize>()) else { continue };
+            let coverage = FileCoverage {
+                file,
+                lines,
+                executed_lines: (percentage / 100.0 * lines as f64).round() as usize
+            };
+
+            match files.iter_mut().find(|existing| existing.file == coverage.file) {
+                Some(existing) => if coverage.executed_lines > existing.executed_lines {
+                    *existing = coverage;
+                },
+                None => files.push(coverage)
+            }
+        } else if line.starts_with("No executable lines") {
+            current_file = None;
+        }
+    }
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+    return files;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse_gcov_summary, FileCoverage};
+
+    #[test]
+    fn parse_summary() {
+        let output = "File '/src/main.c'\nLines executed:75.00% of 4\nCreating 'main.c.gcov'\n\nFile '/src/util.h'\nLines executed:0.00% of 2\n\nFile '/src/util.h'\nLines executed:50.00% of 2\n\nFile '/src/empty.h'\nNo executable lines\n\nLines executed:66.67% of 6\n";
+        assert_eq!(parse_gcov_summary(output), vec![
+            FileCoverage { file: PathBuf::from("/src/main.c"), lines: 4, executed_lines: 3 },
+            FileCoverage { file: PathBuf::from("/src/util.h"), lines: 2, executed_lines: 1 },
+        ]);
+    }
+}
@@ -0,0 +1,4 @@
+use utils::moduse;
+
+moduse!(gcov);
+moduse!(report);
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::tools::{self, CCVersion};
+use crate::BeaverError;
+
+use super::FileCoverage;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum CoverageFormat {
+    /// A summary of the line coverage per file, printed to stdout
+    #[default]
+    Text,
+    /// An lcov tracefile (`coverage.info`)
+    Lcov,
+    /// An HTML report generated by `genhtml`
+    Html,
+}
+
+impl CoverageFormat {
+    pub const ALL: [CoverageFormat; 3] = [CoverageFormat::Text, CoverageFormat::Lcov, CoverageFormat::Html];
+
+    fn name(&self) -> &'static str {
+        match self {
+            CoverageFormat::Text => "text",
+            CoverageFormat::Lcov => "lcov",
+            CoverageFormat::Html => "html",
+        }
+    }
+}
+
+impl std::fmt::Display for CoverageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl TryFrom<&str> for CoverageFormat {
+    type Error = BeaverError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value_lowercase = value.to_lowercase();
+        match CoverageFormat::ALL.iter().find(|format| format.name() == value_lowercase) {
+            Some(format) => Ok(*format),
+            None => Err(BeaverError::TryFromStringError {
+                name: "coverage format".to_string(),
+                got: value.to_string(),
+                expected_values: CoverageFormat::ALL.iter().map(|format| format.to_string()).collect()
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CoverageOptions {
+    pub format: CoverageFormat,
+    /// Where to write the report. Defaults to `coverage.info` or `html` in the `coverage`
+    /// directory of the build directory. The text summary is always printed.
+    pub output: Option<PathBuf>,
+}
+
+/// A table with the line coverage of `files` and the total
+pub(crate) fn text_summary(files: &[FileCoverage], base_dir: Option<&Path>) -> String {
+    let names: Vec<String> = files.iter()
+        .map(|file| base_dir
+            .and_then(|base_dir| file.file.strip_prefix(base_dir).ok())
+            .unwrap_or(&file.file)
+            .display().to_string())
+        .collect();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max("File".len()).max("TOTAL".len());
+
+    let mut out = String::new();
+    _ = writeln!(out, "{:<width$}  {:>7}  {:>7}  {:>7}", "File", "Lines", "Exec", "Cover");
+    for (file, name) in files.iter().zip(names.iter()) {
+        _ = writeln!(out, "{:<width$}  {:>7}  {:>7}  {:>6.2}%", name, file.lines, file.executed_lines, file.percentage());
+    }
+    let total = FileCoverage {
+        file: PathBuf::new(),
+        lines: files.iter().map(|file| file.lines).sum(),
+        executed_lines: files.iter().map(|file| file.executed_lines).sum()
+    };
+    _ = writeln!(out, "{:<width$}  {:>7}  {:>7}  {:>6.2}%", "TOTAL", total.lines, total.executed_lines, total.percentage());
+    return out;
+}
+
+fn run(command: &mut Command) -> crate::Result<()> {
+    let status = command.status()?;
+    if !status.success() {
+        return Err(BeaverError::NonZeroExitStatus(status));
+    }
+    return Ok(());
+}
+
+/// Capture the coverage data in `build_dir` into the lcov tracefile `output`, keeping only the
+/// files in `source_dirs`
+pub(crate) fn write_lcov(build_dir: &Path, source_dirs: &[PathBuf], output: &Path) -> crate::Result<()> {
+    let output_dir = output.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(output_dir)
+        .map_err(|err| BeaverError::io(err, format!("while creating {}", output_dir.display())))?;
+
+    let mut capture = Command::new(tools::lcov.as_path());
+    capture.arg("--capture")
+        .arg("--directory").arg(build_dir)
+        .arg("--output-file").arg(output);
+    if matches!(&*tools::cc_version, CCVersion::Clang(_) | CCVersion::Emscripten(_)) {
+        // lcov only accepts a single executable as gcov tool
+        let wrapper = output_dir.join("llvm-gcov.sh");
+        fs::write(&wrapper, format!("#!/bin/sh\nexec \"{}\" gcov \"$@\"\n", tools::llvm_cov.display()))
+            .map_err(|err| BeaverError::io(err, format!("while writing {}", wrapper.display())))?;
+        #[cfg(unix)] {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&wrapper, fs::Permissions::from_mode(0o755))?;
+        }
+        capture.arg("--gcov-tool").arg(&wrapper);
+    }
+    run(&mut capture)?;
+
+    let mut extract = Command::new(tools::lcov.as_path());
+    extract.arg("--extract").arg(output);
+    extract.args(source_dirs.iter().map(|dir| dir.join("*")));
+    extract.arg("--output-file").arg(output);
+    return run(&mut extract);
+}
+
+/// Generate an HTML report from the lcov tracefile `info` in `output_dir`
+pub(crate) fn write_html(info: &Path, output_dir: &Path) -> crate::Result<()> {
+    run(Command::new(tools::genhtml.as_path())
+        .arg(info)
+        .arg("--output-directory").arg(output_dir))
+}
@@ -10,6 +10,7 @@ use target_lexicon::OperatingSystem;
 use utils::str::OsStrConversionError;
 
 use crate::target::{self, ArtifactType, Language};
+use crate::Sanitizer;
 
 #[derive(thiserror::Error)]
 pub enum BeaverError {
@@ -38,10 +39,36 @@ pub enum BeaverError {
     TargetsReadError(String),
     #[error("Cannot use `{0}` on target {1}")]
     TargetOperationNotSupported(String, String),
+    #[error("Target {0} isn't added to a project")]
+    TargetNotInProject(String),
 
     // Run error //
     #[error("No executable artifact found in target '{0}'")]
     NoExecutableArtifact(String),
+    #[error("Targets compiled for {0} can't be run on this host")]
+    CantRunTargetTriple(String),
+
+    // Test //
+    #[error("{failed} of {total} tests failed")]
+    TestsFailed { failed: usize, total: usize },
+
+    // Profiles //
+    #[error("Profile `{0}` is not defined. Custom profiles must be defined before they are used, e.g. before importing projects or setting the build directory")]
+    UndefinedProfile(String),
+    #[error("Profile `{0}` is already defined")]
+    ProfileAlreadyDefined(String),
+    #[error("Can't define a profile named `{0}`, because it is a built-in optimization mode")]
+    ProfileShadowsOptimizationMode(String),
+    #[error("Invalid profile name `{0}`. Profile names can only contain letters, digits, `_` and `-`")]
+    InvalidProfileName(String),
+
+    // Instrumentation //
+    #[error("The {0} and {1} sanitizers can't be used together")]
+    IncompatibleSanitizers(Sanitizer, Sanitizer),
+    #[error("Coverage instrumentation is not enabled; build with `--coverage`")]
+    CoverageNotEnabled,
+    #[error("No coverage data found in {0}; were the tests built with coverage instrumentation?")]
+    NoCoverageData(PathBuf),
 
     // Target Triple //
     #[error("Unknown target OS `{0}`")]
@@ -70,6 +97,14 @@ pub enum BeaverError {
     BufferWriteError(String),
     #[error("Error writing build file: {0}")]
     BuildFileWriteError(io::Error),
+    #[error("Couldn't read build graph {0}: {1}")]
+    BuildGraphParseError(PathBuf, String),
+    #[error("Unknown target '{0}'")]
+    UnknownBuildTarget(String),
+    #[error("Dependency cycle detected at '{0}'")]
+    BuildGraphCycle(String),
+    #[error("Error writing compilation database: {0}")]
+    CompilationDatabaseWriteError(io::Error),
 
     // Symlink //
     #[error("Couldn't create symlink {1} -> {2}: {0}")]
@@ -94,8 +129,12 @@ pub enum BeaverError {
     PkgconfigNotFound(String),
     #[error("Malformed arguments received from pkgconfig: {0}")]
     PkgconfigMalformed(String),
-    #[error("Malformed version requirement for pkgconfig dependency. Valid requirements are for example `>=1.3.4`, `=1.3`, `<=5`")]
+    #[error("Malformed version requirement `{0}` for pkgconfig dependency. Valid operators are `=`, `!=`, `<`, `<=`, `>` and `>=`, e.g. `>=1.3.4`")]
     PkgconfigMalformedVersionRequirement(String),
+    #[error("Couldn't resolve pkg-config package `{0}`: {1}")]
+    PkgconfigResolveError(String, pkgconfig_parser::Error),
+    #[error("Invalid dependency visibility `{0}` (expected public, private or interface)")]
+    InvalidDependencyVisibility(String),
 
     #[error("Error parsing pkg-config file '{0}': {1}")]
     PkgconfigParsingError(PathBuf, pkgconfig_parser::Error),
@@ -137,18 +176,52 @@ pub enum BeaverError {
     InvalidArtifact(ArtifactType, &'static str),
     #[error("Target {1} does not have an artifact of type {0:?}")]
     NoArtifact(ArtifactType, String),
+    #[error("Artifact '{0:?}' does not produce a file")]
+    ArtifactWithoutFile(ArtifactType),
+    #[error("Interface library {0} can't have sources")]
+    InterfaceLibraryWithSources(String),
+    #[error("Interface library {0} can't have a {1} artifact")]
+    InterfaceLibraryArtifact(String, target::LibraryArtifactType),
     /// Custom targets have build commands
     #[error("Target {0} has no build command")]
     TargetHasNoBuildCommand(String),
 
     #[error("Error while parsing C setting '{}': {:?}", .0, .1)]
     CSettingParseError(String, target::c::SettingParseError),
+    #[error("Language standard {0} is not supported by {1}")]
+    UnsupportedLanguageStandard(String, String),
 
     // Custom //
-    #[error("Invalid pipe command {:x?}", .0)]
-    InvalidPipeCommand(u8),
+    #[error("`beaver callback` is used by builds started by beaver to build custom targets ({} is not set)", crate::callback::SOCKET_ENV)]
+    CallbackSocketNotSet,
+    #[error("`beaver callback` is not supported on this platform")]
+    CallbackUnsupportedPlatform,
+    #[error("Invalid callback request `{0}`")]
+    InvalidCallbackRequest(String),
     #[error("Target {0} has no build command")]
     TargetNoBuildCommand(String),
+    #[error("Custom target {0} can't be built: custom targets are not supported on this platform")]
+    CustomTargetUnsupportedPlatform(String),
+    #[error("Target {target} doesn't generate any {kind}")]
+    NoGeneratedFiles { target: String, kind: crate::target::custom::GeneratedKind },
+
+    // Rules //
+    #[error("Invalid rule name `{0}` (only letters, digits, `_` and `-` are allowed)")]
+    InvalidRuleName(String),
+    #[error("Invalid option `{option}` for rule `{rule}`")]
+    InvalidRuleOption { rule: String, option: String },
+    #[error("Rule `{0}` has no command")]
+    RuleWithoutCommand(String),
+    #[error("Rule `{0}` is already defined")]
+    RuleAlreadyDefined(String),
+    #[error("No rule named `{0}` is defined")]
+    UndefinedRule(String),
+    #[error("A build step using {0} has no outputs")]
+    BuildStepWithoutOutputs(String),
+    #[error("`{0}` is set by beaver and can't be used as a variable of a build step")]
+    ReservedBuildStepVariable(String),
+    #[error("{0:?} is already the output of another build step")]
+    DuplicateBuildOutput(PathBuf),
 
     // CMake //
     #[error("CMake failed")]
@@ -165,6 +238,8 @@ pub enum BeaverError {
     CMakeUnknownLanguage(String),
     #[error("CMake target with id '{0}' not found")]
     NoCMakeTarget(String),
+    #[error("Couldn't read the tests of CMake project {0} from ctest: {1}")]
+    CTestListError(String, String),
 
     // Cargo //
     #[error("CargoManifestError: {0}")]
@@ -182,6 +257,14 @@ pub enum BeaverError {
     #[error("Provided pkgconfig path does not exist {}", .0.display())]
     InvalidPkgConfigPath(PathBuf),
 
+    // Install //
+    #[error("The install rpath `{rpath}` of {} is longer than its build rpath ({available} bytes)", file.display())]
+    InstallRpathTooLong { file: PathBuf, rpath: String, available: usize },
+    #[error("{} is not a valid ELF file", .0.display())]
+    InvalidElfFile(PathBuf),
+    #[error("The build rpath `{rpath}` of {} shares its storage with other strings, so it can't be replaced", file.display())]
+    SharedElfRpath { file: PathBuf, rpath: String },
+
     // General Errors //
     #[error("There are no projects defined")]
     NoProjects,
@@ -228,7 +311,7 @@ pub enum BeaverError {
         got: String,
         expected_values: Vec<String>,
     },
-    #[error("Invalid library artifact type `{0}`. Valid artifacts are `dynlib`, `staticlib`, `pkgconfig`, `framework` and `xcframework`")]
+    #[error("Invalid library artifact type `{0}`. Valid artifacts are `dynlib`, `staticlib`, `pkgconfig`, `module`, `objects`, `interface`, `framework` and `xcframework`")]
     InvalidLibraryArtifactType(String),
     #[error("Invalid executable artifact type `{0}`. Valid artifacts are `executable` and `app`")]
     InvalidExecutableArtifactType(String),
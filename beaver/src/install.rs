@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use log::*;
+
+use crate::BeaverError;
+
+/// Installation directories relative to the prefix, following the GNUInstallDirs layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallDirs {
+    pub bindir: PathBuf,
+    pub libdir: PathBuf,
+    pub includedir: PathBuf,
+    pub datadir: PathBuf,
+}
+
+impl Default for InstallDirs {
+    fn default() -> Self {
+        InstallDirs {
+            bindir: PathBuf::from("bin"),
+            libdir: PathBuf::from("lib"),
+            includedir: PathBuf::from("include"),
+            datadir: PathBuf::from("share"),
+        }
+    }
+}
+
+impl InstallDirs {
+    pub fn pkgconfigdir(&self) -> PathBuf {
+        self.libdir.join("pkgconfig")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    /// The prefix the files are installed in, e.g. `/usr/local`
+    pub prefix: PathBuf,
+    /// Stage the installation in this directory, e.g. for packaging. Files are installed in
+    /// `destdir/prefix`, but are configured to be used from `prefix`.
+    pub destdir: Option<PathBuf>,
+    pub dirs: InstallDirs,
+    /// Replaces the rpaths pointing to the build directory in installed executables. Defaults to
+    /// the library directory, relative to the executable.
+    pub rpath: Option<String>,
+}
+
+impl InstallOptions {
+    pub fn new(prefix: PathBuf) -> InstallOptions {
+        InstallOptions { prefix, destdir: None, dirs: InstallDirs::default(), rpath: None }
+    }
+
+    /// The rpath of installed executables. `origin` is the token the dynamic loader replaces by
+    /// the directory of the executable (e.g. `$ORIGIN`).
+    pub fn install_rpath(&self, origin: &str) -> String {
+        if let Some(rpath) = &self.rpath {
+            return rpath.clone();
+        }
+
+        return match pathdiff::diff_paths(&self.dirs.libdir, &self.dirs.bindir) {
+            Some(libdir) if libdir.as_os_str().is_empty() => origin.to_string(),
+            Some(libdir) if libdir.is_relative() => format!("{}/{}", origin, libdir.display()),
+            _ => self.installed_path(&self.dirs.libdir).display().to_string()
+        };
+    }
+
+    /// The path of `destination` (relative to the prefix) once the files are installed
+    pub fn installed_path(&self, destination: &Path) -> PathBuf {
+        self.prefix.join(destination)
+    }
+
+    /// The path `destination` (relative to the prefix) is written to
+    pub fn staged_path(&self, destination: &Path) -> PathBuf {
+        let installed = self.installed_path(destination);
+        match &self.destdir {
+            // Joining an absolute path would replace destdir
+            Some(destdir) => destdir.join(installed.components()
+                .filter(|component| !matches!(component, Component::RootDir | Component::Prefix(_)))
+                .collect::<PathBuf>()),
+            None => installed
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallKind {
+    Executable,
+    SharedLibrary,
+    StaticLibrary,
+    Header,
+    PkgConfig,
+}
+
+impl InstallKind {
+    /// The permissions of the installed file
+    pub fn mode(&self) -> u32 {
+        match self {
+            InstallKind::Executable | InstallKind::SharedLibrary => 0o755,
+            InstallKind::StaticLibrary | InstallKind::Header | InstallKind::PkgConfig => 0o644,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InstallSource {
+    File(PathBuf),
+    /// A file generated for the installation, e.g. a pkg-config file pointing to the prefix
+    Contents(String),
+    /// A symbolic link to this path, relative to the directory of the link
+    Symlink(PathBuf),
+    /// An executable whose rpaths pointing to the build directory are replaced by the install rpath
+    Binary { file: PathBuf, build_rpaths: Vec<String> },
+}
+
+/// A file installed by a target
+#[derive(Debug, Clone)]
+pub struct InstallFile {
+    pub source: InstallSource,
+    /// Relative to the prefix
+    pub destination: PathBuf,
+    pub kind: InstallKind,
+}
+
+/// Header files in `dir` (recursively), with their path relative to `dir`
+pub(crate) fn collect_headers(dir: &Path) -> crate::Result<Vec<(PathBuf, PathBuf)>> {
+    const HEADER_EXTENSIONS: &[&str] = &["h", "hh", "hpp", "hxx", "h++", "inl", "ipp", "tpp"];
+
+    fn walk(base: &Path, dir: &Path, out: &mut Vec<(PathBuf, PathBuf)>) -> crate::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .map_err(|err| BeaverError::io(err, format!("while reading header directory {}", dir.display())))?
+            .collect::<Result<Vec<fs::DirEntry>, std::io::Error>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(base, &path, out)?;
+            } else if path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| HEADER_EXTENSIONS.contains(&ext)) {
+                let relative = path.strip_prefix(base).unwrap().to_path_buf();
+                out.push((path, relative));
+            }
+        }
+        return Ok(());
+    }
+
+    let mut headers = Vec::new();
+    if dir.is_file() {
+        headers.push((dir.to_path_buf(), PathBuf::from(dir.file_name().unwrap())));
+    } else if dir.is_dir() {
+        walk(dir, dir, &mut headers)?;
+    } else {
+        warn!("Header directory {} doesn't exist", dir.display());
+    }
+    return Ok(headers);
+}
+
+/// Install `file`, returning the path it was written to
+pub(crate) fn install_file(file: &InstallFile, options: &InstallOptions) -> crate::Result<PathBuf> {
+    let destination = options.staged_path(&file.destination);
+    if let Some(dir) = destination.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| BeaverError::io(err, format!("while creating {}", dir.display())))?;
+    }
+
+    // Replace the file instead of overwriting it, so that running executables or loaded
+    // libraries aren't modified
+    if destination.symlink_metadata().is_ok() {
+        fs::remove_file(&destination)
+            .map_err(|err| BeaverError::io(err, format!("while removing {}", destination.display())))?;
+    }
+
+    match &file.source {
+        InstallSource::File(source) => {
+            fs::copy(source, &destination)
+                .map_err(|err| BeaverError::io(err, format!("while copying {} to {}", source.display(), destination.display())))?;
+        },
+        InstallSource::Binary { file: source, build_rpaths } => {
+            fs::copy(source, &destination)
+                .map_err(|err| BeaverError::io(err, format!("while copying {} to {}", source.display(), destination.display())))?;
+            crate::platform::replace_rpaths(&destination, build_rpaths, |origin| options.install_rpath(origin))?;
+        },
+        InstallSource::Contents(contents) => {
+            fs::write(&destination, contents)
+                .map_err(|err| BeaverError::io(err, format!("while writing {}", destination.display())))?;
+        },
+        InstallSource::Symlink(target) => {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(target, &destination)
+                .map_err(|err| BeaverError::io(err, format!("while linking {} to {}", destination.display(), target.display())))?;
+            #[cfg(not(unix))]
+            fs::copy(destination.parent().unwrap().join(target), &destination)
+                .map_err(|err| BeaverError::io(err, format!("while copying {} to {}", target.display(), destination.display())))?;
+            // The permissions are those of the file it links to
+            return Ok(destination);
+        }
+    }
+
+    #[cfg(unix)] {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&destination, fs::Permissions::from_mode(file.kind.mode()))
+            .map_err(|err| BeaverError::io(err, format!("while setting permissions of {}", destination.display())))?;
+    }
+
+    return Ok(destination);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::InstallOptions;
+
+    #[test]
+    fn staged_path() {
+        let mut options = InstallOptions::new(PathBuf::from("/usr/local"));
+        assert_eq!(options.staged_path(Path::new("lib/libA.a")), PathBuf::from("/usr/local/lib/libA.a"));
+        options.destdir = Some(PathBuf::from("/tmp/stage"));
+        assert_eq!(options.staged_path(Path::new("lib/libA.a")), PathBuf::from("/tmp/stage/usr/local/lib/libA.a"));
+        assert_eq!(options.installed_path(Path::new("lib/libA.a")), PathBuf::from("/usr/local/lib/libA.a"));
+    }
+
+    #[test]
+    fn install_rpath() {
+        let mut options = InstallOptions::new(PathBuf::from("/usr/local"));
+        assert_eq!(options.install_rpath("$ORIGIN"), "$ORIGIN/../lib");
+        options.dirs.libdir = PathBuf::from("bin");
+        assert_eq!(options.install_rpath("@loader_path"), "@loader_path");
+        options.dirs.libdir = PathBuf::from("/opt/lib");
+        assert_eq!(options.install_rpath("$ORIGIN"), "/opt/lib");
+        options.rpath = Some("/usr/lib/app".to_string());
+        assert_eq!(options.install_rpath("$ORIGIN"), "/usr/lib/app");
+    }
+}
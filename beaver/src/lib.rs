@@ -4,18 +4,25 @@
 use utils::moduse;
 
 pub mod backend;
+pub mod callback;
 pub(crate) mod platform;
 pub(crate) mod cache;
 pub(crate) mod triple;
 pub(crate) mod path;
 pub mod phase_hook;
 pub mod command;
+pub mod coverage;
+pub mod install;
 pub mod project;
 pub mod target;
+pub mod testing;
 pub mod tools;
 moduse!(beaver);
+moduse!(build_rule);
 moduse!(error);
 moduse!(optimization_mode);
+moduse!(profile);
+moduse!(sanitizer);
 
 pub mod traits {
     pub use crate::project::traits::*;
@@ -1,75 +1,114 @@
 use std::ffi::{self, OsString};
-use std::str::FromStr;
 
 use lazy_static::lazy_static;
 
 use crate::tools::{self, CCVersion};
 use crate::BeaverError;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
 pub enum OptimizationMode {
+    #[default]
     Debug,
-    Release
-    // TODO:
-    // - Size (-Os)
-    // - Fast (-Ofast)
-    // - MinimalSize (-Oz)
+    Release,
+    /// Optimized, with debug info (-O2 -g)
+    RelWithDebInfo,
+    /// Optimize for size (-Os)
+    Size,
+    /// Optimize aggressively for size (-Oz)
+    MinSize,
+    /// Optimize for speed, disregarding strict standards compliance (-Ofast)
+    Fast,
 }
 
 use OptimizationMode::*;
 
+/// Append link time optimization flags to `flags`
+fn with_lto(flags: &[&'static str]) -> Vec<&'static str> {
+    let mut v = flags.to_vec();
+    v.push("-flto");
+    match &*tools::cc_version {
+        CCVersion::Clang(ver) => if ver.major >= 18 { v.push("-ffat-lto-objects") },
+        CCVersion::Gcc(_) => v.push("-ffat-lto-objects"),
+        _ => {}
+    }
+    return v;
+}
+
 // TODO: -march=native -msse4.1 -> C settings (in project?) (https://www.youtube.com/watch?v=egG5Kraswhc)
 lazy_static! {
-    static ref cflags_release: Vec<&'static str> = {
-        let mut v = ["-O3", "-flto", "-DNDEBUG"].to_vec();
-        match &*tools::cc_version {
-            CCVersion::Clang(ver) => if ver.major >= 18 { v.push("-ffat-lto-objects") },
-            CCVersion::Gcc(_) => v.push("-ffat-lto-objects"),
-            _ => {}
-        }
-        return v;
-    };
-
-    static ref linker_flags_release: Vec<&'static str> = {
-        let mut v = ["-O3", "-flto"].to_vec();
-        match &*tools::cc_version {
-            CCVersion::Clang(ver) => if ver.major >= 18 { v.push("-ffat-lto-objects") },
-            CCVersion::Gcc(_) => v.push("-ffat-lto-objects"),
-            _ => {}
-        }
-        return v;
-    };
+    static ref cflags_release: Vec<&'static str> = with_lto(&["-O3", "-DNDEBUG"]);
+    static ref linker_flags_release: Vec<&'static str> = with_lto(&["-O3"]);
+    static ref cflags_size: Vec<&'static str> = with_lto(&["-Os", "-DNDEBUG"]);
+    static ref linker_flags_size: Vec<&'static str> = with_lto(&["-Os"]);
+    static ref cflags_min_size: Vec<&'static str> = with_lto(&["-Oz", "-DNDEBUG"]);
+    static ref linker_flags_min_size: Vec<&'static str> = with_lto(&["-Oz"]);
+    static ref cflags_fast: Vec<&'static str> = with_lto(&["-Ofast", "-DNDEBUG"]);
+    static ref linker_flags_fast: Vec<&'static str> = with_lto(&["-Ofast"]);
 }
 
 /// see: [Options That Control Optimization](https://gcc.gnu.org/onlinedocs/gcc/Optimize-Options.html)
 impl OptimizationMode {
+    pub const ALL: [OptimizationMode; 6] = [Debug, Release, RelWithDebInfo, Size, MinSize, Fast];
+
     // Flags //
     pub fn cflags(&self) -> &[&str] {
         match self {
             Debug => &["-g", "-O0"],
             Release => cflags_release.as_slice(),
+            RelWithDebInfo => &["-O2", "-g", "-DNDEBUG"],
+            Size => cflags_size.as_slice(),
+            MinSize => cflags_min_size.as_slice(),
+            Fast => cflags_fast.as_slice(),
         }
     }
 
     pub fn linker_flags(&self) -> &[&str] {
         match self {
             Debug => &["-g", "-O0"],
-            Release => linker_flags_release.as_slice()
+            Release => linker_flags_release.as_slice(),
+            RelWithDebInfo => &["-O2", "-g"],
+            Size => linker_flags_size.as_slice(),
+            MinSize => linker_flags_min_size.as_slice(),
+            Fast => linker_flags_fast.as_slice(),
         }
     }
 
+    /// Cargo only has a `dev` and `release` profile built in, the other profiles are defined
+    /// on the command line
     pub fn cargo_flags(&self) -> &[&str] {
         match self {
             Debug => &[],
-            Release => &["--release"],
+            Release | Fast => &["--release"],
+            RelWithDebInfo => &[
+                "--profile", "relwithdebinfo",
+                "--config", "profile.relwithdebinfo.inherits='release'",
+                "--config", "profile.relwithdebinfo.debug=true",
+            ],
+            Size => &[
+                "--profile", "size",
+                "--config", "profile.size.inherits='release'",
+                "--config", "profile.size.opt-level='s'",
+            ],
+            MinSize => &[
+                "--profile", "minsize",
+                "--config", "profile.minsize.inherits='release'",
+                "--config", "profile.minsize.opt-level='z'",
+            ],
         }
     }
 
+    /// `cargo_flags` quoted for use in a shell command
+    pub(crate) fn cargo_flags_quoted(&self) -> String {
+        shlex::try_join(self.cargo_flags().iter().copied()).expect("cargo flags don't contain nul bytes")
+    }
+
     // Names //
     pub fn cmake_name(&self) -> &'static str {
         match self {
             Debug => "Debug",
-            Release => "Release",
+            Release | Fast => "Release",
+            RelWithDebInfo => "RelWithDebInfo",
+            Size | MinSize => "MinSizeRel",
         }
     }
 
@@ -77,24 +116,41 @@ impl OptimizationMode {
         match self {
             Debug => "debug",
             Release => "release",
+            RelWithDebInfo => "relwithdebinfo",
+            Size => "size",
+            MinSize => "minsize",
+            Fast => "fast",
         }
     }
 
+    /// The name of the cargo profile, which is also the name of its output directory
     pub fn cargo_name(&self) -> &'static str {
-        self.lowercase_name()
+        match self {
+            Release | Fast => "release",
+            _ => self.lowercase_name()
+        }
     }
 
     pub fn swift_name(&self) -> &'static str {
-        self.lowercase_name()
+        match self {
+            Debug => "debug",
+            _ => "release",
+        }
+    }
+
+    pub fn meson_buildtype(&self) -> &'static str {
+        match self {
+            Debug => "debug",
+            Release | Fast => "release",
+            RelWithDebInfo => "debugoptimized",
+            Size | MinSize => "minsize",
+        }
     }
 }
 
 impl std::fmt::Display for OptimizationMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Debug => f.write_str("debug"),
-            Release => f.write_str("release"),
-        }
+        f.write_str(self.lowercase_name())
     }
 }
 
@@ -102,38 +158,26 @@ impl TryFrom<&str> for OptimizationMode {
     type Error = BeaverError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value.to_lowercase().as_str() {
-            "debug" => Ok(Debug),
-            "release" => Ok(Release),
-            _ => Err(BeaverError::TryFromStringError {
+        let value_lowercase = value.to_lowercase();
+        match OptimizationMode::ALL.iter().find(|mode| mode.lowercase_name() == value_lowercase) {
+            Some(mode) => Ok(*mode),
+            None => Err(BeaverError::TryFromStringError {
                 name: "optimization mode".to_string(),
                 got: value.to_string(),
-                expected_values: vec!["debug".to_string(), "release".to_string()]
+                expected_values: OptimizationMode::ALL.iter().map(|mode| mode.to_string()).collect()
             })
         }
     }
 }
 
-impl Into<String> for OptimizationMode {
-    fn into(self) -> String {
-        match self {
-            Debug => "debug".to_string(),
-            Release => "release".to_string(),
-        }
+impl From<OptimizationMode> for String {
+    fn from(mode: OptimizationMode) -> String {
+        mode.lowercase_name().to_string()
     }
 }
 
-impl Into<ffi::OsString> for OptimizationMode {
-    fn into(self) -> OsString {
-            match self {
-                Debug => OsString::from_str("debug").unwrap(),
-                Release => OsString::from_str("release").unwrap(),
-            }
-        }
-}
-
-impl Default for OptimizationMode {
-    fn default() -> Self {
-        Self::Debug
+impl From<OptimizationMode> for ffi::OsString {
+    fn from(mode: OptimizationMode) -> OsString {
+        OsString::from(mode.lowercase_name())
     }
 }
@@ -5,6 +5,8 @@ pub enum Phase {
     Build,
     Run,
     Clean,
+    Test,
+    Install,
 }
 
 impl TryFrom<&str> for Phase {
@@ -15,6 +17,8 @@ impl TryFrom<&str> for Phase {
             "build" => Ok(Phase::Build),
             "run" => Ok(Phase::Run),
             "clean" => Ok(Phase::Clean),
+            "test" => Ok(Phase::Test),
+            "install" => Ok(Phase::Install),
             _ => Err(BeaverError::InvalidPhase(value.to_string()))
         }
     }
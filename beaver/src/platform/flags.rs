@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use target_lexicon::OperatingSystem::{self, *};
 
 use crate::BeaverError;
@@ -53,3 +55,179 @@ pub fn dynlib_linker_flags_for_os(os: &OperatingSystem) -> crate::Result<&[&str]
         _ => Err(BeaverError::UnknownTargetOS(os.clone()))
     }
 }
+
+/// Flags setting the name a dynamic library is loaded by (its soname) and its version. On macOS
+/// the versions are only passed when they are numeric (e.g. `1.2.3`).
+pub fn soname_linker_flags_for_os(os: &OperatingSystem, soname: &str, version: &str, abi_version: &str) -> Vec<String> {
+    let numeric = |version: &str| version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => {
+            let mut flags = vec!["-install_name".to_string(), format!("@rpath/{}", soname)];
+            if numeric(version) {
+                flags.extend(["-current_version".to_string(), version.to_string()]);
+            }
+            if numeric(abi_version) {
+                flags.extend(["-compatibility_version".to_string(), abi_version.to_string()]);
+            }
+            flags
+        },
+
+        Windows |
+        Emscripten |
+        Nebulet	|
+        Wasi |
+        WasiP1 |
+        WasiP2 => Vec::new(),
+
+        _ => vec![format!("-Wl,-soname,{}", soname)]
+    }
+}
+
+/// The minimum length of the rpath of an ELF executable in the build tree. The rpath is
+/// replaced in place on installation, so it has to be long enough to hold the install rpath.
+pub const RPATH_RESERVED_LENGTH: usize = 128;
+
+/// The token the dynamic loader replaces by the directory of the loading executable in rpaths
+pub fn rpath_origin_for_os(os: &OperatingSystem) -> Option<&'static str> {
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => Some("@loader_path"),
+
+        Windows |
+        Emscripten |
+        Nebulet	|
+        Wasi |
+        WasiP1 |
+        WasiP2 => None,
+
+        _ => Some("$ORIGIN")
+    }
+}
+
+/// Flags adding `rpaths` to the runtime library search path of an executable. On ELF platforms
+/// the rpath is padded with slashes up to `RPATH_RESERVED_LENGTH`, and on macOS room is left in
+/// the header, so that `install` can replace it.
+pub fn rpath_linker_flags_for_os(os: &OperatingSystem, rpaths: &[String]) -> Vec<String> {
+    if rpaths.is_empty() || rpath_origin_for_os(os).is_none() {
+        return Vec::new();
+    }
+
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => {
+            let mut flags: Vec<String> = rpaths.iter().map(|rpath| format!("-Wl,-rpath,{}", rpath)).collect();
+            flags.push("-Wl,-headerpad_max_install_names".to_string());
+            flags
+        },
+
+        _ => {
+            // Trailing slashes don't change the directory, unlike empty entries which refer to
+            // the working directory
+            let mut rpath = rpaths.join(":");
+            while rpath.len() < RPATH_RESERVED_LENGTH {
+                rpath.push('/');
+            }
+            vec![format!("-Wl,-rpath,{}", rpath)]
+        }
+    }
+}
+
+/// The extension and contents of a file limiting the symbols exported by a dynamic library to
+/// `symbols`: a version script, an exported symbols list on macOS or a module-definition file
+/// on Windows. `None` when the OS doesn't support it.
+pub fn exports_file_for_os(os: &OperatingSystem, symbols: &[String]) -> Option<(&'static str, String)> {
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => Some(("exp", symbols.iter().map(|symbol| format!("_{}\n", symbol)).collect())),
+
+        Windows => Some(("def", format!("EXPORTS\n{}", symbols.iter().map(|symbol| format!("    {}\n", symbol)).collect::<String>()))),
+
+        Emscripten |
+        Nebulet	|
+        Wasi |
+        WasiP1 |
+        WasiP2 => None,
+
+        _ => Some(("map", format!("{{\n  global:\n{}  local:\n    *;\n}};\n", symbols.iter().map(|symbol| format!("    {};\n", symbol)).collect::<String>())))
+    }
+}
+
+/// Flags passing a file created by `exports_file_for_os` (or written by the user) to the linker
+pub fn exports_linker_flags_for_os(os: &OperatingSystem, file: &Path) -> Vec<String> {
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => vec![format!("-Wl,-exported_symbols_list,{}", file.display())],
+
+        // The linker reads module-definition files passed as an input
+        Windows => vec![file.display().to_string()],
+
+        Emscripten |
+        Nebulet	|
+        Wasi |
+        WasiP1 |
+        WasiP2 => Vec::new(),
+
+        _ => vec![format!("-Wl,--version-script,{}", file.display())]
+    }
+}
+
+/// Flags for linking loadable modules (plugins). Undefined symbols are resolved against the
+/// executable loading the module.
+pub fn module_linker_flags_for_os(os: &OperatingSystem) -> crate::Result<&[&str]> {
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => Ok(&["-bundle", "-undefined", "dynamic_lookup"]),
+
+        // Shared objects allow undefined symbols by default
+        _ => dynlib_linker_flags_for_os(os)
+    }
+}
+
+/// Flags for compiling the objects of dynamic libraries
+pub fn pic_cflags_for_os(os: &OperatingSystem) -> &'static [&'static str] {
+    match os {
+        // Position independent by default
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) |
+        Windows => &[],
+
+        _ => &["-fPIC"],
+    }
+}
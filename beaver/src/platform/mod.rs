@@ -2,3 +2,4 @@ use crate::moduse;
 
 moduse!(os_extensions);
 moduse!(flags);
+moduse!(rpath);
@@ -53,6 +53,45 @@ pub fn dynlib_extension_for_os(os: &OperatingSystem) -> crate::Result<&'static s
     }
 }
 
+/// The file name of `version` of a dynamic library, e.g. `libfoo.so.1` or `libfoo.1.dylib`.
+/// `None` when dynamic libraries aren't versioned by their file name on `os`.
+pub fn versioned_dynlib_file_name(os: &OperatingSystem, name: &str, version: &str) -> crate::Result<Option<String>> {
+    match os {
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => Ok(Some(format!("lib{}.{}.dylib", name, version))),
+
+        Windows |
+        Emscripten |
+        Nebulet	|
+        Wasi |
+        WasiP1 |
+        WasiP2 => Ok(None),
+
+        _ => Ok(Some(format!("lib{}.{}.{}", name, dynlib_extension_for_os(os)?, version)))
+    }
+}
+
+/// Extension of loadable modules (plugins), which are named without a `lib` prefix
+pub fn module_extension_for_os(os: &OperatingSystem) -> crate::Result<&'static str> {
+    match os {
+        // Bundles are conventionally named .so as well
+        Darwin(_) |
+        IOS(_) |
+        MacOSX(_) |
+        TvOS(_) |
+        VisionOS(_) |
+        WatchOS(_) |
+        XROS(_) => Ok("so"),
+
+        _ => dynlib_extension_for_os(os)
+    }
+}
+
 pub fn staticlib_extension_for_os(os: &OperatingSystem) -> crate::Result<&'static str> {
     match os {
         Windows => Ok("lib"),
@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+use crate::BeaverError;
+
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const DT_SONAME: u64 = 14;
+const DT_RPATH: u64 = 15;
+const DT_RUNPATH: u64 = 29;
+const DT_CONFIG: u64 = 0x6ffffefa;
+const DT_DEPAUDIT: u64 = 0x6ffffefb;
+const DT_AUDIT: u64 = 0x6ffffefc;
+const DT_AUXILIARY: u64 = 0x7ffffffd;
+const DT_FILTER: u64 = 0x7fffffff;
+
+const PT_LOAD: u64 = 1;
+const PT_DYNAMIC: u64 = 2;
+
+/// Replaces the rpaths `build_rpaths` of the executable `file` by an install rpath.
+/// `install_rpath` receives the token the dynamic loader replaces by the directory of the
+/// executable (`$ORIGIN` or `@loader_path`). Other rpaths (e.g. passed in `linker_flags`) are
+/// kept.
+pub fn replace_rpaths(file: &Path, build_rpaths: &[String], install_rpath: impl Fn(&str) -> String) -> crate::Result<()> {
+    let mut contents = fs::read(file)
+        .map_err(|err| BeaverError::io(err, format!("while reading {}", file.display())))?;
+
+    if contents.starts_with(b"\x7fELF") {
+        if replace_elf_rpaths(file, &mut contents, build_rpaths, &install_rpath("$ORIGIN"))? {
+            fs::write(file, contents)
+                .map_err(|err| BeaverError::io(err, format!("while writing {}", file.display())))?;
+        }
+        return Ok(());
+    }
+
+    let is_macho = contents.get(0..4).is_some_and(|magic| matches!(
+        u32::from_be_bytes(magic.try_into().unwrap()),
+        0xfeedface | 0xfeedfacf | 0xcefaedfe | 0xcffaedfe | 0xcafebabe
+    ));
+    if is_macho {
+        return replace_macho_rpaths(file, build_rpaths, &install_rpath("@loader_path"));
+    }
+
+    warn!("Can't replace the rpaths of {}, it will only find its libraries in the build directory", file.display());
+    return Ok(());
+}
+
+#[cfg(target_os = "macos")]
+fn replace_macho_rpaths(file: &Path, build_rpaths: &[String], install_rpath: &str) -> crate::Result<()> {
+    let mut command = std::process::Command::new(&*crate::tools::xcrun);
+    command.arg("install_name_tool");
+    for rpath in build_rpaths {
+        command.args(["-delete_rpath", rpath]);
+    }
+    command.args(["-add_rpath", install_rpath]).arg(file);
+    let status = command.status()?;
+    if !status.success() {
+        return Err(BeaverError::NonZeroExitStatus(status));
+    }
+    return Ok(());
+}
+
+#[cfg(not(target_os = "macos"))]
+fn replace_macho_rpaths(file: &Path, _build_rpaths: &[String], _install_rpath: &str) -> crate::Result<()> {
+    warn!("The rpaths of {} can only be replaced on macOS", file.display());
+    return Ok(());
+}
+
+/// Rewrites the DT_RUNPATH (or DT_RPATH) string in place. The new string can't be longer than
+/// the old one, which is why build rpaths are padded. The linker can store other strings as the
+/// tail of the rpath, in which case it isn't replaced. Returns whether anything was replaced.
+fn replace_elf_rpaths(file: &Path, contents: &mut [u8], build_rpaths: &[String], install_rpath: &str) -> crate::Result<bool> {
+    let invalid = || BeaverError::InvalidElfFile(file.to_path_buf());
+
+    let is_64 = match contents.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err(invalid())
+    };
+    let little_endian = contents.get(5) == Some(&1);
+    let read = |contents: &[u8], offset: u64, size: usize| -> Option<u64> {
+        let bytes = contents.get(usize::try_from(offset).ok()?..)?.get(..size)?;
+        let mut value: u64 = 0;
+        for i in 0..size {
+            let byte = if little_endian { bytes[size - 1 - i] } else { bytes[i] };
+            value = (value << 8) | byte as u64;
+        }
+        Some(value)
+    };
+    let word = if is_64 { 8 } else { 4 };
+
+    let (phoff, phentsize, phnum) = if is_64 {
+        (read(contents, 0x20, 8), read(contents, 0x36, 2), read(contents, 0x38, 2))
+    } else {
+        (read(contents, 0x1c, 4), read(contents, 0x2a, 2), read(contents, 0x2c, 2))
+    };
+    let (Some(phoff), Some(phentsize), Some(phnum)) = (phoff, phentsize, phnum) else {
+        return Err(invalid());
+    };
+
+    // (offset, virtual address, size) of the loaded segments, and the dynamic segment
+    let mut loads: Vec<(u64, u64, u64)> = Vec::new();
+    let mut dynamic: Option<(u64, u64)> = None;
+    for i in 0..phnum {
+        let header = phoff + i * phentsize;
+        let p_type = read(contents, header, 4).ok_or_else(invalid)?;
+        let (offset, vaddr, filesz) = if is_64 {
+            (read(contents, header + 8, 8), read(contents, header + 16, 8), read(contents, header + 32, 8))
+        } else {
+            (read(contents, header + 4, 4), read(contents, header + 8, 4), read(contents, header + 16, 4))
+        };
+        let (Some(offset), Some(vaddr), Some(filesz)) = (offset, vaddr, filesz) else {
+            return Err(invalid());
+        };
+        match p_type {
+            PT_LOAD => loads.push((offset, vaddr, filesz)),
+            PT_DYNAMIC => dynamic = Some((offset, filesz)),
+            _ => {}
+        }
+    }
+
+    // Statically linked
+    let Some((dynamic_offset, dynamic_size)) = dynamic else {
+        return Ok(false);
+    };
+
+    let mut strtab = None;
+    let mut rpaths = Vec::new();
+    // The offsets in the string table of the other strings of the dynamic section
+    let mut strings = Vec::new();
+    let mut entry = dynamic_offset;
+    while entry + 2 * word <= dynamic_offset + dynamic_size {
+        let tag = read(contents, entry, word as usize).ok_or_else(invalid)?;
+        let value = read(contents, entry + word, word as usize).ok_or_else(invalid)?;
+        match tag {
+            DT_NULL => break,
+            DT_STRTAB => strtab = Some(value),
+            DT_RPATH | DT_RUNPATH => rpaths.push(value),
+            DT_NEEDED | DT_SONAME | DT_CONFIG | DT_DEPAUDIT | DT_AUDIT | DT_AUXILIARY | DT_FILTER => strings.push(value),
+            _ => {}
+        }
+        entry += 2 * word;
+    }
+
+    if rpaths.is_empty() {
+        return Ok(false);
+    }
+    let strtab = strtab.ok_or_else(invalid)?;
+    let strtab = loads.iter()
+        .find(|(_, vaddr, filesz)| (*vaddr..*vaddr + *filesz).contains(&strtab))
+        .map(|(offset, vaddr, _)| offset + (strtab - vaddr))
+        .ok_or_else(invalid)?;
+
+    let is_build_rpath = |rpath: &str| build_rpaths.iter().any(|build_rpath| build_rpath.trim_end_matches('/') == rpath.trim_end_matches('/'));
+    let mut replaced = false;
+    for &rpath in &rpaths {
+        let start = usize::try_from(strtab + rpath).map_err(|_| invalid())?;
+        let len = contents.get(start..).and_then(|str| str.iter().position(|c| *c == 0)).ok_or_else(invalid)?;
+        let old = String::from_utf8_lossy(&contents[start..start + len]).to_string();
+
+        let mut entries: Vec<&str> = old.split(':').filter(|entry| !is_build_rpath(entry)).collect();
+        if entries.len() == old.split(':').count() {
+            continue;
+        }
+        if !install_rpath.is_empty() {
+            entries.push(install_rpath);
+        }
+        let new = entries.join(":");
+        if new.len() > len {
+            return Err(BeaverError::InstallRpathTooLong { file: file.to_path_buf(), rpath: new, available: len });
+        }
+        let rpath_len = len as u64;
+        let shared = strings.iter().any(|offset| (rpath..rpath + rpath_len).contains(offset))
+            || rpaths.iter().any(|offset| (rpath + 1..rpath + rpath_len).contains(offset));
+        if shared {
+            return Err(BeaverError::SharedElfRpath { file: file.to_path_buf(), rpath: old });
+        }
+
+        contents[start..start + new.len()].copy_from_slice(new.as_bytes());
+        contents[start + new.len()..start + len].fill(0);
+        replaced = true;
+    }
+
+    return Ok(replaced);
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use crate::BeaverError;
+
+    use super::replace_elf_rpaths;
+
+    const BUILD_RPATH: &str = "/build/dependency/lib";
+
+    /// Links an executable against a library with the soname `soname`, with the rpath `BUILD_RPATH`
+    fn link(soname: &str) -> (PathBuf, Vec<u8>) {
+        let dir = tempdir::TempDir::new("be.jonaseveraert.beaver.tests.rpath").unwrap().into_path();
+        fs::write(dir.join("dep.c"), "int dep(void) { return 0; }\n").unwrap();
+        fs::write(dir.join("main.c"), "int dep(void);\nint main(void) { return dep(); }\n").unwrap();
+        let cc = |args: &[&str]| assert!(Command::new("cc").args(args).current_dir(&dir).status().unwrap().success());
+        cc(&["-shared", "-fPIC", &format!("-Wl,-soname,{}", soname), "-o", "libdep.so", "dep.c"]);
+        cc(&["main.c", "-L.", "-ldep", &format!("-Wl,-rpath,{}", BUILD_RPATH), "-o", "main"]);
+        let file = dir.join("main");
+        let contents = fs::read(&file).unwrap();
+        return (file, contents);
+    }
+
+    fn contains(contents: &[u8], string: &str) -> bool {
+        contents.windows(string.len() + 1).any(|window| window[..string.len()] == *string.as_bytes() && window[string.len()] == 0)
+    }
+
+    #[test]
+    fn replace() {
+        let (file, mut contents) = link("libdep.so");
+        assert!(replace_elf_rpaths(&file, &mut contents, &[BUILD_RPATH.to_string()], "$ORIGIN/../lib").unwrap());
+        assert!(contains(&contents, "$ORIGIN/../lib"));
+        assert!(!contains(&contents, BUILD_RPATH));
+        assert!(contains(&contents, "libdep.so"));
+    }
+
+    #[test]
+    fn shared_storage() {
+        // The linker stores the soname `lib` as the tail of the rpath
+        let (file, mut contents) = link("lib");
+        let original = contents.clone();
+        let result = replace_elf_rpaths(&file, &mut contents, &[BUILD_RPATH.to_string()], "$ORIGIN/../lib");
+        assert!(matches!(result, Err(BeaverError::SharedElfRpath { .. })), "{:?}", result);
+        assert_eq!(contents, original);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::OptimizationMode;
+
+/// A build profile, selected with `-o <name>`
+///
+/// The built-in optimization modes are profiles without additional flags. Scripts can define
+/// their own profiles, which build with the flags of their `base` mode plus their own flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub base: OptimizationMode,
+    pub cflags: Vec<String>,
+    pub linker_flags: Vec<String>,
+}
+
+impl Profile {
+    pub fn new(name: String, base: OptimizationMode, cflags: Vec<String>, linker_flags: Vec<String>) -> Profile {
+        Profile { name, base, cflags, linker_flags }
+    }
+
+    pub fn cflags(&self) -> impl Iterator<Item = &str> {
+        self.base.cflags().iter().copied()
+            .chain(self.cflags.iter().map(|flag| flag.as_str()))
+    }
+
+    pub fn linker_flags(&self) -> impl Iterator<Item = &str> {
+        self.base.linker_flags().iter().copied()
+            .chain(self.linker_flags.iter().map(|flag| flag.as_str()))
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        OptimizationMode::try_from(self.name.as_str()).is_ok()
+    }
+}
+
+impl From<OptimizationMode> for Profile {
+    fn from(mode: OptimizationMode) -> Self {
+        Profile::new(mode.to_string(), mode, Vec::new(), Vec::new())
+    }
+}
@@ -81,7 +81,7 @@ impl project::traits::Project for Project {
         let executables = targets.iter().filter_map(|target| {
             match target {
                 AnyTarget::Library(_) => None,
-                AnyTarget::Executable(exe) => Some(exe),
+                AnyTarget::Executable(exe) => if exe.is_test() { None } else { Some(exe) },
             }
         }).collect::<Vec<&AnyExecutable>>();
 
@@ -121,7 +121,12 @@ impl project::traits::Project for Project {
         _ = scope; // TODO
 
         let (mut guard, steps) = self.register_targets(triple, &builder, context)?;
-        let steps: Vec<&str> = steps.iter().map(|str| str.as_str()).collect();
+        // Tests are not built as part of the project
+        let targets = self.targets()?;
+        let steps: Vec<&str> = steps.iter().zip(targets.iter())
+            .filter(|(_, target)| !target.is_test())
+            .map(|(step, _)| step.as_str())
+            .collect();
         // let targets = self.targets()?;
 
         // let mut guard = builder.write().map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
@@ -63,7 +63,7 @@ pub fn import(
         base_dir.to_path_buf(),
         targets,
         context
-    ))?;
+    )?)?;
 
     Ok(())
 }
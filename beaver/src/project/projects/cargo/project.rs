@@ -8,6 +8,7 @@ use log::error;
 
 use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep};
 use crate::target::TargetRef;
+use crate::testing::TestCase;
 use crate::traits::{self, AnyTarget, MutableProject, Target, TargetType};
 use crate::{tools, Beaver, BeaverError};
 
@@ -28,21 +29,21 @@ impl Project {
         base_dir: PathBuf,
         targets: Vec<AnyTarget>,
         context: &Beaver
-    ) -> Self {
-        let build_dir = base_dir.join("target").join(context.optimize_mode.cargo_name());
+    ) -> crate::Result<Self> {
+        let build_dir = base_dir.join("target").join(context.opt_mode()?.cargo_name());
         let mut targets = targets;
         for i in 0..targets.len() {
             targets[i].set_id(i);
         }
 
-        Self {
+        Ok(Self {
             id: None,
             name,
             cargo_flags,
             base_dir,
             build_dir,
             targets,
-        }
+        })
     }
 }
 
@@ -146,7 +147,7 @@ impl traits::Project for Project {
             dependencies: &[],
             options: &[
                 ("workspaceDir", dir),
-                ("cargoArgs", &(self.cargo_flags.join(" ") + if context.color_enabled() { " --color always " } else { "" } + context.optimize_mode.cargo_flags().join(" ").as_str()))
+                ("cargoArgs", &(self.cargo_flags.join(" ") + if context.color_enabled() { " --color always " } else { "" } + context.opt_mode()?.cargo_flags_quoted().as_str()))
             ],
         })?;
 
@@ -170,6 +171,28 @@ impl traits::Project for Project {
         Ok(())
     }
 
+    /// `cargo test` is run for the whole workspace
+    fn tests(&self, context: &Beaver) -> crate::Result<Vec<TestCase>> {
+        let mut args = vec!["test".to_string()];
+        args.extend(self.cargo_flags.iter().cloned());
+        args.extend(context.opt_mode()?.cargo_flags().iter().map(|flag| flag.to_string()));
+        if context.color_enabled() {
+            args.extend(["--color".to_string(), "always".to_string()]);
+        }
+
+        return Ok(vec![TestCase {
+            name: format!("{}:cargo-test", self.name),
+            project: self.name.clone(),
+            program: tools::cargo.clone(),
+            args,
+            working_dir: std::path::absolute(&self.base_dir)?,
+            env: Vec::new(),
+            timeout: None,
+            // cargo builds the tests itself
+            build_step: None,
+        }]);
+    }
+
     fn as_mutable(&self) -> Option<&dyn MutableProject> {
         None
     }
@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use log::*;
+use serde::Deserialize;
+
+use crate::testing::TestCase;
+use crate::{tools, BeaverError};
+
+/// Output of `ctest --show-only=json-v1`
+#[derive(Deserialize)]
+struct CTestList {
+    tests: Vec<CTestTest>,
+}
+
+#[derive(Deserialize)]
+struct CTestTest {
+    name: String,
+    #[serde(default)]
+    command: Vec<String>,
+    #[serde(default)]
+    properties: Vec<CTestProperty>,
+}
+
+#[derive(Deserialize)]
+struct CTestProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+/// List the tests registered with `add_test` in a configured CMake build directory
+pub(crate) fn ctest_tests(project_name: &str, build_dir: &Path) -> crate::Result<Vec<TestCase>> {
+    if !build_dir.join("CTestTestfile.cmake").exists() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new(tools::ctest.as_path())
+        .arg("--show-only=json-v1")
+        .current_dir(build_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(BeaverError::CTestListError(project_name.to_string(), String::from_utf8_lossy(&output.stderr).to_string()));
+    }
+
+    let list: CTestList = serde_json::from_slice(&output.stdout)
+        .map_err(|err| BeaverError::CTestListError(project_name.to_string(), err.to_string()))?;
+
+    let mut tests = Vec::new();
+    for test in list.tests {
+        let property = |name: &str| test.properties.iter()
+            .find(|property| property.name == name)
+            .map(|property| &property.value);
+
+        if property("DISABLED").and_then(|value| value.as_bool()).unwrap_or(false) {
+            continue;
+        }
+        let mut command = test.command.into_iter();
+        let Some(program) = command.next() else {
+            warn!("CTest test {} in {} has no command", test.name, project_name);
+            continue;
+        };
+
+        tests.push(TestCase {
+            name: format!("{}:{}", project_name, test.name),
+            project: project_name.to_string(),
+            program: program.into(),
+            args: command.collect(),
+            working_dir: property("WORKING_DIRECTORY")
+                .and_then(|value| value.as_str())
+                .map(|dir| dir.into())
+                .unwrap_or(build_dir.to_path_buf()),
+            env: property("ENVIRONMENT")
+                .and_then(|value| value.as_array())
+                .map(|vars| vars.iter()
+                    .filter_map(|var| var.as_str()?.split_once('='))
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect())
+                .unwrap_or_default(),
+            timeout: property("TIMEOUT")
+                .and_then(|value| value.as_f64())
+                .filter(|timeout| *timeout > 0.0)
+                .map(Duration::from_secs_f64),
+            // The CMake project is built as a whole
+            build_step: Some(project_name.to_string()),
+        });
+    }
+
+    return Ok(tests);
+}
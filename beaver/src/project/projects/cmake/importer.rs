@@ -24,7 +24,7 @@ pub fn import(
     trace!("Importing CMake project at {:?}", base_dir);
 
     let base_dir_str = base_dir.to_string_lossy();
-    let file_context = context.optimize_mode.cmake_name().to_string() + ":" + base_dir_str.as_ref();
+    let file_context = context.output_dir_name() + ":" + base_dir_str.as_ref();
 
     let build_dir = context.get_build_dir_for_external_build_system2(base_dir_str.as_ref())?;
 
@@ -64,11 +64,12 @@ pub fn import(
     if reconfigure {
         trace!("Reconfiguring CMake project {:?}", base_dir);
 
-        let build_type_arg = format!("-DCMAKE_BUILD_TYPE={}", context.optimize_mode.cmake_name());
+        let build_type_arg = format!("-DCMAKE_BUILD_TYPE={}", context.opt_mode()?.cmake_name());
         let mut args = vec![
             base_dir_str.as_ref(),
             &build_type_arg,
-            "-G", "Ninja"
+            "-G", "Ninja",
+            "-DCMAKE_EXPORT_COMPILE_COMMANDS=ON"
         ];
         args.extend_from_slice(cmake_flags);
 
@@ -105,10 +106,11 @@ pub fn import(
     trace!("CMake importer: reading codemodel");
 
     let codemodel: CodeModelV2 = reader.read_object()?;
+    let cmake_name = context.opt_mode()?.cmake_name();
     let Some(cmake_config) = codemodel.configurations.iter().find(|config| {
-        config.name == context.optimize_mode.cmake_name()
+        config.name == cmake_name
     }) else {
-        return Err(BeaverError::CMakeMissingConfig(context.optimize_mode.cmake_name()));
+        return Err(BeaverError::CMakeMissingConfig(cmake_name));
     };
 
     trace!("CMake importer: reading replies to targets and projects");
@@ -2,3 +2,4 @@ use utils::moduse;
 
 moduse!(importer);
 moduse!(project);
+moduse!(ctest);
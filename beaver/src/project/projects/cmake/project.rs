@@ -6,6 +6,7 @@ use target_lexicon::Triple;
 
 use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep};
 use crate::target::TargetRef;
+use crate::testing::TestCase;
 use crate::traits::{self, AnyTarget, Target, TargetType};
 use crate::{Beaver, BeaverError};
 
@@ -138,13 +139,17 @@ impl traits::Project for Project {
         return Ok(());
     }
 
+    fn tests(&self, _context: &Beaver) -> crate::Result<Vec<TestCase>> {
+        super::ctest_tests(&self.name, &self.build_dir)
+    }
+
     fn as_mutable(&self) -> Option<&dyn traits::MutableProject> {
         None
     }
 
     fn clean(&self, context: &Beaver) -> crate::Result<()> {
         let base_dir_str = self.base_dir.to_string_lossy();
-        context.cache()?.remove_context(&(context.optimize_mode.cmake_name().to_string() + ":" + base_dir_str.as_ref()))?;
+        context.cache()?.remove_context(&(context.output_dir_name() + ":" + base_dir_str.as_ref()))?;
 
         if !self.build_dir.exists() {
             return Ok(())
@@ -16,7 +16,7 @@ pub fn import(
 ) -> crate::Result<usize> {
     let base_dir = std::path::absolute(base_dir)?;
     let base_dir_str = base_dir.to_string_lossy();
-    let file_context = context.optimize_mode.to_string() + ":" + base_dir_str.as_ref();
+    let file_context = context.output_dir_name() + ":" + base_dir_str.as_ref();
 
     let (build_dir, reconfigured) = meson_configure(&base_dir, base_dir_str.as_ref(), &file_context, meson_configure_args, context)?;
     let meson_info = build_dir.join("meson-info");
@@ -136,10 +136,12 @@ fn meson_configure(
         let build_dir = build_dir.to_str().expect("Non-UTF8 file path");
 
         let color_arg = format!("-Db_colorout={}", if context.color_enabled() { "always" } else { "never" }); // TODO: cache this option
+        let buildtype_arg = format!("--buildtype={}", context.opt_mode()?.meson_buildtype());
         let mut args = vec![
             "setup",
             "--reconfigure",
             &color_arg,
+            &buildtype_arg,
             &build_dir
         ];
         args.extend_from_slice(meson_configure_args);
@@ -28,7 +28,7 @@ pub fn import(
     let cache_dir = Arc::new(std::path::absolute(cache_dir)?);
     let json_save_path = build_sys_cache_dir.join("manifest.json");
 
-    let file_context = context.optimize_mode.to_string() + ":" + base_dir_str.as_ref();
+    let file_context = context.output_dir_name() + ":" + base_dir_str.as_ref();
     let cache = context.cache()?;
     let remake_json = cache.files_changed_in_context(&file_context)? || (!json_save_path.exists());
 
@@ -101,7 +101,7 @@ pub fn import(
         std::path::absolute(base_dir)?,
         cache_dir,
         targets,
-        context.optimize_mode,
+        context.opt_mode()?,
         &context.target_triple
     );
 
@@ -8,6 +8,7 @@ use target_lexicon::Triple;
 use crate::backend::BackendBuilder;
 use crate::target::traits::AnyTarget;
 use crate::target::TargetRef;
+use crate::testing::TestCase;
 use crate::traits::{AnyExecutable, Target};
 use crate::{project, Beaver, BeaverError};
 
@@ -26,7 +27,7 @@ pub trait Project: Send + Sync + std::fmt::Debug {
         let executables = targets.iter().filter_map(|target| {
             match target {
                 AnyTarget::Library(_) => None,
-                AnyTarget::Executable(exe) => Some(exe),
+                AnyTarget::Executable(exe) => if exe.is_test() { None } else { Some(exe) },
             }
         }).collect::<Vec<&AnyExecutable>>();
 
@@ -88,6 +89,18 @@ pub trait Project: Send + Sync + std::fmt::Debug {
             .map_err(BeaverError::from)
     }
 
+    /// All tests in this project, run by `beaver test`
+    fn tests(&self, context: &Beaver) -> crate::Result<Vec<TestCase>> {
+        let targets = self.targets()?;
+        let mut tests = Vec::new();
+        for target in targets.iter() {
+            if let Some(test) = target.test_case(self.name(), self.base_dir(), self.build_dir(), &context.target_triple)? {
+                tests.push(test);
+            }
+        }
+        return Ok(tests);
+    }
+
     fn as_mutable(&self) -> Option<&dyn MutableProject> {
         None
     }
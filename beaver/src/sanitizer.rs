@@ -0,0 +1,109 @@
+use crate::BeaverError;
+
+/// A runtime sanitizer, enabled with `--sanitize <names>`
+///
+/// see: [Program Instrumentation Options](https://gcc.gnu.org/onlinedocs/gcc/Instrumentation-Options.html)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Sanitizer {
+    /// AddressSanitizer: out-of-bounds accesses, use-after-free, ...
+    Address,
+    /// UndefinedBehaviorSanitizer
+    Undefined,
+    /// ThreadSanitizer: data races
+    Thread,
+    /// MemorySanitizer: reads of uninitialized memory (clang only)
+    Memory,
+    /// LeakSanitizer: memory leaks
+    Leak,
+}
+
+use Sanitizer::*;
+
+impl Sanitizer {
+    pub const ALL: [Sanitizer; 5] = [Address, Undefined, Thread, Memory, Leak];
+
+    /// The name passed to `-fsanitize=`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Address => "address",
+            Undefined => "undefined",
+            Thread => "thread",
+            Memory => "memory",
+            Leak => "leak",
+        }
+    }
+
+    /// The abbreviated name, used in the name of the build directory
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Address => "asan",
+            Undefined => "ubsan",
+            Thread => "tsan",
+            Memory => "msan",
+            Leak => "lsan",
+        }
+    }
+
+    /// Whether both sanitizers can be linked into the same program
+    pub fn compatible_with(&self, other: &Sanitizer) -> bool {
+        !matches!((self, other),
+            (Address, Thread) | (Thread, Address)
+            | (Address, Memory) | (Memory, Address)
+            | (Thread, Memory) | (Memory, Thread)
+            | (Thread, Leak) | (Leak, Thread)
+            | (Memory, Leak) | (Leak, Memory)
+        )
+    }
+
+    /// Sort and deduplicate `sanitizers` and check that they can be combined
+    pub fn validate(mut sanitizers: Vec<Sanitizer>) -> crate::Result<Vec<Sanitizer>> {
+        sanitizers.sort();
+        sanitizers.dedup();
+        for (i, a) in sanitizers.iter().enumerate() {
+            if let Some(b) = sanitizers[i + 1..].iter().find(|b| !a.compatible_with(b)) {
+                return Err(BeaverError::IncompatibleSanitizers(*a, *b));
+            }
+        }
+        return Ok(sanitizers);
+    }
+
+    /// Flags for both compiling and linking with `sanitizers`
+    pub fn flags(sanitizers: &[Sanitizer]) -> Vec<String> {
+        if sanitizers.is_empty() {
+            return Vec::new();
+        }
+
+        let mut flags = vec![
+            format!("-fsanitize={}", sanitizers.iter().map(|sanitizer| sanitizer.name()).collect::<Vec<_>>().join(",")),
+            // Readable stack traces in reports
+            "-fno-omit-frame-pointer".to_string(),
+        ];
+        if sanitizers.contains(&Undefined) {
+            // Make undefined behaviour fail the program (and tests), instead of only printing a report
+            flags.push("-fno-sanitize-recover=undefined".to_string());
+        }
+        return flags;
+    }
+}
+
+impl std::fmt::Display for Sanitizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl TryFrom<&str> for Sanitizer {
+    type Error = BeaverError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value_lowercase = value.to_lowercase();
+        match Sanitizer::ALL.iter().find(|sanitizer| sanitizer.name() == value_lowercase || sanitizer.short_name() == value_lowercase) {
+            Some(sanitizer) => Ok(*sanitizer),
+            None => Err(BeaverError::TryFromStringError {
+                name: "sanitizer".to_string(),
+                got: value.to_string(),
+                expected_values: Sanitizer::ALL.iter().map(|sanitizer| sanitizer.to_string()).collect()
+            })
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::cmp::{Eq, PartialEq};
+
+use target_lexicon::Triple;
+
+use crate::{platform, BeaverError};
+
+pub trait TArtifactType: Sized + std::fmt::Display + Into<ArtifactType> + Copy + Clone {
+    fn parse(str: &str) -> crate::Result<Self>;
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum ArtifactType {
+    Library(LibraryArtifactType),
+    Executable(ExecutableArtifactType),
+}
+
+impl ArtifactType {
+    pub fn as_library(&self) -> Option<LibraryArtifactType> {
+        match self {
+            ArtifactType::Library(art) => Some(*art),
+            _ => None
+        }
+    }
+
+    pub fn as_executable(&self) -> Option<ExecutableArtifactType> {
+        match self {
+            ArtifactType::Executable(art) => Some(*art),
+            _ => None
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum LibraryArtifactType {
+    /// A dynamic library callable through C convention
+    Dynlib,
+    Staticlib,
+    PkgConfig,
+    /// A dynamic library loaded at runtime (a plugin). Dependents are not linked against it.
+    Module,
+    /// The object files of a library, linked directly into its dependents
+    Objects,
+    /// A library without any files, consisting only of public headers, flags and dependencies
+    Interface,
+    // framework/xcframework: see https://bitmountn.com/difference-between-framework-and-xcframework-in-ios/
+    /// macOS framework
+    Framework,
+    XCFramework,
+
+    // Rust //
+    /// rlib: A static rust library
+    RustLib,
+    /// dylib: A dynamic rust library
+    RustDynlib,
+
+    // Emscripten //
+    JSLib,
+}
+
+impl TArtifactType for LibraryArtifactType {
+    fn parse(str: &str) -> crate::Result<LibraryArtifactType> {
+        match str {
+            "dynlib" => Ok(LibraryArtifactType::Dynlib),
+            "staticlib" | "static" => Ok(LibraryArtifactType::Staticlib),
+            "pkgconfig" | "pkg-config" | "pkgconf" | "pkg-conf" => Ok(LibraryArtifactType::PkgConfig),
+            "module" | "plugin" => Ok(LibraryArtifactType::Module),
+            "objects" | "object" => Ok(LibraryArtifactType::Objects),
+            "interface" => Ok(LibraryArtifactType::Interface),
+            "framework" => Ok(LibraryArtifactType::Framework),
+            "xcframework" => Ok(LibraryArtifactType::XCFramework),
+            "jslib" => Ok(LibraryArtifactType::JSLib),
+            _ => Err(BeaverError::InvalidLibraryArtifactType(str.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for LibraryArtifactType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryArtifactType::Dynlib => f.write_str("dynlib"),
+            LibraryArtifactType::Staticlib => f.write_str("staticlib"),
+            LibraryArtifactType::PkgConfig => f.write_str("pkgconfig"),
+            LibraryArtifactType::Module => f.write_str("module"),
+            LibraryArtifactType::Objects => f.write_str("objects"),
+            LibraryArtifactType::Interface => f.write_str("interface"),
+            LibraryArtifactType::Framework => f.write_str("framework"),
+            LibraryArtifactType::XCFramework => f.write_str("xcframework"),
+            LibraryArtifactType::RustLib => f.write_str("rlib"),
+            LibraryArtifactType::RustDynlib => f.write_str("rust_dynlib"),
+            LibraryArtifactType::JSLib => f.write_str("jslib"),
+        }
+    }
+}
+
+impl Into<ArtifactType> for LibraryArtifactType {
+    fn into(self) -> ArtifactType {
+        ArtifactType::Library(self)
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum ExecutableArtifactType {
+    Executable,
+    /// a macOS app
+    App
+}
+
+impl TArtifactType for ExecutableArtifactType {
+    fn parse(str: &str) -> crate::Result<ExecutableArtifactType> {
+        match str {
+            "exe" | "exec" | "executable" => Ok(ExecutableArtifactType::Executable),
+            "app" => Ok(ExecutableArtifactType::App),
+            _ => Err(BeaverError::InvalidExecutableArtifactType(str.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutableArtifactType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutableArtifactType::Executable => f.write_str("exe"),
+            ExecutableArtifactType::App => f.write_str("app"),
+        }
+    }
+}
+
+impl Into<ArtifactType> for ExecutableArtifactType {
+    fn into(self) -> ArtifactType {
+        ArtifactType::Executable(self)
+    }
+}
+
+/// How the object files of a C target are compiled
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum CObjectType {
+    /// Position independent code, linked into dynamic libraries
+    Dynamic,
+    Static
+}
+
+impl CObjectType {
+    /// Flags passed when compiling objects of this type, on top of the target's flags
+    pub fn cflags(&self, triple: &Triple) -> &'static [&'static str] {
+        match self {
+            CObjectType::Dynamic => platform::pic_cflags_for_os(&triple.operating_system),
+            CObjectType::Static => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for CObjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CObjectType::Dynamic => f.write_str("dynamic"),
+            CObjectType::Static => f.write_str("static"),
+        }
+    }
+}
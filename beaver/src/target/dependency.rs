@@ -0,0 +1,461 @@
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use log::*;
+use target_lexicon::Triple;
+
+use crate::{tools, Beaver, BeaverError};
+use crate::traits::{Project, Target};
+use super::traits::Library;
+use super::LibraryArtifactType;
+
+#[derive(Eq, PartialEq, Hash, Clone, Debug)]
+pub enum Dependency {
+    Library(LibraryTargetDependency),
+    Flags {
+        cflags: Option<Vec<String>>,
+        linker_flags: Option<Vec<String>>,
+        headers: Option<Vec<PathBuf>>,
+    },
+    /// A library found using pkg-config
+    PkgConfig {
+        name: String,
+        /// e.g. `>=1.0`
+        version_requirement: Option<String>,
+        cflags: Vec<String>,
+        linker_flags: Vec<String>,
+    },
+    /// reference to a CMake id
+    CMakeId(String),
+    Multi(Vec<Dependency>),
+    /// A file dependency, this can refer to any file (that isn't a source file)
+    File(PathBuf),
+}
+
+/// How a dependency of a target is passed on to the targets depending on it
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub enum DependencyVisibility {
+    /// Used by the target and by its dependents
+    Public,
+    /// Only used by the target itself. The dependents of a static library still link it.
+    Private,
+    /// Only used by the dependents of the target
+    Interface,
+}
+
+impl DependencyVisibility {
+    pub const ALL: [DependencyVisibility; 3] = [DependencyVisibility::Public, DependencyVisibility::Private, DependencyVisibility::Interface];
+
+    pub fn parse(str: &str) -> crate::Result<DependencyVisibility> {
+        match str {
+            "public" => Ok(DependencyVisibility::Public),
+            "private" => Ok(DependencyVisibility::Private),
+            "interface" => Ok(DependencyVisibility::Interface),
+            _ => Err(BeaverError::InvalidDependencyVisibility(str.to_string())),
+        }
+    }
+}
+
+// Initializers //
+
+pub enum PkgconfigOption<'a> {
+    WithPath(&'a str),
+}
+
+impl<'a> PkgconfigOption<'a> {
+    fn flag_into(&self, args: &mut Vec<OsString>) {
+        match self {
+            Self::WithPath(path) => args.push(OsString::from(format!("--with-path={}", path))),
+        }
+    }
+
+    fn apply(&self, resolver: &mut pkgconfig_parser::Resolver) {
+        match self {
+            Self::WithPath(path) => resolver.prepend_search_path(path),
+        }
+    }
+}
+
+pub enum PkgconfigFlagOption {
+    PreferStatic,
+}
+
+impl PkgconfigFlagOption {
+    fn flag_into(&self, args: &mut Vec<OsString>) {
+        match self {
+            Self::PreferStatic => args.push(OsString::from("--static")),
+        }
+    }
+
+    fn apply(&self, resolver: &mut pkgconfig_parser::Resolver) {
+        match self {
+            Self::PreferStatic => resolver.set_prefer_static(true),
+        }
+    }
+}
+
+// Constructors
+impl Dependency {
+    /// Find a library using pkg-config. The pkg-config files are resolved by beaver itself, when this
+    /// fails (e.g. because the package is not in the default search path), `pkgconf` is invoked.
+    pub fn pkgconfig(name: &str, version_contstraint: Option<&str>, options: &[PkgconfigOption], flag_options: &[PkgconfigFlagOption]) -> crate::Result<Dependency> {
+        let constraint = version_contstraint
+            .map(|constraint| pkgconfig_parser::Requirement::parse_constraint(constraint)
+                .map_err(|_| BeaverError::PkgconfigMalformedVersionRequirement(constraint.to_string())))
+            .transpose()?;
+        let requirement = pkgconfig_parser::Requirement { name: name.to_string(), constraint };
+
+        let mut resolver = pkgconfig_parser::Resolver::new();
+        for option in options.iter().rev() {
+            option.apply(&mut resolver);
+        }
+        for flag_option in flag_options {
+            flag_option.apply(&mut resolver);
+        }
+
+        match resolver.resolve_requirement(&requirement) {
+            Ok(resolved) => {
+                return Ok(Dependency::PkgConfig {
+                    name: name.to_string(),
+                    version_requirement: version_contstraint.map(|str| str.to_string()),
+                    cflags: resolved.cflags,
+                    linker_flags: resolved.libs,
+                });
+            },
+            Err(err @ (pkgconfig_parser::Error::VersionMismatch { .. } | pkgconfig_parser::Error::Conflict { .. })) => {
+                return Err(BeaverError::PkgconfigResolveError(name.to_string(), err));
+            },
+            Err(err) => {
+                debug!("Couldn't resolve pkg-config package {}: {}. Falling back to {:?}", name, err, tools::pkgconf.as_path());
+            }
+        }
+
+        return Self::pkgconfig_subprocess(&requirement, options, flag_options);
+    }
+
+    fn pkgconfig_subprocess(requirement: &pkgconfig_parser::Requirement, options: &[PkgconfigOption], flag_options: &[PkgconfigFlagOption]) -> crate::Result<Dependency> {
+        let name = requirement.name.as_str();
+        // e.g. `--exists "zlib >= 1.2"`
+        let mut exists_args: Vec<OsString> = vec![OsString::from("--exists"), OsString::from(requirement.to_string()), OsString::from("--print-errors")];
+        for option in options {
+            option.flag_into(&mut exists_args);
+        }
+        trace!("Invoking pkg-config exists for {} with args {:?}", name, &exists_args);
+        let mut exists_process = Command::new(tools::pkgconf.as_os_str())
+            .args(exists_args)
+            .spawn()?;
+
+        let mut flags_args: Vec<OsString> = vec![OsString::from("--cflags"), OsString::from(name), OsString::from("--print-errors")];
+        for option in options {
+            option.flag_into(&mut flags_args);
+        }
+        for flag_option in flag_options {
+            flag_option.flag_into(&mut flags_args);
+        }
+        trace!("Invoking pkg-config for {} with args {:?}", name, &flags_args);
+        let mut cflags_process = Command::new(tools::pkgconf.as_os_str())
+            .args(&flags_args)
+            .stderr(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        flags_args[0] = OsString::from("--libs");
+        trace!("Invoking pkg-config for {} with args {:?}", name, &flags_args);
+        let mut linker_flags_process = Command::new(tools::pkgconf.as_os_str())
+            .args(flags_args)
+            .stderr(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        // Check exists
+        if !exists_process.wait()?.success() {
+            if let Err(err) = cflags_process.kill() { error!("{:?}", err) }
+            if let Err(err) = linker_flags_process.kill() { error!("{:?}", err) }
+            return Err(BeaverError::PkgconfigNotFound(name.to_string()));
+        }
+
+        // Collect cflags
+        let cflags_status = cflags_process.wait()?;
+        if !cflags_status.success() {
+            _ = linker_flags_process.kill().map_err(|err| error!("{:?}", err));
+            return Err(BeaverError::NonZeroExitStatus(cflags_status));
+        }
+        let mut cflags_stdout = cflags_process.stdout.take().expect("Stdout should be captured");
+        let mut string = String::new();
+        cflags_stdout.read_to_string(&mut string)?;
+        let Some(cflags) = shlex::split(&string) else {
+            if let Err(err) = linker_flags_process.kill() { error!("{:?}", err) }
+            return Err(BeaverError::PkgconfigMalformed(string));
+        };
+
+        // Collect linker flags
+        let linker_flags_status = linker_flags_process.wait()?;
+        if !linker_flags_status.success() {
+            return Err(BeaverError::NonZeroExitStatus(linker_flags_status));
+        }
+        let mut linker_flags_stdout = linker_flags_process.stdout.take().expect("Stdout should be captured");
+        let mut string = String::new();
+        linker_flags_stdout.read_to_string(&mut string)?;
+        let Some(linker_flags) = shlex::split(&string) else {
+            return Err(BeaverError::PkgconfigMalformed(string));
+        };
+
+        Ok(Dependency::PkgConfig {
+            name: name.to_string(),
+            version_requirement: requirement.constraint.as_ref().map(|(operator, version)| format!("{}{}", operator, version)),
+            cflags,
+            linker_flags
+        })
+    }
+
+    pub fn system(name: &str) -> Dependency {
+        Dependency::Flags { cflags: None, linker_flags: Some(vec![format!("-l{}", name)]), headers: None }
+    }
+
+    pub fn framework(name: &str) -> Dependency {
+        Dependency::Flags {
+            cflags: None,
+            linker_flags: Some(vec!["-framework".to_string(), name.to_string()]),
+            headers: None
+        }
+    }
+
+    pub fn pkgconfig_from_file(file: &Path, prepend_search_path: Option<&Path>) -> crate::Result<Dependency> {
+        let mut resolver = pkgconfig_parser::Resolver::new();
+        if let Some(dir) = prepend_search_path {
+            resolver.prepend_search_path(std::path::absolute(dir)?);
+        }
+        match resolver.resolve_file(file) {
+            Ok(resolved) => {
+                return Ok(Dependency::Flags {
+                    cflags: Some(resolved.cflags),
+                    linker_flags: Some(resolved.libs),
+                    headers: None,
+                });
+            },
+            Err(err @ (pkgconfig_parser::Error::VersionMismatch { .. } | pkgconfig_parser::Error::Conflict { .. })) => {
+                return Err(BeaverError::PkgconfigResolveError(file.display().to_string(), err));
+            },
+            Err(err) => {
+                debug!("Couldn't resolve pkg-config file {:?}: {}. Falling back to {:?}", file, err, tools::pkgconf.as_path());
+            }
+        }
+
+        let contents = std::fs::read_to_string(file)?;
+        let pkgconf = pkgconfig_parser::PkgConfig::parse(&contents)
+            .map_err(|err| BeaverError::PkgconfigParsingError(file.to_path_buf(), err))?;
+
+        let mut deps = vec![
+            Dependency::Flags {
+                cflags: pkgconf.cflags().as_ref().map(|cflags| shlex::split(cflags.as_ref()).unwrap()),
+                linker_flags: pkgconf.libs().as_ref().map(|lflags| shlex::split(lflags.as_ref()).unwrap()),
+                headers: None,
+            }
+        ];
+
+        if let Some(mut dependencies) = crate::target::pkgconfig_collect_dependencies(&pkgconf, prepend_search_path)? {
+            deps.append(&mut dependencies);
+        }
+
+        Ok(Dependency::Multi(deps))
+    }
+}
+
+// LibraryTarget //
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub struct TargetRef {
+    pub target: usize,
+    pub project: usize,
+}
+
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
+pub struct LibraryTargetDependency {
+    pub target: TargetRef,
+    pub artifact: LibraryArtifactType,
+}
+
+// fns //
+
+impl Dependency {
+    pub(crate) fn ninja_name(&self, context: &Beaver) -> crate::Result<Option<String>> {
+        match self {
+            Dependency::Library(dep) => {
+                return context.with_project_and_target::<Option<String>, BeaverError>(&dep.target, |project, target| {
+                    Ok(Some(format!("{}$:{}$:{}", project.name(), target.name(), dep.artifact)))
+                });
+            },
+            Dependency::Flags { cflags: _, linker_flags: _, headers: _ } | Dependency::PkgConfig { .. } => {
+                return Ok(None);
+            },
+            Dependency::CMakeId(cmake_id) => {
+                return context.with_cmake_project_and_library(&cmake_id, |project, target| {
+                    Ok(target.map(|target| format!("{}$:{}$:{}", project.name(), target.name(), target.artifact)))
+                });
+            },
+            Dependency::Multi(_deps) => {
+                return Ok(None); // TODO
+            },
+            Dependency::File(file) => {
+                file.to_str()
+                    .ok_or(BeaverError::NonUTF8OsStr(file.as_os_str().to_os_string()))
+                    .map(|str| Some(str.to_string()))
+            }
+        }
+    }
+
+    /// Like [`ninja_name`](Self::ninja_name), but includes all dependencies of a `Multi` dependency
+    pub(crate) fn ninja_names(&self, context: &Beaver, out: &mut Vec<String>) -> crate::Result<()> {
+        match self {
+            Dependency::Multi(deps) => {
+                for dep in deps {
+                    dep.ninja_names(context, out)?;
+                }
+            },
+            _ => if let Some(name) = self.ninja_name(context)? {
+                out.push(name);
+            }
+        }
+        return Ok(());
+    }
+
+    pub(crate) fn ninja_name_not_escaped(&self, context: &Beaver) -> crate::Result<Option<String>> {
+        match self {
+            Dependency::Library(dep) => {
+                return context.with_project_and_target::<Option<String>, BeaverError>(&dep.target, |project, target| {
+                    Ok(Some(format!("{}:{}:{}", project.name(), target.name(), dep.artifact)))
+                });
+            },
+            Dependency::Flags { cflags: _, linker_flags: _, headers: _ } | Dependency::PkgConfig { .. } => {
+                return Ok(None);
+            },
+            Dependency::CMakeId(cmake_id) => {
+                return context.with_cmake_project_and_library(&cmake_id, |project, target| {
+                    Ok(target.map(|target| format!("{}:{}:{}", project.name(), target.name(), target.artifact)))
+                });
+            },
+            Dependency::Multi(_deps) => {
+                return Ok(None); // TODO
+            },
+            Dependency::File(file) => {
+                file.to_str()
+                    .ok_or(BeaverError::NonUTF8OsStr(file.as_os_str().to_os_string()))
+                    .map(|str| Some(str.to_string()))
+            }
+        }
+    }
+
+    pub(crate) fn public_cflags(&self, context: &Beaver, out: &mut Vec<String>, additional_file_dependencies: &mut Vec<PathBuf>) -> crate::Result<()> {
+        match self {
+            // Modules are loaded at runtime, dependents don't use their headers
+            Dependency::Library(dep) if dep.artifact == LibraryArtifactType::Module => Ok(()),
+            Dependency::Library(dep) => {
+                context.with_project_and_target::<(), BeaverError>(&dep.target, |proj, target| {
+                    let library = target.as_library().unwrap();
+                    library.public_cflags(proj.base_dir(), proj.build_dir(), out, additional_file_dependencies)?;
+                    library.artifact_cflags(dep.artifact, out);
+                    Ok(())
+                })
+            },
+            Dependency::Flags { cflags, linker_flags: _, headers } => {
+                if let Some(cflags) = cflags {
+                    out.extend_from_slice(cflags.as_slice());
+                }
+                if let Some(headers) = headers {
+                    context.with_current_project::<(), BeaverError>(|proj| {
+                        for header in headers {
+                            out.push(format!("-I{}", proj.base_dir().join(header).to_str()
+                                .ok_or_else(|| BeaverError::NonUTF8OsStr(proj.base_dir().join(header).into_os_string()))?));
+                        }
+                        Ok(())
+                    })?;
+                }
+                Ok(())
+            },
+            Dependency::PkgConfig { cflags, .. } => {
+                out.extend_from_slice(cflags.as_slice());
+                Ok(())
+            },
+            Dependency::CMakeId(cmake_id) => {
+                context.with_cmake_project_and_library(&cmake_id, |project, target| {
+                    if let Some(target) = target {
+                        target.public_cflags(project.base_dir(), project.build_dir(), out, additional_file_dependencies)
+                    } else {
+                        debug!("dependency unused: {}", cmake_id);
+                        Ok(())
+                    }
+                })
+            },
+            Dependency::Multi(deps) => {
+                deps.iter().map(|dep| dep.public_cflags(context, out, additional_file_dependencies)).collect()
+            },
+            Dependency::File(_) => Ok(())
+        }
+    }
+
+    pub(crate) fn linker_flags(&self, triple: &Triple, context: &Beaver, out: &mut Vec<String>, additional_files: &mut Vec<PathBuf>) -> crate::Result<()> {
+        match self {
+            Dependency::Library(dep) => {
+                context.with_project_and_target::<(), BeaverError>(&dep.target, |proj, target| {
+                    target.as_library().unwrap().link_against_library(proj.base_dir(), proj.build_dir(), dep.artifact, &triple, out, additional_files)
+                    // out.append(&mut target.as_library().unwrap().link_against_library(proj.build_dir(), dep.artifact, &triple)?);
+                })
+            },
+            Dependency::Flags { cflags: _, linker_flags, headers: _ } => {
+                if let Some(linker_flags) = linker_flags {
+                    out.extend_from_slice(linker_flags.as_slice());
+                }
+                // return Ok(linker_flags.clone());
+                Ok(())
+            },
+            Dependency::PkgConfig { linker_flags, .. } => {
+                out.extend_from_slice(linker_flags.as_slice());
+                Ok(())
+            },
+            Dependency::CMakeId(cmake_id) => {
+                context.with_cmake_project_and_library(&cmake_id, |project, target| {
+                    if let Some(target) = target {
+                        target.link_against_library(project.base_dir(), project.build_dir(), target.artifact, &triple, out, additional_files)
+                    } else {
+                        debug!("dependency unused: {}", cmake_id);
+                        Ok(())
+                    }
+                })
+            },
+            Dependency::Multi(deps) => {
+                deps.iter().map(|dep| dep.linker_flags(triple, context, out, additional_files)).collect()
+            },
+            Dependency::File(_) => Ok(())
+        }
+    }
+
+    /// Collects the directories of the dynamic libraries this dependency links against, which
+    /// have to be searched when running the dependent
+    pub(crate) fn runtime_library_dirs(&self, triple: &Triple, context: &Beaver, out: &mut Vec<PathBuf>) -> crate::Result<()> {
+        match self {
+            Dependency::Library(dep) => {
+                context.with_project_and_target::<(), BeaverError>(&dep.target, |proj, target| {
+                    if let Some(dir) = target.as_library().unwrap().runtime_library_dir(proj.build_dir(), dep.artifact, &triple)? {
+                        out.push(dir);
+                    }
+                    Ok(())
+                })
+            },
+            Dependency::CMakeId(cmake_id) => {
+                context.with_cmake_project_and_library(&cmake_id, |project, target| {
+                    if let Some(target) = target {
+                        if let Some(dir) = target.runtime_library_dir(project.build_dir(), target.artifact, &triple)? {
+                            out.push(dir);
+                        }
+                    }
+                    Ok(())
+                })
+            },
+            Dependency::Multi(deps) => {
+                deps.iter().map(|dep| dep.runtime_library_dirs(triple, context, out)).collect()
+            },
+            Dependency::Flags { .. } | Dependency::PkgConfig { .. } | Dependency::File(_) => Ok(())
+        }
+    }
+}
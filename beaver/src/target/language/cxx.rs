@@ -0,0 +1 @@
+pub static CXX_TO_C_LINKER_FLAGS: [&str; 1] = ["-lstdc++"];
@@ -0,0 +1,43 @@
+use crate::target::{Dependency, DependencyVisibility};
+
+/// The dependencies of a target, by how they are passed on to its dependents
+#[derive(Debug, Default)]
+pub struct Dependencies {
+    pub(crate) public: Vec<Dependency>,
+    pub(crate) private: Vec<Dependency>,
+    pub(crate) interface: Vec<Dependency>,
+}
+
+impl Dependencies {
+    pub fn new(public: Vec<Dependency>, private: Vec<Dependency>, interface: Vec<Dependency>) -> Dependencies {
+        Dependencies { public, private, interface }
+    }
+
+    pub(crate) fn get(&self, visibility: DependencyVisibility) -> &[Dependency] {
+        match visibility {
+            DependencyVisibility::Public => &self.public,
+            DependencyVisibility::Private => &self.private,
+            DependencyVisibility::Interface => &self.interface,
+        }
+    }
+
+    pub fn extend(&mut self, dependencies: impl IntoIterator<Item = Dependency>, visibility: DependencyVisibility) {
+        match visibility {
+            DependencyVisibility::Public => self.public.extend(dependencies),
+            DependencyVisibility::Private => self.private.extend(dependencies),
+            DependencyVisibility::Interface => self.interface.extend(dependencies),
+        }
+    }
+
+    /// The dependencies used to build the target itself
+    pub(crate) fn own(&self) -> impl Iterator<Item = &Dependency> {
+        self.public.iter().chain(self.private.iter())
+    }
+}
+
+/// All dependencies are public
+impl From<Vec<Dependency>> for Dependencies {
+    fn from(public: Vec<Dependency>) -> Dependencies {
+        Dependencies { public, private: Vec::new(), interface: Vec::new() }
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub struct Headers {
+    public: Vec<PathBuf>,
+    private: Vec<PathBuf>,
+    /// Include directories written by other targets. Steps including them depend on them, so
+    /// that the headers are generated first.
+    generated: Vec<PathBuf>,
+}
+
+impl Headers {
+    pub fn new(public: Vec<PathBuf>, private: Vec<PathBuf>) -> Headers {
+        Headers { public, private, generated: Vec::new() }
+    }
+
+    /// Add include directories generated by other targets. `dirs` are absolute paths
+    pub fn extend_generated(&mut self, dirs: impl IntoIterator<Item = PathBuf>, public: bool) {
+        for dir in dirs {
+            if public {
+                self.public.push(dir.clone());
+            } else {
+                self.private.push(dir.clone());
+            }
+            self.generated.push(dir);
+        }
+    }
+
+    pub(crate) fn public<'a>(&'a self, relative_to_path: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        self.public.iter().map(|path| {
+            relative_to_path.join(path)
+        })
+    }
+
+    pub(crate) fn private<'a>(&'a self, relative_to_path: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        self.private.iter().map(|path| {
+            relative_to_path.join(path)
+        })
+    }
+
+    /// Both public and private generated include directories
+    pub(crate) fn generated(&self) -> &[PathBuf] {
+        &self.generated
+    }
+
+    pub(crate) fn generated_public(&self) -> impl Iterator<Item = &PathBuf> {
+        self.generated.iter().filter(|dir| self.public.contains(dir))
+    }
+}
@@ -0,0 +1,7 @@
+use crate::moduse;
+
+moduse!(headers);
+moduse!(flags);
+moduse!(default_argument);
+moduse!(files);
+moduse!(dependencies);
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use pkgconfig_parser::{PkgConfig, Requirement};
+
+use crate::BeaverError;
+use super::{Dependency, PkgconfigOption};
+
+pub(crate) fn pkgconfig_collect_dependencies(pkg_config: &PkgConfig, base_dir: Option<&Path>) -> crate::Result<Option<Vec<Dependency>>> {
+    let base_dir = base_dir.map(|dir| std::path::absolute(dir)).transpose()?;
+    let options = match &base_dir {
+        Some(dir) => vec![PkgconfigOption::WithPath(dir.to_str().expect("Non UTF-8 path"))],
+        None => Vec::new()
+    };
+
+    pkg_config.requires().as_ref().map(|requires| {
+        // e.g. Requires: gobject-2.0 >=  2.62, harfbuzz >=  4.3.0
+        let requirements = Requirement::parse_list(requires)
+            .map_err(|err| BeaverError::PkgconfigMalformed(err.to_string()))?;
+        requirements.iter().map(|requirement| {
+            let constraint = requirement.constraint.as_ref().map(|(operator, version)| format!("{}{}", operator, version));
+            Dependency::pkgconfig(&requirement.name, constraint.as_deref(), &options, &[])
+        }).collect::<crate::Result<Vec<Dependency>>>()
+    }).map_or(Ok(None), |v| v.map(Some)) // Option<Result> to Result<Option>
+}
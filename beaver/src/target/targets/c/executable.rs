@@ -0,0 +1,472 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use log::warn;
+use target_lexicon::Triple;
+
+use crate::backend::{BackendBuilder, BackendBuilderScope, BuildStep};
+use crate::install::{InstallFile, InstallKind, InstallOptions, InstallSource};
+use crate::platform::{executable_extension_for_os, rpath_linker_flags_for_os, rpath_origin_for_os};
+use crate::target::parameters::{DefaultArgument, Dependencies, Files, Flags, Headers};
+use crate::target::{self, traits, ArtifactType, CObjectType, Dependency, ExecutableArtifactType, Language, Version};
+use crate::testing::{TestCase, TestSettings};
+use crate::traits::{Project, TargetType};
+use crate::{Beaver, BeaverError};
+
+use super::{object_file, CTarget, Setting, TargetDescriptor, C_LANGUAGES};
+
+#[derive(Debug)]
+pub struct Executable {
+    id: Option<usize>,
+    project_id: Option<usize>,
+    name: String,
+    description: Option<String>,
+    homepage: Option<url::Url>,
+    version: Option<Version>,
+    license: Option<String>,
+
+    language: Language,
+
+    sources: Files,
+    /// The language of the sources matching a pattern, instead of the language of their extension
+    source_languages: Vec<(Files, Language)>,
+    /// Additional cflags for the sources matching a pattern, e.g. to silence warnings in vendored code
+    source_flags: Vec<(Files, Vec<String>)>,
+
+    cflags: Flags,
+    headers: Headers,
+    linker_flags: Vec<String>,
+
+    artifacts: Vec<ExecutableArtifactType>,
+    dependencies: Dependencies,
+
+    settings: Vec<Setting>,
+
+    /// Set for test executables, which are run by `beaver test`
+    test: Option<TestSettings>,
+}
+
+impl Executable {
+    pub fn new_desc(desc: TargetDescriptor<ExecutableArtifactType>) -> crate::Result<Executable> {
+        Executable::new(
+            desc.name,
+            desc.description,
+            desc.homepage,
+            desc.version,
+            desc.license,
+            desc.language,
+            desc.sources,
+            desc.cflags,
+            desc.headers,
+            desc.linker_flags,
+            desc.artifacts,
+            desc.dependencies,
+            desc.settings
+        )
+    }
+
+    /// An executable which isn't built by default, but is run by `beaver test`
+    pub fn new_test_desc(desc: TargetDescriptor<ExecutableArtifactType>, test: TestSettings) -> crate::Result<Executable> {
+        let mut executable = Executable::new_desc(desc)?;
+        executable.test = Some(test);
+        return Ok(executable);
+    }
+
+    pub fn new(
+        name: String,
+        description: Option<String>,
+        homepage: Option<url::Url>,
+        version: Option<Version>,
+        license: Option<String>,
+        language: Language,
+        sources: Files,
+        cflags: Flags,
+        headers: Headers,
+        linker_flags: Vec<String>,
+        artifacts: DefaultArgument<Vec<ExecutableArtifactType>>,
+        dependencies: Vec<Dependency>,
+        settings: Vec<Setting>
+    ) -> crate::Result<Executable> {
+        target::utils::check_language(&C_LANGUAGES, &language, "C")?;
+
+        let artifacts = artifacts.or_default(vec![ExecutableArtifactType::Executable]);
+        let valid_artifacts = HashSet::from([ExecutableArtifactType::App, ExecutableArtifactType::Executable]);
+        target::utils::check_artifacts(&valid_artifacts, &artifacts, "C")?;
+
+        Ok(Executable {
+            id: None,
+            project_id: None,
+            name,
+            description,
+            homepage,
+            version,
+            license,
+            language,
+            sources,
+            source_languages: Vec::new(),
+            source_flags: Vec::new(),
+            cflags,
+            headers,
+            linker_flags,
+            artifacts,
+            dependencies: dependencies.into(),
+            settings,
+            test: None
+        })
+    }
+
+    /// Replace the dependencies, e.g. to also give the target private and interface dependencies
+    pub fn set_dependencies(&mut self, dependencies: Dependencies) {
+        self.dependencies = dependencies;
+    }
+
+    /// Compile the sources matching each pattern as a language other than the one of their
+    /// extension
+    pub fn set_source_languages(&mut self, source_languages: Vec<(Files, Language)>) -> crate::Result<()> {
+        for (_, language) in source_languages.iter() {
+            target::utils::check_language(&C_LANGUAGES, language, "C")?;
+        }
+        self.source_languages = source_languages;
+        return Ok(());
+    }
+
+    /// Compile the sources matching each pattern with additional cflags
+    pub fn set_source_flags(&mut self, source_flags: Vec<(Files, Vec<String>)>) {
+        self.source_flags = source_flags;
+    }
+}
+
+
+impl traits::Target for Executable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| s.as_str())
+    }
+
+    fn homepage(&self) -> Option<&url::Url> {
+        self.homepage.as_ref()
+    }
+
+    fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.license.as_ref().map(|s| s.as_str())
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn link_language(&self) -> crate::Result<Language> {
+        self.source_link_language()
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    fn set_id(&mut self, new_id: usize) {
+        self.id = Some(new_id);
+    }
+
+    fn project_id(&self) -> Option<usize> {
+        self.project_id
+    }
+
+    fn set_project_id(&mut self, new_id: usize) {
+        self.project_id = Some(new_id);
+    }
+
+    fn artifacts(&self) -> Vec<crate::target::ArtifactType> {
+        self.artifacts.iter().map(|a| ArtifactType::Executable(*a)).collect()
+    }
+
+    fn dependencies(&self) -> crate::Result<Cow<'_, [Dependency]>> {
+        Ok(Cow::Owned(self.dependencies.own().cloned().collect()))
+    }
+
+    fn r#type(&self) -> TargetType {
+        TargetType::Executable
+    }
+
+    fn artifact_file(&self, project_build_dir: &Path, artifact: ArtifactType, target_triple: &Triple) -> crate::Result<PathBuf> {
+        let dir = self.artifact_output_dir(project_build_dir, target_triple);
+        return match artifact {
+            ArtifactType::Executable(exe) => match exe {
+                ExecutableArtifactType::Executable => {
+                    let mut path = dir.join(&self.name);
+                    if let Ok(Some(ext)) = executable_extension_for_os(&target_triple.operating_system) {
+                        path.set_extension(ext);
+                    }
+                    Ok(path)
+                },
+                ExecutableArtifactType::App => {
+                    let mut path = dir.join(&self.name);
+                    path.set_extension("app");
+                    Ok(path)
+                }
+            },
+            ArtifactType::Library(_) => panic!("bug"),
+        }
+    }
+
+    fn register<Builder: BackendBuilder<'static>>(&self,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>
+    ) -> crate::Result<String> {
+        CTarget::register_impl(
+            self,
+            project_name,
+            project_base_dir,
+            project_build_dir,
+            target_triple,
+            builder,
+            scope,
+            &self.cc_rules()?.into_iter().chain([self.link_rule()?]).collect::<Vec<_>>(),
+            context
+        )
+    }
+
+    fn debug_attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attributes = vec![
+            ("sources", format!("{:?}", self.sources.resolve().unwrap())),
+            ("cflags", format!("{:?}", self.cflags)),
+            ("headers", format!("{:?}", self.headers)),
+            ("linker_flags", self.linker_flags.join(", ")),
+        ];
+        if let Some(test) = &self.test {
+            attributes.push(("test", format!("{:?}", test)));
+        }
+        attributes
+    }
+
+    fn is_test(&self) -> bool {
+        self.test.is_some()
+    }
+
+    fn test_case(&self, project_name: &str, project_base_dir: &Path, project_build_dir: &Path, triple: &Triple) -> crate::Result<Option<TestCase>> {
+        let Some(test) = &self.test else {
+            return Ok(None);
+        };
+
+        let program = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Executable(ExecutableArtifactType::Executable), triple)?;
+        let name = format!("{}:{}", project_name, self.name);
+        return Ok(Some(TestCase {
+            name: name.clone(),
+            project: project_name.to_string(),
+            program: std::path::absolute(program)?,
+            args: test.args.clone(),
+            working_dir: project_base_dir.to_path_buf(),
+            env: Vec::new(),
+            timeout: test.timeout,
+            build_step: Some(name),
+        }));
+    }
+
+    fn install_files(&self, _project_base_dir: &Path, project_build_dir: &Path, triple: &Triple, options: &InstallOptions, context: &Beaver) -> crate::Result<Vec<InstallFile>> {
+        if self.test.is_some() {
+            return Ok(Vec::new());
+        }
+
+        let (link_dependencies, _) = self.link_dependencies(context)?;
+        let build_rpaths = self.rpaths(project_build_dir, link_dependencies.iter(), triple, context)?;
+
+        let mut files = Vec::new();
+        for artifact in &self.artifacts {
+            match artifact {
+                ExecutableArtifactType::Executable => {
+                    let file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Executable(*artifact), triple)?;
+                    files.push(InstallFile {
+                        destination: options.dirs.bindir.join(file.file_name().unwrap()),
+                        source: if build_rpaths.is_empty() {
+                            InstallSource::File(file)
+                        } else {
+                            InstallSource::Binary { file, build_rpaths: build_rpaths.clone() }
+                        },
+                        kind: InstallKind::Executable,
+                    });
+                },
+                ExecutableArtifactType::App => warn!("Installing app artifacts is not supported, skipping the app of {}", self.name),
+            }
+        }
+        return Ok(files);
+    }
+}
+
+impl CTarget for Executable {
+    type TargetArtifactType = ExecutableArtifactType;
+
+    fn user_cflags(&self) -> impl Iterator<Item = &String> {
+        self.cflags.public.iter()
+            .chain(self.cflags.private.iter())
+    }
+
+    fn all_headers<'a>(&'a self, project_base_dir: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        self.headers.public(project_base_dir)
+            .chain(self.headers.private(project_base_dir))
+    }
+
+    fn generated_headers(&self) -> &[PathBuf] {
+        self.headers.generated()
+    }
+
+    fn target_artifacts(&self) -> &[Self::TargetArtifactType] {
+        &self.artifacts
+    }
+
+    fn sources(&self) -> &Files {
+        &self.sources
+    }
+
+    fn source_language_overrides(&self) -> &[(Files, Language)] {
+        &self.source_languages
+    }
+
+    fn source_flag_overrides(&self) -> &[(Files, Vec<String>)] {
+        &self.source_flags
+    }
+
+    fn settings(&self) -> &[Setting] {
+        &self.settings
+    }
+
+    /// All linker flags used by this executable when linking
+    fn linker_flags<'a>(&self, dependencies: impl Iterator<Item = &'a Dependency>, languages: impl Iterator<Item = &'a Language>, triple: &Triple, context: &Beaver) -> crate::Result<(Vec<String>, Vec<PathBuf>)> {
+        let mut flags: Vec<String> = self.linker_flags.clone();
+
+        let dependencies: Vec<&Dependency> = dependencies.collect();
+        let mut additional_files = Vec::new();
+        for dependency in &dependencies {
+            dependency.linker_flags(triple, context, &mut flags, &mut additional_files)?;
+        }
+
+        // Dynamic libraries are found without setting the library search path when running
+        // from the build directory. The `$` is escaped for the build file and the shell.
+        let project_id = self.project_id.ok_or_else(|| BeaverError::TargetNotInProject(self.name.clone()))?;
+        let project_build_dir = context.with_project(project_id, |project| Ok(project.build_dir().to_path_buf()))?;
+        let rpaths = self.rpaths(&project_build_dir, dependencies.into_iter(), triple, context)?;
+        flags.extend(rpath_linker_flags_for_os(&triple.operating_system, &rpaths).into_iter()
+            .map(|flag| flag.replace('$', "\\$$")));
+        let link_language = self.source_link_language()?;
+        for lang in languages {
+            let Some(lang_flags) = Language::linker_flags(*lang, link_language, triple) else { continue };
+            flags.extend(lang_flags.iter().map(|str| str.to_string()))
+        }
+
+        flags.extend(context.linker_flags()?);
+
+        if (link_language == Language::OBJC || link_language == Language::OBJCXX) && self.settings.contains(&Setting::ObjCArc) {
+            flags.push("-fobjc-arc".to_string());
+        }
+
+        return Ok((flags, additional_files));
+    }
+
+    fn register_artifact<Scope: BackendBuilderScope>(
+        &self,
+        artifact: &Self::TargetArtifactType,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        dependency_steps: &[&str],
+        cflags: &HashMap<Language, String>,
+        linker_flags: &str,
+        additional_artifact_files: &[PathBuf],
+        additional_dependency_files: &[&str],
+        builder: &mut Scope
+    ) -> crate::Result<String> {
+        let link_rule = self.link_rule()?;
+
+        match artifact {
+            ExecutableArtifactType::Executable => {
+                let object_dir = self.object_dir(project_build_dir, CObjectType::Static);
+                let mut object_files: Vec<PathBuf> = Vec::new();
+                let sources = self.sources.resolve()?;
+                if sources.len() == 0 { warn!("No sources in C::Executable {}", self.name); }
+                for source in sources {
+                    object_files.push(object_file(&object_dir, source, project_base_dir, project_build_dir));
+
+                    self.add_compile_step(source, &object_files[object_files.len() - 1], cflags, "", additional_dependency_files, builder)?;
+                }
+
+                // Static libraries come after the objects using them
+                object_files.extend(additional_artifact_files.iter().cloned());
+
+                let artifact_file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Executable(*artifact), target_triple)?;
+                builder.add_step(&BuildStep::Build {
+                    rule: link_rule,
+                    output: &artifact_file,
+                    input: &object_files.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                    dependencies: dependency_steps,
+                    options: &[("linkerFlags", linker_flags)]
+                })?;
+
+                let artifact_step = format!("{}$:{}$:{}", project_name, &self.name, artifact);
+                builder.add_step(&BuildStep::Phony {
+                    name: &artifact_step,
+                    args: &[Scope::format_path(builder, artifact_file).to_str().unwrap()],
+                    // args: &[&artifact_file.to_str().unwrap()],
+                    dependencies: &[]
+                })?;
+
+                return Ok(artifact_step);
+            },
+            ExecutableArtifactType::App => {
+                todo!("App have a dependency on executable and then construct app")
+            }
+        }
+    }
+}
+
+impl traits::Executable for Executable {
+    fn executable_artifacts(&self) -> Vec<ExecutableArtifactType> {
+        self.artifacts.clone()
+    }
+}
+
+impl Executable {
+    /// The rpaths to the dynamic libraries the executable links against, relative to the
+    /// executable when possible
+    fn rpaths<'a>(&self, project_build_dir: &Path, dependencies: impl Iterator<Item = &'a Dependency>, triple: &Triple, context: &Beaver) -> crate::Result<Vec<String>> {
+        let Some(origin) = rpath_origin_for_os(&triple.operating_system) else {
+            return Ok(Vec::new());
+        };
+
+        let mut dirs = Vec::new();
+        for dependency in dependencies {
+            dependency.runtime_library_dirs(triple, context, &mut dirs)?;
+        }
+
+        let artifact_dir = std::path::absolute(self.artifact_output_dir(project_build_dir, triple))?;
+        let mut rpaths: Vec<String> = Vec::new();
+        for dir in dirs {
+            let rpath = match pathdiff::diff_paths(&dir, &artifact_dir) {
+                Some(relative) if relative.as_os_str().is_empty() => origin.to_string(),
+                Some(relative) => format!("{}/{}", origin, relative.display()),
+                None => dir.display().to_string()
+            };
+            if !rpaths.contains(&rpath) {
+                rpaths.push(rpath);
+            }
+        }
+        return Ok(rpaths);
+    }
+
+    fn artifact_output_dir(&self,  project_build_dir: &Path, target_triple: &Triple) -> PathBuf {
+        _ = target_triple; // todo: support cross-compiling in the future
+        project_build_dir.join("artifacts")
+    }
+}
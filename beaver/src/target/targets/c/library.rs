@@ -0,0 +1,1008 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use log::{trace, warn};
+use pkgconfig_parser::PkgConfig;
+use target_lexicon::{OperatingSystem, Triple};
+
+use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep};
+use crate::install::{self, InstallFile, InstallKind, InstallOptions, InstallSource};
+use crate::platform::{dynlib_extension_for_os, dynlib_linker_flags_for_os, exports_file_for_os, exports_linker_flags_for_os, module_extension_for_os, module_linker_flags_for_os, soname_linker_flags_for_os, staticlib_extension_for_os, versioned_dynlib_file_name};
+use crate::target::parameters::{DefaultArgument, Dependencies, Files, Flags, Headers};
+use crate::target::traits::{self, AnyLibrary, TargetType};
+use crate::target::{self, ArtifactType, CObjectType, Dependency, DependencyVisibility, Language, LibraryArtifactType, Version};
+use crate::traits::Library as _;
+use crate::{Beaver, BeaverError};
+
+use super::{object_file, CTarget, Setting, TargetDescriptor, C_LANGUAGES};
+
+/// The symbols exported by a dynamic library
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exports {
+    Symbols(Vec<String>),
+    /// A linker version script (an exported symbols list on macOS) or a module-definition
+    /// (`.def`) file, relative to the project
+    File(PathBuf),
+}
+
+//TODO #[init_descriptor(super::TargetDescriptor, false)]
+#[derive(Debug)]
+pub struct Library {
+    id: Option<usize>,
+    project_id: Option<usize>,
+    name: String,
+    description: Option<String>,
+    homepage: Option<url::Url>,
+    version: Option<Version>,
+    /// Overrides the version in the soname of the dynamic library
+    abi_version: Option<String>,
+    /// The symbols exported by the dynamic library. By default all visible symbols are exported.
+    exports: Option<Exports>,
+    license: Option<String>,
+
+    language: Language,
+
+    sources: Files,
+    /// The language of the sources matching a pattern, instead of the language of their extension
+    source_languages: Vec<(Files, Language)>,
+    /// Additional cflags for the sources matching a pattern, e.g. to silence warnings in vendored code
+    source_flags: Vec<(Files, Vec<String>)>,
+
+    cflags: Flags,
+    headers: Headers,
+    linker_flags: Vec<String>,
+
+    artifacts: Vec<LibraryArtifactType>,
+    dependencies: Dependencies,
+
+    settings: Vec<crate::target::c::Setting>,
+}
+
+impl Library {
+    pub fn new_desc(desc: TargetDescriptor<LibraryArtifactType>) -> crate::Result<Library> {
+        Library::new(
+            desc.name,
+            desc.description,
+            desc.homepage,
+            desc.version,
+            desc.license,
+            desc.language,
+            desc.sources,
+            desc.cflags,
+            desc.headers,
+            desc.linker_flags,
+            desc.artifacts,
+            desc.dependencies,
+            desc.settings
+        )
+    }
+
+    /// A library without sources, which only passes on its public headers, flags and dependencies
+    pub fn new_interface_desc(mut desc: TargetDescriptor<LibraryArtifactType>) -> crate::Result<Library> {
+        let mut artifacts = desc.artifacts.or_default(Vec::new());
+        if !artifacts.contains(&LibraryArtifactType::Interface) {
+            artifacts.insert(0, LibraryArtifactType::Interface);
+        }
+        desc.artifacts = DefaultArgument::Some(artifacts);
+        Library::new_desc(desc)
+    }
+
+    pub fn new(
+        name: String,
+        description: Option<String>,
+        homepage: Option<url::Url>,
+        version: Option<Version>,
+        license: Option<String>,
+        language: Language,
+        sources: Files,
+        cflags: Flags,
+        headers: Headers,
+        linker_flags: Vec<String>,
+        artifacts: DefaultArgument<Vec<LibraryArtifactType>>,
+        dependencies: Vec<Dependency>,
+        settings: Vec<Setting>
+    ) -> crate::Result<Library> {
+        target::utils::check_language(&C_LANGUAGES, &language, "C")?;
+
+        let artifacts = artifacts.or_default(vec![
+            LibraryArtifactType::Dynlib,
+            LibraryArtifactType::Staticlib,
+            // LibraryArtifactType::PkgConfig,
+        ]);
+        let valid_artifacts = HashSet::from([LibraryArtifactType::Dynlib, LibraryArtifactType::Staticlib, LibraryArtifactType::Framework, LibraryArtifactType::XCFramework, LibraryArtifactType::PkgConfig, LibraryArtifactType::JSLib, LibraryArtifactType::Module, LibraryArtifactType::Objects, LibraryArtifactType::Interface]);
+        target::utils::check_artifacts(&valid_artifacts, &artifacts, "C")?;
+
+        if artifacts.contains(&LibraryArtifactType::Interface) {
+            if let Some(artifact) = artifacts.iter().find(|artifact| !matches!(artifact, LibraryArtifactType::Interface | LibraryArtifactType::PkgConfig)) {
+                return Err(BeaverError::InterfaceLibraryArtifact(name, *artifact));
+            }
+            if sources.resolve()?.len() > 0 {
+                return Err(BeaverError::InterfaceLibraryWithSources(name));
+            }
+        }
+
+        Ok(Library {
+            id: None,
+            project_id: None,
+            name,
+            description,
+            homepage,
+            version,
+            abi_version: None,
+            exports: None,
+            license,
+            language,
+            sources,
+            source_languages: Vec::new(),
+            source_flags: Vec::new(),
+            cflags,
+            headers,
+            linker_flags,
+            artifacts,
+            dependencies: dependencies.into(),
+            settings
+        })
+    }
+
+    /// Set the version in the soname of the dynamic library. By default this is the major version.
+    pub fn set_abi_version(&mut self, abi_version: String) {
+        self.abi_version = Some(abi_version);
+    }
+
+    /// Replace the dependencies, e.g. to also give the target private and interface dependencies
+    pub fn set_dependencies(&mut self, dependencies: Dependencies) {
+        self.dependencies = dependencies;
+    }
+
+    /// Only export these symbols from the dynamic library
+    pub fn set_exports(&mut self, exports: Exports) {
+        self.exports = Some(exports);
+    }
+
+    /// Compile the sources matching each pattern as a language other than the one of their
+    /// extension
+    pub fn set_source_languages(&mut self, source_languages: Vec<(Files, Language)>) -> crate::Result<()> {
+        for (_, language) in source_languages.iter() {
+            target::utils::check_language(&C_LANGUAGES, language, "C")?;
+        }
+        self.source_languages = source_languages;
+        return Ok(());
+    }
+
+    /// Compile the sources matching each pattern with additional cflags
+    pub fn set_source_flags(&mut self, source_flags: Vec<(Files, Vec<String>)>) {
+        self.source_flags = source_flags;
+    }
+
+    fn abi_version(&self) -> Option<String> {
+        if let Some(abi_version) = &self.abi_version {
+            return Some(abi_version.clone());
+        }
+        match self.version.as_ref()? {
+            Version::Semver(version) => Some(version.major.to_string()),
+            Version::Any(version) => version.split('.').next().map(|major| major.to_string()),
+        }
+    }
+
+    /// The file names of the versioned dynamic library and of its soname (e.g. `libfoo.so.1.2.3`
+    /// and `libfoo.so.1`). The unversioned file name links to the soname, which links to the
+    /// library. `None` when the library has no version, or the OS doesn't version dynamic
+    /// libraries by their file names. The links are made with `ln`, so there is no chain when
+    /// building on Windows.
+    fn versioned_dynlib_file_names(&self, target_triple: &Triple) -> crate::Result<Option<(String, String)>> {
+        if cfg!(windows) {
+            return Ok(None);
+        }
+        let Some(abi_version) = self.abi_version() else { return Ok(None) };
+        let version = self.version.as_ref().map(|version| version.to_string()).unwrap_or(abi_version.clone());
+        let os = &target_triple.operating_system;
+        match (versioned_dynlib_file_name(os, &self.name, &version)?, versioned_dynlib_file_name(os, &self.name, &abi_version)?) {
+            (Some(file), Some(soname)) => Ok(Some((file, soname))),
+            _ => Ok(None)
+        }
+    }
+
+    /// How the objects linked into `artifact` are compiled. Dynamic libraries always have their own
+    /// objects, as they are compiled with another export macro than the objects of static libraries.
+    fn object_type(artifact: LibraryArtifactType) -> Option<CObjectType> {
+        match artifact {
+            LibraryArtifactType::Dynlib | LibraryArtifactType::Module => Some(CObjectType::Dynamic),
+            LibraryArtifactType::Staticlib | LibraryArtifactType::JSLib | LibraryArtifactType::Objects => Some(CObjectType::Static),
+            _ => None
+        }
+    }
+
+    /// Write the pkg-config file of this library
+    fn write_pkgconfig(&self, project_base_dir: &Path, project_build_dir: &Path, target_triple: &Triple, context: &Beaver) -> crate::Result<()> {
+        let file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Library(LibraryArtifactType::PkgConfig), target_triple)?;
+        let contents = self.pkgconfig(project_base_dir, project_build_dir, target_triple, None, context)?.to_string();
+        Self::write_if_changed(&file, &contents)
+    }
+
+    /// Files generated while registering are only written when their contents changed, so that
+    /// dependents aren't rebuilt unnecessarily.
+    fn write_if_changed(file: &Path, contents: &str) -> crate::Result<()> {
+        if std::fs::read_to_string(file).is_ok_and(|current| current == contents) {
+            return Ok(());
+        }
+
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(file, contents)
+            .map_err(|err| BeaverError::io(err, format!("while writing {:?}", file)))
+    }
+
+    /// Libraries loaded dynamically get a header defining `<NAME>_API`, which marks the symbols
+    /// exported by the library
+    fn has_export_header(&self) -> bool {
+        self.artifacts.iter().any(|artifact| matches!(artifact, LibraryArtifactType::Dynlib | LibraryArtifactType::Module))
+    }
+
+    /// The name of the library in macros, e.g. `MY_LIB` for `my-lib`
+    fn macro_name(&self) -> String {
+        self.name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect()
+    }
+
+    fn export_header_dir(&self, project_build_dir: &Path) -> PathBuf {
+        project_build_dir.join("export").join(&self.name)
+    }
+
+    /// The macro defined while compiling objects of `object_type`, for the export header
+    fn export_define(&self, object_type: CObjectType) -> Option<String> {
+        if !self.has_export_header() {
+            return None;
+        }
+        Some(match object_type {
+            CObjectType::Dynamic => format!("-D{}_BUILDING", self.macro_name()),
+            CObjectType::Static => format!("-D{}_STATIC", self.macro_name()),
+        })
+    }
+
+    fn export_header_name(&self) -> String {
+        format!("{}_export.h", self.macro_name().to_lowercase())
+    }
+
+    /// Write `<name>_export.h`. `<NAME>_BUILDING` is defined while compiling the dynamic library,
+    /// which exports the symbols, while consumers import them. The static library and objects, and
+    /// their consumers, define `<NAME>_STATIC`.
+    fn write_export_header(&self, project_build_dir: &Path) -> crate::Result<()> {
+        let name = self.macro_name();
+        let contents = format!(concat!(
+            "/* Generated by beaver */\n",
+            "#ifndef {name}_EXPORT_H\n",
+            "#define {name}_EXPORT_H\n",
+            "\n",
+            "#if defined({name}_STATIC)\n",
+            "#  define {name}_API\n",
+            "#elif defined(_WIN32) || defined(__CYGWIN__)\n",
+            "#  if defined({name}_BUILDING)\n",
+            "#    define {name}_API __declspec(dllexport)\n",
+            "#  else\n",
+            "#    define {name}_API __declspec(dllimport)\n",
+            "#  endif\n",
+            "#elif defined(__GNUC__) || defined(__clang__)\n",
+            "#  define {name}_API __attribute__((visibility(\"default\")))\n",
+            "#else\n",
+            "#  define {name}_API\n",
+            "#endif\n",
+            "\n",
+            "#endif\n"
+        ), name = name);
+        Self::write_if_changed(&self.export_header_dir(project_build_dir).join(self.export_header_name()), &contents)
+    }
+
+    /// The file limiting the symbols exported by the dynamic library, which is generated for a list
+    /// of symbols
+    fn exports_file(&self, project_base_dir: &Path, project_build_dir: &Path, target_triple: &Triple) -> crate::Result<Option<PathBuf>> {
+        let os = &target_triple.operating_system;
+        let symbols = match &self.exports {
+            None => return Ok(None),
+            Some(Exports::File(file)) => {
+                let file = project_base_dir.join(file);
+                // Module-definition files are only understood on Windows, elsewhere their exports are used
+                if file.extension().is_none_or(|ext| ext != "def") || *os == OperatingSystem::Windows {
+                    return Ok(Some(file));
+                }
+                let contents = std::fs::read_to_string(&file)
+                    .map_err(|err| BeaverError::io(err, format!("while reading {}", file.display())))?;
+                Self::def_file_exports(&contents)
+            },
+            Some(Exports::Symbols(symbols)) => symbols.clone()
+        };
+
+        let Some((ext, contents)) = exports_file_for_os(os, &symbols) else {
+            warn!("Limiting the exported symbols isn't supported for {}, all symbols of {} are exported", os, self.name);
+            return Ok(None);
+        };
+        let file = project_build_dir.join("objects").join(&self.name).join(format!("exports.{}", ext));
+        Self::write_if_changed(&file, &contents)?;
+        return Ok(Some(file));
+    }
+
+    /// The names in the `EXPORTS` section of a module-definition file
+    fn def_file_exports(contents: &str) -> Vec<String> {
+        const SECTIONS: &[&str] = &["NAME", "LIBRARY", "DESCRIPTION", "STACKSIZE", "HEAPSIZE", "SECTIONS", "VERSION", "EXPORTS"];
+
+        let mut exports = Vec::new();
+        let mut in_exports = false;
+        for line in contents.lines() {
+            let line = line.split(';').next().unwrap().trim();
+            let Some(first) = line.split_whitespace().next() else { continue };
+            if SECTIONS.contains(&first) {
+                in_exports = first == "EXPORTS";
+                continue;
+            }
+            if in_exports {
+                // e.g. `name=internal_name @1`
+                exports.push(first.split('=').next().unwrap().to_string());
+            }
+        }
+        return exports;
+    }
+
+    /// The pkg-config description of this library, for use by consumers outside of beaver
+    ///
+    /// Dependencies found using pkg-config and beaver libraries with a pkg-config artifact
+    /// are added to `Requires`. The flags of other dependencies are added to `Cflags` and
+    /// `Libs.private`.
+    ///
+    /// When `install` is passed, the file refers to the library and headers installed in the
+    /// prefix instead of the build directory.
+    fn pkgconfig(&self, project_base_dir: &Path, project_build_dir: &Path, target_triple: &Triple, install: Option<&InstallOptions>, context: &Beaver) -> crate::Result<PkgConfig<'static>> {
+        let mut pkgconfig = PkgConfig::new(
+            self.name.clone(),
+            self.description.clone().unwrap_or(self.name.clone()),
+            self.version.as_ref().map(|version| version.to_string()).unwrap_or("0".to_string())
+        );
+        if let Some(homepage) = &self.homepage {
+            pkgconfig.set_url(homepage.to_string());
+        }
+
+        let mut requires = Vec::new();
+        let mut requires_private = Vec::new();
+        let mut cflags = Vec::new();
+        let mut libs_private = Vec::new();
+        let mut include_installed_headers = false;
+        match install {
+            None => {
+                let libdir = std::path::absolute(traits::Library::artifact_output_dir(self, project_build_dir, target_triple))?;
+                pkgconfig.add_variable("libdir", libdir.display().to_string());
+                traits::Library::public_cflags(self, project_base_dir, project_build_dir, &mut cflags, &mut Vec::new())?;
+            },
+            Some(options) => {
+                let prefixed = |dir: &Path| if dir.is_absolute() {
+                    dir.display().to_string()
+                } else {
+                    format!("${{prefix}}/{}", dir.display())
+                };
+                pkgconfig.add_variable("prefix", options.prefix.display().to_string());
+                pkgconfig.add_variable("libdir", prefixed(&options.dirs.libdir));
+                pkgconfig.add_variable("includedir", prefixed(&options.dirs.includedir));
+                cflags.extend(self.cflags.public.iter().cloned());
+                include_installed_headers = self.headers.public(project_base_dir).next().is_some() || self.has_export_header();
+            }
+        }
+        for dependency in self.dependencies.public.iter().chain(self.dependencies.interface.iter()) {
+            Self::pkgconfig_dependency(dependency, target_triple, context, &mut requires, &mut cflags, &mut libs_private)?;
+        }
+        // Consumers only need private dependencies for linking, not for compiling
+        for dependency in &self.dependencies.private {
+            Self::pkgconfig_dependency(dependency, target_triple, context, &mut requires_private, &mut Vec::new(), &mut libs_private)?;
+        }
+
+        let quote = |flags: &[String]| flags.iter()
+            .map(|flag| shlex::try_quote(flag).map(|flag| flag.to_string()).unwrap_or(flag.clone()))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        // An interface library has no library file to link against
+        let mut libs = if self.artifacts.contains(&LibraryArtifactType::Interface) {
+            String::new()
+        } else {
+            format!("-L${{libdir}} -l{}", self.name)
+        };
+        if self.linker_flags.len() > 0 {
+            if libs.len() > 0 { libs.push(' '); }
+            libs.push_str(&quote(&self.linker_flags));
+        }
+
+        if requires.len() > 0 {
+            pkgconfig.set_requires(requires.join(", "));
+        }
+        if requires_private.len() > 0 {
+            pkgconfig.set_requires_private(requires_private.join(", "));
+        }
+        if libs.len() > 0 {
+            pkgconfig.set_libs(libs);
+        }
+        if libs_private.len() > 0 {
+            pkgconfig.set_libs_private(quote(&libs_private));
+        }
+        if include_installed_headers {
+            // Not quoted, so that the variable is expanded
+            pkgconfig.set_cflags(if cflags.len() > 0 { format!("{} -I${{includedir}}", quote(&cflags)) } else { String::from("-I${includedir}") });
+        } else if cflags.len() > 0 {
+            pkgconfig.set_cflags(quote(&cflags));
+        }
+
+        return Ok(pkgconfig);
+    }
+
+    fn pkgconfig_dependency(
+        dependency: &Dependency,
+        target_triple: &Triple,
+        context: &Beaver,
+        requires: &mut Vec<String>,
+        cflags: &mut Vec<String>,
+        libs_private: &mut Vec<String>
+    ) -> crate::Result<()> {
+        match dependency {
+            Dependency::PkgConfig { name, version_requirement, .. } => {
+                requires.push(match version_requirement {
+                    Some(requirement) => {
+                        let (operator, version) = pkgconfig_parser::Requirement::parse_constraint(requirement)
+                            .map_err(|_| BeaverError::PkgconfigMalformedVersionRequirement(requirement.clone()))?;
+                        format!("{} {} {}", name, operator, version)
+                    },
+                    None => name.clone()
+                });
+                return Ok(());
+            },
+            Dependency::Library(library_dependency) => {
+                let pkgconfig_name = context.with_project_and_target::<Option<String>, BeaverError>(&library_dependency.target, |_, target| {
+                    Ok(match target.as_library() {
+                        Some(AnyLibrary::C(library)) if library.artifacts.contains(&LibraryArtifactType::PkgConfig) => Some(library.name.clone()),
+                        _ => None
+                    })
+                })?;
+                if let Some(name) = pkgconfig_name {
+                    requires.push(name);
+                    return Ok(());
+                }
+            },
+            Dependency::Multi(dependencies) => {
+                for dependency in dependencies {
+                    Self::pkgconfig_dependency(dependency, target_triple, context, requires, cflags, libs_private)?;
+                }
+                return Ok(());
+            },
+            Dependency::File(_) => return Ok(()),
+            Dependency::Flags { .. } | Dependency::CMakeId(_) => {}
+        }
+
+        let mut files = Vec::new();
+        dependency.public_cflags(context, cflags, &mut files)?;
+        dependency.linker_flags(target_triple, context, libs_private, &mut files)?;
+        libs_private.extend(files.iter().map(|file| file.display().to_string()));
+
+        return Ok(());
+    }
+}
+
+impl traits::Target for Library {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| s.as_str())
+    }
+
+    fn homepage(&self) -> Option<&url::Url> {
+        self.homepage.as_ref()
+    }
+
+    fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.license.as_ref().map(|s| s.as_str())
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn link_language(&self) -> crate::Result<Language> {
+        self.source_link_language()
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    fn set_id(&mut self, new_id: usize) {
+        self.id = Some(new_id);
+    }
+
+    fn project_id(&self) -> Option<usize> {
+        self.project_id
+    }
+
+    fn set_project_id(&mut self, new_id: usize) {
+        self.project_id = Some(new_id);
+    }
+
+    fn artifacts(&self) -> Vec<crate::target::ArtifactType> {
+        self.artifacts.iter().map(|a| ArtifactType::Library(*a)).collect()
+    }
+
+    fn dependencies(&self) -> crate::Result<Cow<'_, [Dependency]>> {
+        Ok(Cow::Owned(self.dependencies.own().cloned().collect()))
+    }
+
+    fn visible_dependencies(&self, visibility: DependencyVisibility) -> crate::Result<Cow<'_, [Dependency]>> {
+        Ok(Cow::Borrowed(self.dependencies.get(visibility)))
+    }
+
+    fn r#type(&self) -> TargetType {
+        TargetType::Library
+    }
+
+    fn artifact_file(&self, project_build_dir: &std::path::Path, artifact: ArtifactType, target_triple: &Triple) -> crate::Result<std::path::PathBuf> {
+        let dir = self.artifact_output_dir(project_build_dir, target_triple);
+        return match artifact {
+            ArtifactType::Library(lib) => match lib {
+                LibraryArtifactType::Dynlib => Ok(dir.join(format!("lib{}.{}", self.name, dynlib_extension_for_os(&target_triple.operating_system)?))),
+                LibraryArtifactType::Staticlib => Ok(dir.join(format!("lib{}.{}", self.name, staticlib_extension_for_os(&target_triple.operating_system)?))),
+                LibraryArtifactType::Module => Ok(dir.join(format!("{}.{}", self.name, module_extension_for_os(&target_triple.operating_system)?))),
+                LibraryArtifactType::PkgConfig => Ok(dir.join(format!("{}.pc", self.name))),
+                LibraryArtifactType::Objects | LibraryArtifactType::Interface => Err(BeaverError::ArtifactWithoutFile(artifact)),
+                LibraryArtifactType::Framework => {
+                    if !target_triple.operating_system.is_like_darwin() {
+                        Err(BeaverError::TargetDoesntSupportFrameworks(target_triple.operating_system))
+                    } else {
+                        Ok(dir.join(format!("{}.framework", self.name)))
+                    }
+                },
+                LibraryArtifactType::XCFramework => {
+                    if !target_triple.operating_system.is_like_darwin() {
+                        Err(BeaverError::TargetDoesntSupportFrameworks(target_triple.operating_system))
+                    } else {
+                        Ok(dir.join(format!("{}.xcframework", self.name)))
+                    }
+                },
+                LibraryArtifactType::JSLib => {
+                    if target_triple.operating_system != OperatingSystem::Emscripten {
+                        Err(BeaverError::TargetDoesntSupportJSLib(target_triple.operating_system))
+                    } else {
+                        Ok(dir.join(format!("{}.js", self.name)))
+                    }
+                }
+                _ => unreachable!("Unsupported artifact for C") // TODO: validate in `new`
+            },
+            ArtifactType::Executable(_) => panic!("bug")
+        };
+    }
+
+    fn register<Builder: BackendBuilder<'static>>(&self,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>
+    ) -> crate::Result<String> {
+        if self.artifacts.contains(&LibraryArtifactType::PkgConfig) {
+            self.write_pkgconfig(project_base_dir, project_build_dir, target_triple, context)?;
+        }
+        if self.has_export_header() {
+            self.write_export_header(project_build_dir)?;
+        }
+
+        let mut rules = self.cc_rules()?;
+        rules.extend([self.link_rule()?, &rules::AR]);
+        if self.artifacts.contains(&LibraryArtifactType::Dynlib) && self.versioned_dynlib_file_names(target_triple)?.is_some() {
+            rules.push(&rules::SYMLINK);
+        }
+        if target_triple.operating_system == OperatingSystem::Emscripten && self.artifacts.contains(&LibraryArtifactType::JSLib) {
+            rules.push(self.jslib_rule()?)
+        }
+
+        CTarget::register_impl(
+            self,
+            project_name,
+            project_base_dir,
+            project_build_dir,
+            target_triple,
+            builder,
+            scope,
+            &rules,
+            context
+        )
+    }
+
+    fn debug_attributes(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("sources", format!("{:?}", self.sources.resolve().unwrap())),
+            ("cflags", format!("{:?}", self.cflags)),
+            ("headers", format!("{:?}", self.headers)),
+            ("linker_flags", self.linker_flags.join(", ")),
+        ]
+    }
+
+    fn install_files(&self, project_base_dir: &Path, project_build_dir: &Path, target_triple: &Triple, options: &InstallOptions, context: &Beaver) -> crate::Result<Vec<InstallFile>> {
+        let mut files = Vec::new();
+        for artifact in &self.artifacts {
+            let kind = match artifact {
+                LibraryArtifactType::Dynlib | LibraryArtifactType::Module => InstallKind::SharedLibrary,
+                LibraryArtifactType::Staticlib => InstallKind::StaticLibrary,
+                LibraryArtifactType::PkgConfig => {
+                    // The pkg-config file in the build directory refers to the build directory
+                    let pkgconfig = self.pkgconfig(project_base_dir, project_build_dir, target_triple, Some(options), context)?;
+                    files.push(InstallFile {
+                        source: InstallSource::Contents(pkgconfig.to_string()),
+                        destination: options.dirs.pkgconfigdir().join(format!("{}.pc", self.name)),
+                        kind: InstallKind::PkgConfig,
+                    });
+                    continue;
+                },
+                // Only the headers of an interface library are installed
+                LibraryArtifactType::Interface => continue,
+                _ => {
+                    warn!("Installing {} artifacts is not supported, skipping the {} of {}", artifact, artifact, self.name);
+                    continue;
+                }
+            };
+            let file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Library(*artifact), target_triple)?;
+            if *artifact == LibraryArtifactType::Dynlib && let Some((versioned_file, soname)) = self.versioned_dynlib_file_names(target_triple)? {
+                files.push(InstallFile {
+                    destination: options.dirs.libdir.join(&versioned_file),
+                    source: InstallSource::File(file.with_file_name(&versioned_file)),
+                    kind,
+                });
+                if soname != versioned_file {
+                    files.push(InstallFile {
+                        destination: options.dirs.libdir.join(&soname),
+                        source: InstallSource::Symlink(PathBuf::from(&versioned_file)),
+                        kind,
+                    });
+                }
+                files.push(InstallFile {
+                    destination: options.dirs.libdir.join(file.file_name().unwrap()),
+                    source: InstallSource::Symlink(PathBuf::from(soname)),
+                    kind,
+                });
+                continue;
+            }
+            files.push(InstallFile {
+                destination: options.dirs.libdir.join(file.file_name().unwrap()),
+                source: InstallSource::File(file),
+                kind,
+            });
+        }
+
+        for header_dir in self.headers.public(project_base_dir) {
+            for (source, relative) in install::collect_headers(&header_dir)? {
+                files.push(InstallFile {
+                    source: InstallSource::File(source),
+                    destination: options.dirs.includedir.join(relative),
+                    kind: InstallKind::Header,
+                });
+            }
+        }
+        if self.has_export_header() {
+            files.push(InstallFile {
+                source: InstallSource::File(self.export_header_dir(project_build_dir).join(self.export_header_name())),
+                destination: options.dirs.includedir.join(self.export_header_name()),
+                kind: InstallKind::Header,
+            });
+        }
+
+        return Ok(files);
+    }
+}
+
+impl CTarget for Library {
+    type TargetArtifactType = LibraryArtifactType;
+
+    fn user_cflags(&self) -> impl Iterator<Item = &String> {
+        self.cflags.public.iter()
+            .chain(self.cflags.private.iter())
+    }
+
+    fn all_headers<'a>(&'a self, project_base_dir: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        self.headers.public(project_base_dir)
+            .chain(self.headers.private(project_base_dir))
+    }
+
+    fn generated_headers(&self) -> &[PathBuf] {
+        self.headers.generated()
+    }
+
+    fn target_artifacts(&self) -> &[Self::TargetArtifactType] {
+        &self.artifacts
+    }
+
+    fn sources(&self) -> &Files {
+        &self.sources
+    }
+
+    fn source_language_overrides(&self) -> &[(Files, Language)] {
+        &self.source_languages
+    }
+
+    fn source_flag_overrides(&self) -> &[(Files, Vec<String>)] {
+        &self.source_flags
+    }
+
+    fn build_dir_cflags(&self, project_build_dir: &Path) -> Vec<String> {
+        if !self.has_export_header() {
+            return Vec::new();
+        }
+        vec![format!("-I{}", self.export_header_dir(project_build_dir).display())]
+    }
+
+    fn settings(&self) -> &[Setting] {
+        &self.settings
+    }
+
+    /// All linker flags used by this library when linking, except for the flags of the artifact being linked
+    fn linker_flags<'a>(&self, dependencies: impl Iterator<Item = &'a Dependency>, languages: impl Iterator<Item = &'a Language>, triple: &Triple, context: &Beaver) -> crate::Result<(Vec<String>, Vec<PathBuf>)> {
+        let mut flags: Vec<String> = self.linker_flags.clone();
+
+        let mut additional_files = Vec::new();
+        for dependency in dependencies {
+            dependency.linker_flags(triple, context, &mut flags, &mut additional_files)?;
+        }
+        let link_language = self.source_link_language()?;
+        for lang in languages {
+            let Some(lang_flags) = Language::linker_flags(*lang, link_language, triple) else { continue };
+            flags.extend(lang_flags.iter().map(|str| str.to_string()))
+        }
+
+        flags.extend(context.linker_flags()?);
+
+        if (link_language == Language::OBJC || link_language == Language::OBJCXX) && self.settings.contains(&Setting::ObjCArc) {
+            flags.push("-fobjc-arc".to_string());
+        }
+
+        return Ok((flags, additional_files));
+    }
+
+    fn register_artifact<Scope: BackendBuilderScope>(&self,
+        artifact: &LibraryArtifactType,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        dependency_steps: &[&str],
+        cflags: &HashMap<Language, String>,
+        linker_flags: &str,
+        additional_artifact_files: &[PathBuf],
+        additional_dependency_files: &[&str],
+        builder: &mut Scope
+    ) -> crate::Result<String> {
+        let link_rule = self.link_rule()?;
+
+        match artifact {
+            LibraryArtifactType::Dynlib | LibraryArtifactType::Module | LibraryArtifactType::Staticlib | LibraryArtifactType::JSLib | LibraryArtifactType::Objects => {
+                let object_type = Self::object_type(*artifact).unwrap();
+                let object_dir = self.object_dir(project_build_dir, object_type);
+                // Objects are compiled once for all artifacts using them
+                let compiled = self.artifacts.iter()
+                    .take_while(|other| *other != artifact)
+                    .any(|other| Self::object_type(*other) == Some(object_type));
+                // Dependents can link the objects of an objects artifact into a dynamic library
+                let pic_cflags: &[&str] = if object_type == CObjectType::Static && self.artifacts.contains(&LibraryArtifactType::Objects) {
+                    CObjectType::Dynamic.cflags(target_triple)
+                } else {
+                    &[]
+                };
+                let object_cflags = utils::flags::concat_quoted(object_type.cflags(target_triple).iter()
+                    .chain(pic_cflags)
+                    .map(|flag| flag.to_string())
+                    .chain(self.export_define(object_type)));
+
+                let mut object_files: Vec<PathBuf> = Vec::new(); //additional_artifact_files.to_vec();
+                let sources = self.sources.resolve()?;
+                if sources.len() == 0 { warn!("No sources in C::Library {}", self.name); }
+                for source in sources {
+                    object_files.push(object_file(&object_dir, source, project_base_dir, project_build_dir));
+
+                    if compiled { continue }
+
+                    self.add_compile_step(source, &object_files[object_files.len() - 1], cflags, &object_cflags, additional_dependency_files, builder)?;
+                }
+
+                let artifact_step = format!("{}$:{}$:{}", project_name, &self.name, artifact);
+
+                if *artifact == LibraryArtifactType::Objects {
+                    // Dependents link the object files themselves, so the dependencies are built first as well
+                    let object_files = object_files.into_iter()
+                        .map(|path| Scope::format_path(builder, path).to_str().unwrap().to_string())
+                        .collect::<Vec<String>>();
+                    builder.add_step(&BuildStep::Phony {
+                        name: &artifact_step,
+                        args: &object_files.iter().map(|str| str.as_str()).collect::<Vec<&str>>(),
+                        dependencies: dependency_steps
+                    })?;
+
+                    return Ok(artifact_step);
+                }
+
+                let artifact_file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Library(*artifact), target_triple)?;
+                match *artifact {
+                    LibraryArtifactType::Dynlib | LibraryArtifactType::Module => {
+                        let os = &target_triple.operating_system;
+                        let mut artifact_flags: Vec<String> = if *artifact == LibraryArtifactType::Module {
+                            module_linker_flags_for_os(os)?
+                        } else {
+                            dynlib_linker_flags_for_os(os)?
+                        }.iter().map(|flag| flag.to_string()).collect();
+
+                        // Modules are loaded by their path, so they aren't versioned
+                        let versioned = if *artifact == LibraryArtifactType::Dynlib { self.versioned_dynlib_file_names(target_triple)? } else { None };
+                        let library_file = match &versioned {
+                            Some((file, soname)) => {
+                                let abi_version = self.abi_version().unwrap();
+                                let version = self.version.as_ref().map(|version| version.to_string()).unwrap_or(abi_version.clone());
+                                artifact_flags.extend(soname_linker_flags_for_os(os, soname, &version, &abi_version));
+                                artifact_file.with_file_name(file)
+                            },
+                            None => artifact_file.clone()
+                        };
+
+                        // Relinked when the exported symbols change
+                        let mut link_dependencies = dependency_steps.to_vec();
+                        let exports_file = if *artifact == LibraryArtifactType::Dynlib { self.exports_file(project_base_dir, project_build_dir, target_triple)? } else { None };
+                        if let Some(exports_file) = &exports_file {
+                            artifact_flags.extend(exports_linker_flags_for_os(os, exports_file));
+                            link_dependencies.push(exports_file.to_str().ok_or_else(|| BeaverError::NonUTF8OsStr(exports_file.as_os_str().to_os_string()))?);
+                        }
+
+                        builder.add_step(&BuildStep::Build {
+                            rule: link_rule,
+                            output: &library_file,
+                            input: &(object_files.iter().chain(additional_artifact_files.iter())).map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                            dependencies: &link_dependencies,
+                            options: &[("linkerFlags", &format!("{}{}", utils::flags::concat_quoted(artifact_flags.into_iter()), linker_flags))]
+                        })?;
+
+                        // Dependents link against the unversioned name: libfoo.so -> libfoo.so.1 -> libfoo.so.1.2.3
+                        if let Some((file, soname)) = versioned {
+                            let soname_file = artifact_file.with_file_name(&soname);
+                            if soname != file {
+                                builder.add_step(&BuildStep::Build {
+                                    rule: &rules::SYMLINK,
+                                    output: &soname_file,
+                                    input: &[&library_file],
+                                    dependencies: &[],
+                                    options: &[("target", &file)]
+                                })?;
+                            }
+                            builder.add_step(&BuildStep::Build {
+                                rule: &rules::SYMLINK,
+                                output: &artifact_file,
+                                input: &[&soname_file],
+                                dependencies: &[],
+                                options: &[("target", &soname)]
+                            })?;
+                        }
+                    },
+                    LibraryArtifactType::Staticlib => {
+                        trace!("Creating staticlib for {} | object_files = {:?} | dependency_steps = {:?}", &self.name, &object_files, dependency_steps);
+                        builder.add_step(&BuildStep::Build {
+                            rule: &rules::AR,
+                            output: &artifact_file,
+                            input: &object_files.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                            dependencies: dependency_steps,
+                            options: &[]
+                        })?;
+                    },
+                    LibraryArtifactType::JSLib => {
+                        match target_triple.operating_system {
+                            OperatingSystem::Emscripten => {
+                                builder.add_step(&BuildStep::Build {
+                                    rule: &rules::JSLIB_C,
+                                    output: &artifact_file,
+                                    input: &(object_files.iter().chain(additional_artifact_files.iter())).map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                                    dependencies: dependency_steps,
+                                    options: &[
+                                        ("linkerFlags", &format!("{}{}", utils::flags::concat_quoted(dynlib_linker_flags_for_os(&target_triple.operating_system)?.iter().map(|flag| flag.to_string())), linker_flags))
+                                    ],
+                                })?;
+                            },
+                            _ => {}
+                        }
+                    }
+                    _ => unreachable!()
+                }
+                // if *artifact == LibraryArtifactType::Dynlib {
+                // } else {
+                // }
+
+                builder.add_step(&BuildStep::Phony {
+                    name: &artifact_step,
+                    args: &[Scope::format_path(builder, artifact_file).to_str().unwrap()],
+                    // args: &[&artifact_file.to_str().unwrap()],
+                    dependencies: &[]
+                })?;
+
+                return Ok(artifact_step);
+            },
+            LibraryArtifactType::Interface => {
+                // Nothing is built, but dependents do need the dependencies of this library
+                let artifact_step = format!("{}$:{}$:{}", project_name, &self.name, artifact);
+                builder.add_step(&BuildStep::Phony {
+                    name: &artifact_step,
+                    args: &[],
+                    dependencies: dependency_steps
+                })?;
+
+                return Ok(artifact_step);
+            },
+            LibraryArtifactType::PkgConfig => {
+                // The pkg-config file is written when registering the target, it refers to the
+                // libraries of this target, so those need to be built as well
+                let artifact_file = traits::Target::artifact_file(self, project_build_dir, ArtifactType::Library(*artifact), target_triple)?;
+                let library_steps = self.artifacts.iter()
+                    .filter(|artifact| matches!(artifact, LibraryArtifactType::Dynlib | LibraryArtifactType::Staticlib | LibraryArtifactType::Interface))
+                    .map(|artifact| format!("{}$:{}$:{}", project_name, &self.name, artifact))
+                    .collect::<Vec<String>>();
+
+                let artifact_step = format!("{}$:{}$:{}", project_name, &self.name, artifact);
+                builder.add_step(&BuildStep::Phony {
+                    name: &artifact_step,
+                    args: &[Scope::format_path(builder, artifact_file).to_str().unwrap()],
+                    dependencies: &library_steps.iter().map(|str| str.as_str()).collect::<Vec<&str>>()
+                })?;
+
+                return Ok(artifact_step);
+            },
+            LibraryArtifactType::Framework => todo!("Framework artifacts are coming soon"),
+            LibraryArtifactType::XCFramework => todo!("XCFramework artifacts are coming soon"),
+            _ => unreachable!("Invalid artifact")
+        }
+    }
+}
+
+impl traits::Library for Library {
+    fn public_cflags(&self, project_base_dir: &Path, project_build_dir: &Path, out: &mut Vec<String>, additional_file_dependencies: &mut Vec<PathBuf>) -> crate::Result<()> {
+        out.extend(self.cflags.public.iter().cloned());
+        out.extend(self.headers.public(project_base_dir)
+            .map(|h| format!("-I{}", h.display())));
+        if self.has_export_header() {
+            out.push(format!("-I{}", std::path::absolute(self.export_header_dir(project_build_dir))?.display()));
+        }
+        additional_file_dependencies.extend(self.headers.generated_public().cloned());
+        Ok(())
+    }
+
+    fn artifact_cflags(&self, artifact: LibraryArtifactType, collect_into: &mut Vec<String>) {
+        if matches!(artifact, LibraryArtifactType::Staticlib | LibraryArtifactType::Objects) {
+            collect_into.extend(self.export_define(CObjectType::Static));
+        }
+    }
+
+    fn library_artifacts(&self) -> Vec<LibraryArtifactType> {
+        self.artifacts.clone()
+    }
+
+    fn object_files(&self, project_base_dir: &Path, project_build_dir: &Path, _: &Triple) -> crate::Result<Vec<PathBuf>> {
+        if !self.artifacts.contains(&LibraryArtifactType::Objects) {
+            return Err(BeaverError::NoArtifact(ArtifactType::Library(LibraryArtifactType::Objects), self.name.clone()));
+        }
+        let object_dir = self.object_dir(project_build_dir, CObjectType::Static);
+        self.sources.resolve()?.iter()
+            .map(|source| Ok(std::path::absolute(object_file(&object_dir, source, project_base_dir, project_build_dir))?))
+            .collect()
+    }
+
+    fn additional_linker_flags<'a>(&'a self, _: &Path, _: &Triple, out: &mut Vec<String>) -> crate::Result<()> {
+        out.extend(self.linker_flags.iter().cloned()); // would like to have a Cow here, but that would require redesigning some parts, so maybe in another release
+        Ok(())
+    }
+
+    fn artifact_output_dir(&self,  project_build_dir: &std::path::Path, target_triple: &Triple) -> std::path::PathBuf {
+        _ = target_triple; // todo: support cross-compiling in the future --> this is supported, target_triple isn't necessary since the project_build_dir has this variable
+        project_build_dir.join("artifacts")
+    }
+}
@@ -0,0 +1,5 @@
+use utils::moduse;
+
+moduse!(library);
+moduse!(target);
+moduse!(executable);
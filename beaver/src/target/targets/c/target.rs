@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use target_lexicon::Triple;
+
+use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep, CompileCommand, Rule};
+use crate::target::language;
+use crate::target::parameters::{DefaultArgument, Files, Flags, Headers};
+use crate::target::{CObjectType, Dependency, Language, TArtifactType, Version};
+use crate::{tools, traits, Beaver, BeaverError};
+
+/// The path of the object file compiled from `source` in `object_dir`.
+///
+/// Sources in the project's build directory (e.g. the outputs of a build step) are placed
+/// in `generated`.
+pub(crate) fn object_file(object_dir: &Path, source: &Path, project_base_dir: &Path, project_build_dir: &Path) -> PathBuf {
+    // The build directory is usually inside of the project's directory
+    let mut object_path = if let Ok(path) = source.strip_prefix(project_build_dir) {
+        object_dir.join("generated").join(path)
+    } else if let Ok(path) = source.strip_prefix(project_base_dir) {
+        object_dir.join(path)
+    } else {
+        object_dir.join("external").join(source.components()
+            .filter(|component| matches!(component, std::path::Component::Normal(_)))
+            .collect::<PathBuf>())
+    };
+    let mut object_filename = object_path.file_name().unwrap().to_os_string();
+    object_filename.push(".o");
+    object_path.set_file_name(object_filename);
+    return object_path;
+}
+
+/// The languages of the sources of C targets
+pub(crate) const C_LANGUAGES: [Language; 5] = [Language::C, Language::CXX, Language::OBJC, Language::OBJCXX, Language::ASM];
+
+pub struct TargetDescriptor<ArtifactType> {
+    pub name: String,
+    pub description: Option<String>,
+    pub homepage: Option<url::Url>,
+    pub version: Option<Version>,
+    pub license: Option<String>,
+    pub language: Language,
+    pub sources: Files,
+    pub cflags: Flags,
+    pub headers: Headers,
+    pub linker_flags: Vec<String>,
+    pub artifacts: DefaultArgument<Vec<ArtifactType>>,
+    /// Public dependencies. Private and interface dependencies are set with `set_dependencies`
+    /// on the target.
+    pub dependencies: Vec<Dependency>,
+    pub settings: Vec<Setting>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Setting {
+    ObjCArc,
+    /// Symbols are hidden unless marked with the export macro (`-fvisibility=hidden`)
+    HiddenVisibility,
+    /// The C standard used for C and Objective-C sources
+    CStd(LanguageStandard),
+    /// The C++ standard used for C++ and Objective-C++ sources
+    CXXStd(LanguageStandard),
+}
+
+#[derive(Debug)]
+pub enum SettingParseError {
+    NotParseable,
+    ExpectedNumber,
+    NotANumber(ParseIntError),
+    UnknownStandard(u32),
+}
+
+impl Setting {
+    pub fn parse(str: &str) -> Result<Setting, SettingParseError> {
+        match str.to_lowercase().as_str() {
+            "objcarc" | "objc-arc" | "objc_arc" | "arc" => Ok(Setting::ObjCArc),
+            "visibility=hidden" | "hidden-visibility" | "hidden_visibility" => Ok(Setting::HiddenVisibility),
+            str => match str.split_once('=') {
+                Some(("cstd", std)) => Ok(Setting::CStd(LanguageStandard::parse(std, "c", C_STANDARDS)?)),
+                Some(("cxxstd" | "c++std", std)) => Ok(Setting::CXXStd(LanguageStandard::parse(std, "c++", CXX_STANDARDS)?)),
+                None if matches!(str, "cstd" | "cxxstd" | "c++std") => Err(SettingParseError::ExpectedNumber),
+                _ => Err(SettingParseError::NotParseable)
+            },
+        }
+    }
+}
+
+/// A version of the C or C++ standard, e.g. `c11` or `gnu++20`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LanguageStandard {
+    pub version: u32,
+    /// Use the GNU dialect of the standard
+    pub gnu: bool,
+}
+
+/// The name of a standard as passed to `-std=`, with the minimum gcc and clang versions
+/// that accept it
+struct StandardName {
+    name: &'static str,
+    gcc: (u64, u64),
+    clang: (u64, u64),
+}
+
+const fn std_name(name: &'static str, gcc: (u64, u64), clang: (u64, u64)) -> StandardName {
+    StandardName { name, gcc, clang }
+}
+
+/// Known standards with their names, preferred name first
+const C_STANDARDS: &[(u32, &[StandardName])] = &[
+    (89, &[std_name("c89", (0, 0), (0, 0))]),
+    (90, &[std_name("c90", (0, 0), (0, 0))]),
+    (99, &[std_name("c99", (0, 0), (0, 0))]),
+    (11, &[std_name("c11", (4, 7), (3, 1))]),
+    (17, &[std_name("c17", (8, 0), (6, 0))]),
+    (18, &[std_name("c18", (8, 0), (6, 0))]),
+    (23, &[std_name("c23", (14, 0), (18, 0)), std_name("c2x", (9, 0), (9, 0))]),
+];
+
+const CXX_STANDARDS: &[(u32, &[StandardName])] = &[
+    (98, &[std_name("c++98", (0, 0), (0, 0))]),
+    (3, &[std_name("c++03", (0, 0), (0, 0))]),
+    (11, &[std_name("c++11", (4, 8), (3, 3))]),
+    (14, &[std_name("c++14", (5, 0), (3, 5))]),
+    (17, &[std_name("c++17", (7, 0), (5, 0)), std_name("c++1z", (5, 0), (3, 5))]),
+    (20, &[std_name("c++20", (10, 0), (10, 0)), std_name("c++2a", (8, 0), (5, 0))]),
+    (23, &[std_name("c++23", (11, 0), (17, 0)), std_name("c++2b", (11, 0), (13, 0))]),
+    (26, &[std_name("c++26", (14, 0), (18, 0)), std_name("c++2c", (14, 0), (17, 0))]),
+];
+
+impl LanguageStandard {
+    /// Parses `11`, `c11` or `gnu11` (with `prefix` = "c")
+    fn parse(str: &str, prefix: &str, known: &[(u32, &[StandardName])]) -> Result<LanguageStandard, SettingParseError> {
+        let str = str.trim();
+        let (version, gnu) = if let Some(version) = str.strip_prefix("gnu").map(|str| str.trim_start_matches("++")) {
+            (version, true)
+        } else {
+            (str.strip_prefix(prefix).unwrap_or(str), false)
+        };
+
+        if version.is_empty() {
+            return Err(SettingParseError::ExpectedNumber);
+        }
+        let version = match version.parse::<u32>() {
+            Ok(i) => i,
+            Err(err) => return Err(SettingParseError::NotANumber(err)),
+        };
+        if !known.iter().any(|(known, _)| *known == version) {
+            return Err(SettingParseError::UnknownStandard(version));
+        }
+
+        Ok(LanguageStandard { version, gnu })
+    }
+
+    /// The `-std=` flag for a C standard, checked against the version of `compiler`
+    pub fn c_flag(&self, compiler: &tools::CCVersion) -> crate::Result<String> {
+        self.flag(C_STANDARDS, compiler)
+    }
+
+    /// The `-std=` flag for a C++ standard, checked against the version of `compiler`
+    pub fn cxx_flag(&self, compiler: &tools::CCVersion) -> crate::Result<String> {
+        self.flag(CXX_STANDARDS, compiler)
+    }
+
+    fn flag(&self, known: &[(u32, &[StandardName])], compiler: &tools::CCVersion) -> crate::Result<String> {
+        let names = known.iter()
+            .find(|(version, _)| *version == self.version)
+            .map(|(_, names)| *names)
+            .expect("Language standard should have been validated while parsing");
+
+        let supported = |std: &&StandardName| match compiler {
+            tools::CCVersion::Gcc(version) => (version.major, version.minor) >= std.gcc,
+            tools::CCVersion::Clang(version) => (version.major, version.minor) >= std.clang,
+            // Compiler versions can't be compared to gcc or clang
+            _ => true,
+        };
+
+        let Some(std) = names.iter().find(supported) else {
+            return Err(BeaverError::UnsupportedLanguageStandard(self.name(names[0].name), compiler.to_string()));
+        };
+
+        return Ok(format!("-std={}", self.name(std.name)));
+    }
+
+    fn name(&self, name: &str) -> String {
+        if self.gnu {
+            format!("gnu{}", name.trim_start_matches("c"))
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+pub(crate) trait CTarget: traits::Target {
+    type TargetArtifactType: TArtifactType;
+
+    fn user_cflags(&self) -> impl Iterator<Item = &String>;
+    /// Both public and private headers
+    fn all_headers<'a>(&'a self, project_base_dir: &'a Path) -> impl Iterator<Item = PathBuf> + 'a;
+    /// Include directories generated by other targets. Compiling depends on these.
+    fn generated_headers(&self) -> &[PathBuf];
+
+    fn target_artifacts(&self) -> &[Self::TargetArtifactType];
+
+    /// The directory of the objects of `object_type`. Each target has its own, so that targets
+    /// compiling the same sources with different flags don't write to the same files.
+    fn object_dir(&self, project_build_dir: &Path, object_type: CObjectType) -> PathBuf {
+        project_build_dir.join("objects").join(self.name()).join(object_type.to_string())
+    }
+
+    fn sources(&self) -> &Files;
+
+    /// Sources compiled as another language than the one of their extension
+    fn source_language_overrides(&self) -> &[(Files, Language)];
+
+    /// The language `source` is compiled as: an override, the language of its extension or
+    /// the language of the target
+    fn source_language(&self, source: &Path) -> crate::Result<Language> {
+        for (files, language) in self.source_language_overrides() {
+            if files.resolve()?.iter().any(|file| file == source) {
+                return Ok(*language);
+            }
+        }
+        return Ok(source.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Language::from_extension)
+            .unwrap_or(self.language()));
+    }
+
+    /// Additional cflags for the sources matching a pattern
+    fn source_flag_overrides(&self) -> &[(Files, Vec<String>)];
+
+    /// The additional cflags `source` is compiled with, in the order of the patterns matching it
+    fn source_cflags(&self, source: &Path) -> crate::Result<Vec<String>> {
+        let mut cflags = Vec::new();
+        for (files, flags) in self.source_flag_overrides() {
+            if files.resolve()?.iter().any(|file| file == source) {
+                cflags.extend(flags.iter().cloned());
+            }
+        }
+        return Ok(cflags);
+    }
+
+    /// The languages of the sources of this target
+    fn source_languages(&self) -> crate::Result<Vec<Language>> {
+        let mut languages = Vec::new();
+        for source in self.sources().resolve()? {
+            let language = self.source_language(source)?;
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+        return Ok(languages);
+    }
+
+    /// The language the artifacts are linked as, the strongest of the target's language and
+    /// the languages of its sources
+    fn source_link_language(&self) -> crate::Result<Language> {
+        Ok(Language::strongest(self.source_languages()?.into_iter().chain([self.language()])))
+    }
+
+    /// Flags only used when compiling this target itself which refer to the build directory,
+    /// e.g. for generated headers
+    fn build_dir_cflags(&self, project_build_dir: &Path) -> Vec<String> {
+        _ = project_build_dir;
+        Vec::new()
+    }
+
+    // TODO: for libraries -> cache cflags and linker_flags
+    /// Returns the cflags and files this target depends on
+    fn cflags<'a>(
+        &self,
+        project_base_dir: &Path,
+        dependencies: impl Iterator<Item = &'a Dependency>,
+        dependency_languages: impl Iterator<Item = &'a Language>,
+        context: &Beaver
+    ) -> crate::Result<(Vec<String>, Vec<PathBuf>)> {
+        let mut add_dependency_files: Vec<PathBuf> = Vec::new();
+        let mut cflags: Vec<String> = context.cflags()?;
+        cflags.extend(self.user_cflags().map(|string| string.clone()));
+        cflags.extend(self.all_headers(project_base_dir).map(|path| format!("-I{}", path.display())));
+        add_dependency_files.extend(self.generated_headers().iter().cloned());
+        if let Some(langflags) = Language::cflags(self.language(), self.language()) {
+            cflags.extend(langflags.iter().map(|str| str.to_string()));
+        }
+
+        for dependency in dependencies {
+            dependency.public_cflags(context, &mut cflags, &mut add_dependency_files)?;
+        }
+        for lang in dependency_languages {
+            let Some(lang_cflags) = Language::cflags(*lang, self.language()) else { continue };
+            cflags.extend(lang_cflags.iter().map(|str| str.to_string()));
+        }
+
+        if context.color_enabled() {
+            cflags.push("-fdiagnostics-color=always".to_string());
+        }
+
+        if self.settings().contains(&Setting::HiddenVisibility) {
+            cflags.push("-fvisibility=hidden".to_string());
+        }
+
+        return Ok((cflags, add_dependency_files));
+    }
+
+    /// The cflags for sources of `language`, added to the ones of the target
+    fn language_cflags(&self, language: Language) -> crate::Result<Vec<String>> {
+        let mut cflags = Vec::new();
+
+        if (language == Language::OBJC || language == Language::OBJCXX) && self.settings().contains(&Setting::ObjCArc) {
+            cflags.push("-fobjc-arc".to_string());
+        }
+
+        if (language == Language::CXX || language == Language::OBJCXX) && self.settings().contains(&Setting::HiddenVisibility) {
+            cflags.push("-fvisibility-inlines-hidden".to_string());
+        }
+
+        if let Some(std_flag) = self.std_flag(language)? {
+            cflags.push(std_flag);
+        }
+
+        return Ok(cflags);
+    }
+
+    /// The `-std=` flag for sources of `language`, if a standard was set
+    fn std_flag(&self, language: Language) -> crate::Result<Option<String>> {
+        for setting in self.settings() {
+            match (setting, language) {
+                (Setting::CStd(std), Language::C | Language::OBJC) => return std.c_flag(&tools::cc_version).map(Some),
+                (Setting::CXXStd(std), Language::CXX | Language::OBJCXX) => return std.cxx_flag(&tools::cxx_version).map(Some),
+                _ => {}
+            }
+        }
+        return Ok(None);
+    }
+
+    // TODO: return iter?
+    fn linker_flags<'a>(
+        &self,
+        dependencies: impl Iterator<Item = &'a Dependency>,
+        languages: impl Iterator<Item = &'a Language>,
+        triple: &Triple,
+        context: &Beaver
+    ) -> crate::Result<(Vec<String>, Vec<PathBuf>)>;
+
+    fn settings(&self) -> &[Setting];
+
+    fn register_impl<Builder: BackendBuilder<'static>>(&self,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        _scope: &mut Builder::Scope,
+        rules: &[&'static Rule],
+        context: &crate::Beaver
+    ) -> crate::Result<String> {
+        let mut guard = builder.write()
+            .map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
+        for rule in rules {
+            guard.add_rule_if_not_exists(rule);
+        }
+
+        let mut scope = guard.new_scope();
+        drop(guard);
+
+        let compile_dependencies = self.compile_dependencies(context)?;
+        let (link_dependencies, languages) = self.link_dependencies(context)?;
+
+        // Interface dependencies of dependencies are linked as well, so they are built first
+        let mut dependency_steps = Vec::new();
+        for dep in link_dependencies.iter() {
+            dep.ninja_names(context, &mut dependency_steps)?;
+        }
+        let dependency_steps = dependency_steps.iter()
+            .map(|str| str.as_str())
+            .collect::<Vec<&str>>();
+
+        let (cflags, additional_dependency_files) = self.cflags(project_base_dir, compile_dependencies.iter(), languages.iter(), context)?;
+        let build_dir_cflags = self.build_dir_cflags(project_build_dir);
+        let mut language_cflags: HashMap<Language, Vec<String>> = HashMap::new();
+        for language in self.source_languages()? {
+            let mut flags = cflags.clone();
+            flags.extend(self.language_cflags(language)?);
+            flags.extend(build_dir_cflags.iter().cloned());
+            language_cflags.insert(language, flags);
+        }
+        self.add_compile_commands(project_base_dir, &language_cflags, context)?;
+        let cflags_str: HashMap<Language, String> = language_cflags.into_iter()
+            .map(|(language, flags)| (language, utils::flags::concat_quoted(flags.into_iter())))
+            .collect();
+        let additional_dependency_files = additional_dependency_files.iter().map(|path: &PathBuf| {
+            if let Some(path) = path.to_str() {
+                Ok(path)
+            } else {
+                Err(BeaverError::NonUTF8OsStr(path.as_os_str().to_os_string()))
+            }
+        }).collect::<crate::Result<Vec<&str>>>()?;
+
+        let (linker_flags, additional_artifact_files) = self.linker_flags(link_dependencies.iter(), languages.iter(), target_triple, context)?;
+        let linker_flags_str = utils::flags::concat_quoted(linker_flags.into_iter());
+
+        let mut artifact_steps: Vec<String> = Vec::new();
+        artifact_steps.reserve_exact(self.artifacts().len());
+
+        for artifact in self.target_artifacts() {
+            #[cfg(debug_assertions)] {
+                scope.add_comment(&format!("{}:{}", self.name(), artifact))?;
+            }
+
+            artifact_steps.push(self.register_artifact(
+                artifact,
+                project_name, project_base_dir, project_build_dir,
+                &target_triple,
+                &dependency_steps,
+                &cflags_str,
+                &linker_flags_str,
+                &additional_artifact_files,
+                &additional_dependency_files,
+                &mut scope
+            )?);
+        }
+
+        let target_step = format!("{}$:{}", project_name, self.name());
+        scope.add_step(&BuildStep::Phony {
+            name: &target_step,
+            args: &artifact_steps.iter().map(|str| str.as_str()).collect::<Vec<&str>>(),
+            dependencies: &[]
+        })?;
+
+        let mut builder_guard = builder.write()
+            .map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
+        builder_guard.apply_scope(scope);
+
+        return Ok(target_step);
+    }
+
+    fn register_artifact<Scope: BackendBuilderScope>(
+        &self,
+        artifact: &Self::TargetArtifactType,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        target_triple: &Triple,
+        dependency_steps: &[&str],
+        // The cflags for the sources of each language, pre-formatted
+        cflags: &HashMap<Language, String>,
+        linker_flags: &str,
+        additional_artifact_files: &[PathBuf],
+        // Additional files pre-formatted
+        additional_dependency_files: &[&str],
+        builder: &mut Scope
+    ) -> crate::Result<String>;
+
+    /// Adds the step compiling `source` to `object` with the compiler of its language.
+    /// `extra_cflags` are pre-formatted and added after the cflags of the language, followed by
+    /// the cflags of the source itself.
+    fn add_compile_step<Scope: BackendBuilderScope>(
+        &self,
+        source: &Path,
+        object: &Path,
+        cflags: &HashMap<Language, String>,
+        extra_cflags: &str,
+        dependencies: &[&str],
+        builder: &mut Scope
+    ) -> crate::Result<()> {
+        let language = self.source_language(source)?;
+        let source_cflags = utils::flags::concat_quoted(self.source_cflags(source)?.into_iter());
+        builder.add_step(&BuildStep::Build {
+            rule: Self::cc_rule(language),
+            output: object,
+            input: &[source],
+            dependencies,
+            options: &[("cflags", &format!("{}{}{}", cflags[&language], extra_cflags, source_cflags))]
+        })
+    }
+
+    /// Adds an entry for each source of this target to the compilation database
+    fn add_compile_commands(&self, project_base_dir: &Path, cflags: &HashMap<Language, Vec<String>>, context: &Beaver) -> crate::Result<()> {
+        for source in self.sources().resolve()? {
+            let language = self.source_language(source)?;
+            let mut arguments = Self::compiler_arguments(language);
+            arguments.extend(cflags[&language].iter().cloned());
+            arguments.extend(self.source_cflags(source)?);
+            arguments.extend(["-c".to_string(), source.display().to_string()]);
+
+            context.add_compile_command(CompileCommand {
+                directory: project_base_dir.to_path_buf(),
+                file: source.clone(),
+                arguments,
+                command: None,
+                output: None,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// The compiler invocation used by `cc_rule`, without any flags
+    fn compiler_arguments(language: Language) -> Vec<String> {
+        match language {
+            Language::C | Language::ASM => std::iter::once(tools::cc.display().to_string())
+                .chain(tools::cc_extra_args.unwrap_or(&[]).iter().map(|str| str.to_string()))
+                .collect(),
+            Language::CXX => std::iter::once(tools::cxx.display().to_string())
+                .chain(tools::cxx_extra_args.unwrap_or(&[]).iter().map(|str| str.to_string()))
+                .collect(),
+            Language::OBJC => [tools::objc.display().to_string(), "-x".to_string(), "objective-c".to_string()].into_iter()
+                .chain(language::objc::objc_cflags().iter().map(|str| str.to_string()))
+                .collect(),
+            Language::OBJCXX => [tools::objcxx.display().to_string(), "-x".to_string(), "objective-c++".to_string()].into_iter()
+                .chain(language::objc::objcxx_cflags().iter().map(|str| str.to_string()))
+                .collect(),
+            _ => unreachable!("Invalid language for C target")
+        }
+    }
+
+    fn cc_rule(language: Language) -> &'static Rule {
+        match language {
+            Language::C => &rules::CC,
+            Language::CXX => &rules::CXX,
+            Language::OBJC => &rules::OBJC,
+            Language::OBJCXX => &rules::OBJCXX,
+            Language::ASM => &rules::ASM,
+            _ => unreachable!("Invalid language for C target")
+        }
+    }
+
+    /// The rules compiling the sources of this target
+    fn cc_rules(&self) -> crate::Result<Vec<&'static Rule>> {
+        Ok(self.source_languages()?.into_iter().map(Self::cc_rule).collect())
+    }
+
+    fn link_rule(&self) -> crate::Result<&'static Rule> {
+        Ok(match self.source_link_language()? {
+            Language::C | Language::ASM => &rules::LINK,
+            Language::CXX => &rules::LINKXX,
+            Language::OBJC => &rules::LINKOBJC,
+            Language::OBJCXX => &rules::LINKOBJCXX,
+            _ => unreachable!("Invalid language for C target")
+        })
+    }
+
+    fn jslib_rule(&self) -> crate::Result<&'static Rule> {
+        match self.source_link_language()? {
+            Language::C => Ok(&rules::JSLIB_C),
+            Language::CXX => Ok(&rules::JSLIB_CXX),
+            language => return Err(BeaverError::InvalidLanguageForArtifact(language, "jslib"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tools::CCVersion;
+
+    use super::{LanguageStandard, Setting, SettingParseError};
+
+    #[test]
+    fn parse_std() {
+        assert_eq!(Setting::parse("cstd=11").unwrap(), Setting::CStd(LanguageStandard { version: 11, gnu: false }));
+        assert_eq!(Setting::parse("CSTD=gnu99").unwrap(), Setting::CStd(LanguageStandard { version: 99, gnu: true }));
+        assert_eq!(Setting::parse("cxxstd=c++20").unwrap(), Setting::CXXStd(LanguageStandard { version: 20, gnu: false }));
+        assert_eq!(Setting::parse("c++std=gnu++03").unwrap(), Setting::CXXStd(LanguageStandard { version: 3, gnu: true }));
+        assert!(Setting::parse("cstd=12").is_err());
+        assert!(Setting::parse("cstd").is_err());
+        assert!(matches!(Setting::parse("cstdfoo=11"), Err(SettingParseError::NotParseable)));
+        assert!(matches!(Setting::parse("c++stdx=20"), Err(SettingParseError::NotParseable)));
+        assert!(Setting::parse("cstd=11=x").is_err());
+        assert_eq!(Setting::parse("visibility=hidden").unwrap(), Setting::HiddenVisibility);
+    }
+
+    #[test]
+    fn std_flag() {
+        let gcc = |version: &str| CCVersion::Gcc(semver::Version::parse(version).unwrap());
+        let clang = |version: &str| CCVersion::Clang(semver::Version::parse(version).unwrap());
+
+        let c23 = LanguageStandard { version: 23, gnu: false };
+        assert_eq!(c23.c_flag(&gcc("14.1.0")).unwrap(), "-std=c23");
+        assert_eq!(c23.c_flag(&gcc("12.2.0")).unwrap(), "-std=c2x");
+        assert!(c23.c_flag(&gcc("8.5.0")).is_err());
+
+        let gnucxx20 = LanguageStandard { version: 20, gnu: true };
+        assert_eq!(gnucxx20.cxx_flag(&clang("18.1.3")).unwrap(), "-std=gnu++20");
+        assert_eq!(gnucxx20.cxx_flag(&clang("9.0.0")).unwrap(), "-std=gnu++2a");
+        assert!(gnucxx20.cxx_flag(&clang("4.0.0")).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::DefaultHasher;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use target_lexicon::Triple;
+use url::Url;
+
+use crate::backend::BackendBuilder;
+use crate::target::parameters::Files;
+use crate::target::{ArtifactType, Dependency, ExecutableArtifactType, Language, Version};
+use crate::traits::{self, TargetType};
+use crate::{Beaver, BeaverError};
+
+use super::{BuildCommand, BuildOutput, CustomStep};
+
+#[derive(Debug)]
+pub struct Executable {
+    id: Option<usize>,
+    proj_id: Option<usize>,
+
+    name: String,
+    version: Option<Version>,
+    description: Option<String>,
+    homepage: Option<Url>,
+    license: Option<String>,
+
+    language: Language,
+    dependencies: Vec<Dependency>,
+
+    /// Files the build command reads. When declared, the command only runs when one of them
+    /// changed or when an artifact is missing.
+    inputs: Option<Files>,
+    artifacts: HashMap<ExecutableArtifactType, PathBuf>,
+    build_cmd: BuildCommand,
+}
+
+impl Executable {
+    pub fn new(
+        name: String,
+        version: Option<Version>,
+        description: Option<String>,
+        homepage: Option<Url>,
+        license: Option<String>,
+        language: Language,
+        dependencies: Vec<Dependency>,
+        inputs: Option<Files>,
+        artifacts: HashMap<ExecutableArtifactType, PathBuf>,
+        build_cmd: BuildCommand,
+    ) -> Self {
+        Self {
+            id: None,
+            proj_id: None,
+            name,
+            version,
+            description,
+            homepage,
+            license,
+            language,
+            dependencies,
+            inputs,
+            artifacts,
+            build_cmd,
+        }
+    }
+
+    pub fn build(&self, output: BuildOutput) -> crate::Result<()> {
+        (self.build_cmd.run)(output)
+    }
+}
+
+impl traits::Target for Executable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn homepage(&self) -> Option<&Url> {
+        self.homepage.as_ref()
+    }
+
+    fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    fn set_id(&mut self, new_id: usize) {
+        self.id = Some(new_id)
+    }
+
+    fn project_id(&self) -> Option<usize> {
+        self.proj_id
+    }
+
+    fn set_project_id(&mut self, new_id: usize) {
+        self.proj_id = Some(new_id)
+    }
+
+    fn artifacts(&self) -> Vec<ArtifactType> {
+        self.artifacts
+            .iter()
+            .map(|(art, _)| ArtifactType::Executable(*art))
+            .collect()
+    }
+
+    fn dependencies(&self) -> crate::Result<Cow<'_, [Dependency]>> {
+        Ok(Cow::Borrowed(&[]))
+    }
+
+    fn r#type(&self) -> TargetType {
+        TargetType::Executable
+    }
+
+    fn artifact_file(
+        &self,
+        _project_build_dir: &Path,
+        artifact: ArtifactType,
+        _triple: &Triple,
+    ) -> crate::Result<PathBuf> {
+        match self.artifacts.get(&artifact.as_executable().unwrap()) {
+            Some(art_path) => {
+                return Ok(art_path.clone());
+            }
+            None => {
+                return Err(BeaverError::NoArtifact(artifact, self.name.clone()));
+            }
+        }
+    }
+
+    fn register<Builder: BackendBuilder<'static>>(
+        &self,
+        project_name: &str,
+        _project_base_dir: &Path,
+        _project_build_dir: &Path,
+        _: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>,
+    ) -> crate::Result<String> {
+        let definition = |_: &mut DefaultHasher| {};
+        CustomStep {
+            project_name,
+            project_id: self.project_id().unwrap(),
+            target_id: self.id().unwrap(),
+            name: &self.name,
+            dependencies: &self.dependencies,
+            inputs: self.inputs.as_ref(),
+            artifacts: self.artifacts.iter().map(|(artifact, path)| (artifact.to_string(), path.clone())).collect(),
+            generated_sources: &[],
+            generated_headers: &[],
+            definition: &definition,
+            build_cmd: &self.build_cmd,
+        }.register(builder, scope, context)
+    }
+
+    fn debug_attributes(&self) -> Vec<(&'static str, String)> {
+        super::debug_attributes(self.inputs.as_ref(), self.artifacts.values().cloned())
+    }
+}
+
+impl traits::Executable for Executable {
+    fn executable_artifacts(&self) -> Vec<ExecutableArtifactType> {
+        self.artifacts.iter().map(|(k, _)| *k).collect()
+    }
+}
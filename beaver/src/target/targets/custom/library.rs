@@ -0,0 +1,229 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use log::trace;
+use target_lexicon::Triple;
+use url::Url;
+
+use crate::{Beaver, BeaverError};
+use crate::backend::BackendBuilder;
+use crate::target::parameters::Files;
+use crate::target::{ArtifactType, Dependency, Language, LibraryArtifactType, Version};
+use crate::traits::{self, TargetType};
+
+use super::{BuildCommand, BuildOutput, CustomStep, GeneratedKind};
+
+#[derive(Debug)]
+pub struct Library {
+    id: Option<usize>,
+    proj_id: Option<usize>,
+
+    name: String,
+    version: Option<Version>,
+    description: Option<String>,
+    homepage: Option<Url>,
+    license: Option<String>,
+
+    language: Language,
+    dependencies: Vec<Dependency>,
+
+    linker_flags: Vec<String>,
+    public_cflags: Vec<String>,
+
+    /// Files the build command reads. When declared, the command only runs when one of them
+    /// changed or when an artifact is missing.
+    inputs: Option<Files>,
+    artifacts: HashMap<LibraryArtifactType, PathBuf>,
+    /// Sources the build command writes, which other targets can compile (absolute paths)
+    generated_sources: Vec<PathBuf>,
+    /// Include directories the build command writes headers to (absolute paths)
+    generated_headers: Vec<PathBuf>,
+    build_cmd: BuildCommand,
+}
+
+impl Library {
+    pub fn new(
+        name: String,
+        version: Option<Version>,
+        description: Option<String>,
+        homepage: Option<Url>,
+        license: Option<String>,
+        language: Language,
+        dependencies: Vec<Dependency>,
+        inputs: Option<Files>,
+        artifacts: HashMap<LibraryArtifactType, PathBuf>,
+        generated_sources: Vec<PathBuf>,
+        generated_headers: Vec<PathBuf>,
+        linker_flags: Vec<String>,
+        public_cflags: Vec<String>,
+        build_cmd: BuildCommand,
+    ) -> Self {
+        Self {
+            id: None,
+            proj_id: None,
+            name,
+            version,
+            description,
+            homepage,
+            license,
+            language,
+            dependencies,
+            inputs,
+            artifacts,
+            generated_sources,
+            generated_headers,
+            linker_flags,
+            public_cflags,
+            build_cmd,
+        }
+    }
+
+    pub fn build(&self, output: BuildOutput) -> crate::Result<()> {
+        trace!("Building {:?}", self);
+        (self.build_cmd.run)(output)
+    }
+
+    /// Files of `kind` written by the build command
+    pub fn generated(&self, kind: GeneratedKind) -> &[PathBuf] {
+        match kind {
+            GeneratedKind::Sources => &self.generated_sources,
+            GeneratedKind::Headers => &self.generated_headers,
+        }
+    }
+}
+
+impl traits::Target for Library {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn homepage(&self) -> Option<&Url> {
+        self.homepage.as_ref()
+    }
+
+    fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    fn license(&self) -> Option<&str> {
+        self.license.as_deref()
+    }
+
+    fn language(&self) -> Language {
+        self.language
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    fn set_id(&mut self, new_id: usize) {
+        self.id = Some(new_id)
+    }
+
+    fn project_id(&self) -> Option<usize> {
+        self.proj_id
+    }
+
+    fn set_project_id(&mut self, new_id: usize) {
+        self.proj_id = Some(new_id)
+    }
+
+    fn artifacts(&self) -> Vec<ArtifactType> {
+        self.artifacts
+            .iter()
+            .map(|(art, _)| ArtifactType::Library(*art))
+            .collect()
+    }
+
+    fn dependencies(&self) -> crate::Result<Cow<'_, [Dependency]>> {
+        Ok(Cow::Borrowed(&[]))
+    }
+
+    fn r#type(&self) -> TargetType {
+        TargetType::Library
+    }
+
+    fn artifact_file(
+        &self,
+        _project_build_dir: &Path,
+        artifact: ArtifactType,
+        _triple: &Triple,
+    ) -> crate::Result<PathBuf> {
+        match self.artifacts.get(&artifact.as_library().unwrap()) {
+            Some(art_path) => {
+                return Ok(art_path.clone());
+            }
+            None => {
+                return Err(BeaverError::NoArtifact(artifact, self.name.clone()));
+            }
+        }
+    }
+
+    fn register<Builder: BackendBuilder<'static>>(
+        &self,
+        project_name: &str,
+        _project_base_dir: &Path,
+        _project_build_dir: &Path,
+        _: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>,
+    ) -> crate::Result<String> {
+        let definition = |hasher: &mut DefaultHasher| {
+            self.linker_flags.hash(hasher);
+            self.public_cflags.hash(hasher);
+        };
+        CustomStep {
+            project_name,
+            project_id: self.project_id().unwrap(),
+            target_id: self.id().unwrap(),
+            name: &self.name,
+            dependencies: &self.dependencies,
+            inputs: self.inputs.as_ref(),
+            artifacts: self.artifacts.iter().map(|(artifact, path)| (artifact.to_string(), path.clone())).collect(),
+            generated_sources: &self.generated_sources,
+            generated_headers: &self.generated_headers,
+            definition: &definition,
+            build_cmd: &self.build_cmd,
+        }.register(builder, scope, context)
+    }
+
+    fn debug_attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attributes = super::debug_attributes(self.inputs.as_ref(), self.artifacts.values().cloned());
+        if !self.generated_sources.is_empty() {
+            attributes.push(("generated sources", format!("{:?}", self.generated_sources)));
+        }
+        if !self.generated_headers.is_empty() {
+            attributes.push(("generated headers", format!("{:?}", self.generated_headers)));
+        }
+        return attributes;
+    }
+}
+
+impl traits::Library for Library {
+    fn artifact_output_dir(&self, _project_build_dir: &Path, _triple: &Triple) -> PathBuf {
+        panic!("No fixed artifact output dir")
+    }
+
+    fn library_artifacts(&self) -> Vec<LibraryArtifactType> {
+        self.artifacts.iter().map(|(art, _)| *art).collect()
+    }
+
+    fn additional_linker_flags(&self, _: &Path, _: &Triple, out: &mut Vec<String>) -> crate::Result<()> {
+        out.extend(self.linker_flags.iter().cloned());
+        Ok(())
+    }
+
+    fn public_cflags(&self, _project_base_dir: &Path, _project_build_dir: &Path, collect_into: &mut Vec<String>, _additional_file_dependencies: &mut Vec<PathBuf>) -> crate::Result<()> {
+        collect_into.extend(self.public_cflags.iter().map(|str| str.clone()));
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use utils::moduse;
+
+use crate::backend::{rules, BackendBuilder, BackendBuilderScope, BuildStep};
+use crate::target::parameters::Files;
+use crate::target::Dependency;
+use crate::{Beaver, BeaverError};
+
+moduse!(library);
+moduse!(executable);
+
+/// Where a build command writes its output. The output is streamed to the `beaver callback`
+/// process which requested the build, so that it shows up in the output of the build.
+pub struct BuildOutput {
+    pub stdout: std::io::PipeWriter,
+    pub stderr: std::io::PipeWriter,
+}
+
+pub struct BuildCommand {
+    pub run: Box<dyn Fn(BuildOutput) -> crate::Result<()> + Send + Sync>,
+    /// Describes the command, e.g. the instructions of a Ruby block. Targets are rebuilt when
+    /// their command's definition changes.
+    pub definition: String,
+}
+
+impl std::fmt::Debug for BuildCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BuildCommand")
+    }
+}
+
+/// The kind of files generated by a custom target, which other targets can use with `generated`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratedKind {
+    Sources,
+    /// Include directories
+    Headers,
+}
+
+impl std::fmt::Display for GeneratedKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratedKind::Sources => f.write_str("sources"),
+            GeneratedKind::Headers => f.write_str("headers"),
+        }
+    }
+}
+
+/// The parts of a custom target which are shared by libraries and executables
+pub(crate) struct CustomStep<'a> {
+    pub project_name: &'a str,
+    pub project_id: usize,
+    pub target_id: usize,
+    pub name: &'a str,
+    pub dependencies: &'a [Dependency],
+    /// When `None`, the command is executed on every build
+    pub inputs: Option<&'a Files>,
+    /// Artifact names and paths
+    pub artifacts: Vec<(String, PathBuf)>,
+    /// Sources written by the build command (absolute paths)
+    pub generated_sources: &'a [PathBuf],
+    /// Include directories written by the build command (absolute paths)
+    pub generated_headers: &'a [PathBuf],
+    /// Hashed together with the build command's definition
+    pub definition: &'a dyn Fn(&mut DefaultHasher),
+    pub build_cmd: &'a BuildCommand,
+}
+
+impl<'a> CustomStep<'a> {
+    fn definition_hash(&self, inputs: &[PathBuf]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        inputs.hash(&mut hasher);
+        self.artifacts.hash(&mut hasher);
+        self.generated_sources.hash(&mut hasher);
+        self.generated_headers.hash(&mut hasher);
+        self.build_cmd.definition.hash(&mut hasher);
+        (self.definition)(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Add the step which calls back into beaver to run the build command. Returns the name
+    /// other steps can depend on.
+    pub(crate) fn register<Builder: BackendBuilder<'static>>(
+        &self,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>,
+    ) -> crate::Result<String> {
+        context.enable_communication()?;
+
+        let mut guard = builder.write()
+            .map_err(|err| BeaverError::BackendLockError(err.to_string()))?;
+        guard.add_rule_if_not_exists(&rules::CUSTOM);
+        drop(guard);
+
+        let target_cmd_name = format!("{}$:{}", self.project_name, self.name);
+        let target_name = format!("{}:{}", self.project_name, self.name);
+
+        let mut deps = Vec::new();
+        for dep in self.dependencies {
+            dep.ninja_names(context, &mut deps)?;
+        }
+        let deps = deps.iter().map(|str| str.as_str()).collect::<Vec<&str>>();
+
+        let inputs: Vec<PathBuf> = match self.inputs {
+            Some(inputs) => inputs.resolve()?.clone(),
+            None => Vec::new(),
+        };
+        // Artifacts come first, so that they can be zipped with their names below
+        let outputs = self.artifacts.iter()
+            .map(|(_, path)| std::path::absolute(path))
+            .chain(self.generated_sources.iter().map(|path| Ok(path.clone())))
+            .collect::<Result<Vec<PathBuf>, _>>()?;
+        for output in outputs.iter().chain(self.generated_headers.iter()) {
+            if output.to_str().is_none() {
+                return Err(BeaverError::NonUTF8OsStr(output.as_os_str().to_os_string()));
+            }
+        }
+
+        scope.add_comment(&target_cmd_name)?;
+
+        #[cfg(unix)] {
+            // The socket is passed to `beaver callback` through the environment, so that the
+            // command only changes when the target's definition does
+            let executable = std::env::current_exe()?;
+            let Some(executable) = executable.to_str() else {
+                return Err(BeaverError::NonUTF8OsStr(executable.into_os_string()));
+            };
+            let executable = shlex::try_quote(executable).map_err(|err| BeaverError::AnyError(err.to_string()))?;
+            let cmd = format!(": definition {:016x} && {} callback build {}:{}", self.definition_hash(&inputs), executable, self.project_id, self.target_id);
+
+            let options: &[(&str, &str)] = &[
+                ("name", &target_name),
+                ("cmd", &cmd)
+            ];
+            if self.inputs.is_some() && outputs.len() > 0 {
+                scope.add_step(&BuildStep::Generate {
+                    rule: &rules::CUSTOM,
+                    outputs: &outputs.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                    input: &inputs.iter().map(|path| path.as_path()).collect::<Vec<&Path>>(),
+                    dependencies: deps.as_slice(),
+                    options
+                })?;
+                let outputs = outputs.iter().map(|path| path.to_str().unwrap()).collect::<Vec<&str>>();
+                scope.add_step(&BuildStep::Phony {
+                    name: &target_cmd_name,
+                    args: &outputs,
+                    dependencies: &[],
+                })?;
+            } else {
+                scope.add_step(&BuildStep::Cmd {
+                    rule: &rules::CUSTOM,
+                    name: &target_cmd_name,
+                    dependencies: deps.as_slice(),
+                    options
+                })?;
+                for output in outputs.iter() {
+                    scope.add_step(&BuildStep::Phony {
+                        name: output.to_str().unwrap(),
+                        args: &[&target_cmd_name],
+                        dependencies: &[],
+                    })?;
+                }
+            }
+        }
+        #[cfg(not(unix))] {
+            return Err(BeaverError::CustomTargetUnsupportedPlatform(target_name));
+        }
+
+        // Directories can't be the outputs of a step, so dependents of generated headers depend
+        // on the command through a phony step named after the directory
+        for dir in self.generated_headers.iter() {
+            scope.add_step(&BuildStep::Phony {
+                name: dir.to_str().unwrap(),
+                args: &[&target_cmd_name],
+                dependencies: &[],
+            })?;
+        }
+
+        for ((artifact, _), output) in self.artifacts.iter().zip(outputs.iter()) {
+            let artifact_arg = if self.inputs.is_some() { output.to_str().unwrap() } else { target_cmd_name.as_str() };
+            scope.add_step(&BuildStep::Phony {
+                name: &format!("{}$:{}", &target_cmd_name, artifact),
+                args: &[artifact_arg],
+                dependencies: &[],
+            })?;
+        }
+
+        Ok(target_cmd_name)
+    }
+}
+
+/// Format the inputs and outputs of a custom target for `beaver list`
+pub(crate) fn debug_attributes(inputs: Option<&Files>, artifacts: impl Iterator<Item = PathBuf>) -> Vec<(&'static str, String)> {
+    let mut attributes = Vec::new();
+    if let Some(inputs) = inputs {
+        attributes.push(("inputs", match inputs.resolve() {
+            Ok(files) => format!("{:?}", files),
+            Err(err) => err.to_string(),
+        }));
+    }
+    attributes.push(("outputs", format!("{:?}", artifacts.collect::<Vec<PathBuf>>())));
+    return attributes;
+}
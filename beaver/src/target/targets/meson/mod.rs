@@ -0,0 +1,4 @@
+use utils::moduse;
+
+moduse!(library);
+moduse!(executable);
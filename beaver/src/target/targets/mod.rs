@@ -0,0 +1,8 @@
+pub mod c;
+pub mod cmake;
+pub mod cargo;
+pub mod spm;
+pub mod meson;
+pub mod custom;
+
+pub(crate) mod utils;
@@ -0,0 +1,5 @@
+use utils::moduse;
+
+moduse!(target);
+moduse!(library);
+moduse!(executable);
@@ -0,0 +1,257 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use enum_dispatch::enum_dispatch;
+use log::debug;
+use target_lexicon::Triple;
+use url::Url;
+use crate::backend::BackendBuilder;
+use crate::target::{self, ArtifactType, Dependency, DependencyVisibility, Language, LibraryArtifactType, TargetRef, Version};
+use crate::install::{InstallFile, InstallOptions};
+use crate::testing::TestCase;
+use crate::{Beaver, BeaverError};
+
+use super::{AnyExecutable, AnyLibrary};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TargetType {
+    Library,
+    Executable,
+}
+
+#[enum_dispatch]
+pub trait Target: Send + Sync + std::fmt::Debug {
+    // General Info //
+    fn name(&self) -> &str;
+    fn description(&self) -> Option<&str>;
+    fn homepage(&self) -> Option<&Url>;
+    fn version(&self) -> Option<&Version>;
+    fn license(&self) -> Option<&str>;
+    fn language(&self) -> Language;
+    /// The language the artifacts of this target are linked as, which dependents link against
+    fn link_language(&self) -> crate::Result<Language> {
+        Ok(self.language())
+    }
+
+    // Identification //
+    fn id(&self) -> Option<usize>;
+    fn set_id(&mut self, new_id: usize);
+    fn project_id(&self) -> Option<usize>;
+    fn set_project_id(&mut self, new_id: usize);
+    fn tref(&self) -> Option<TargetRef> {
+        match (self.project_id(), self.id()) {
+            (Some(project_id), Some(target_id)) => {
+                Some(TargetRef {
+                    project: project_id,
+                    target: target_id
+                })
+            },
+            _ => None
+        }
+    }
+
+    fn artifacts(&self) -> Vec<ArtifactType>;
+    fn dependencies(&self) -> crate::Result<Cow<'_, [Dependency]>>;
+
+    fn r#type(&self) -> TargetType;
+
+    fn artifact_file(&self, project_build_dir: &Path, artifact: ArtifactType, triple: &Triple) -> crate::Result<PathBuf>;
+
+    /// Returns the target name
+    fn register<Builder: BackendBuilder<'static>>(&self,
+        project_name: &str,
+        project_base_dir: &Path,
+        project_build_dir: &Path,
+        triple: &Triple,
+        builder: Arc<RwLock<Builder>>,
+        scope: &mut Builder::Scope,
+        context: &Arc<Beaver>
+    ) -> crate::Result<String>;
+
+    /// The dependencies with `visibility`. Only targets which distinguish between them have
+    /// private and interface dependencies, the dependencies of other targets are public.
+    fn visible_dependencies(&self, visibility: DependencyVisibility) -> crate::Result<Cow<'_, [Dependency]>> {
+        match visibility {
+            DependencyVisibility::Public => self.dependencies(),
+            DependencyVisibility::Private | DependencyVisibility::Interface => Ok(Cow::Borrowed(&[])),
+        }
+    }
+
+    /// The dependencies whose public flags this target is compiled with: its own dependencies
+    /// and, recursively, their public and interface dependencies
+    fn compile_dependencies(&self, context: &Beaver) -> crate::Result<Vec<Dependency>> {
+        let mut out = Vec::new();
+        for dep in self.dependencies()?.iter() {
+            collect_compile_dependency(dep, &mut out, context)?;
+        }
+        return Ok(out);
+    }
+
+    /// The dependencies this target links against and their languages. A library comes before
+    /// the libraries it depends on, so that static libraries are linked in the right order.
+    ///
+    /// Static libraries and object files pass on all of their dependencies, other libraries only
+    /// their public and interface dependencies. Modules are only built first.
+    fn link_dependencies(&self, context: &Beaver) -> crate::Result<(Vec<Dependency>, HashSet<Language>)> {
+        let mut post_order = Vec::new();
+        let mut languages = HashSet::new();
+        let mut visited = HashSet::new();
+        for dep in self.dependencies()?.iter().rev() {
+            collect_link_dependency(dep, &mut post_order, &mut languages, &mut visited, context)?;
+        }
+        post_order.reverse();
+        return Ok((post_order, languages));
+    }
+
+    /// Debug attributes to print when using `--debug`
+    fn debug_attributes(&self) -> Vec<(&'static str, String)>;
+
+    fn add_dependency(&mut self, _: Dependency) -> crate::Result<()> {
+        return Err(crate::BeaverError::TargetOperationNotSupported("add_dependency".to_string(), self.name().to_string()));
+    }
+
+    /// Test targets are only built by `beaver test` or when requested explicitly
+    fn is_test(&self) -> bool {
+        false
+    }
+
+    /// The test to run for this target with `beaver test`
+    fn test_case(&self, project_name: &str, project_base_dir: &Path, project_build_dir: &Path, triple: &Triple) -> crate::Result<Option<TestCase>> {
+        _ = (project_name, project_base_dir, project_build_dir, triple);
+        return Ok(None);
+    }
+
+    /// The files `beaver install` copies into the prefix
+    fn install_files(&self, project_base_dir: &Path, project_build_dir: &Path, triple: &Triple, options: &InstallOptions, context: &Beaver) -> crate::Result<Vec<InstallFile>> {
+        _ = (project_base_dir, project_build_dir, triple, options, context);
+        return Ok(Vec::new());
+    }
+}
+
+/// The dependencies of the target `dependency` refers to with the visibilities for its artifact,
+/// and the target's language
+fn target_dependencies(dependency: &Dependency, visibilities: impl Fn(LibraryArtifactType) -> &'static [DependencyVisibility], context: &Beaver) -> crate::Result<Option<(Vec<Dependency>, Language)>> {
+    match dependency {
+        Dependency::Library(target_dep) => {
+            context.with_project_and_target::<Option<(Vec<Dependency>, Language)>, BeaverError>(&target_dep.target, |_, target| {
+                let mut deps = Vec::new();
+                for visibility in visibilities(target_dep.artifact) {
+                    deps.extend(target.visible_dependencies(*visibility)?.iter().cloned());
+                }
+                Ok(Some((deps, target.link_language()?)))
+            })
+        },
+        Dependency::CMakeId(cmake_id) => {
+            context.with_cmake_project_and_library(&cmake_id, |_, target| {
+                let Some(target) = target else {
+                    debug!("Unmapped dependency {}", cmake_id);
+                    return Ok(None);
+                };
+                let mut deps = Vec::new();
+                for visibility in visibilities(target.artifact) {
+                    deps.extend(target.visible_dependencies(*visibility)?.iter().cloned());
+                }
+                Ok(Some((deps, target.language())))
+            })
+        },
+        Dependency::Flags { .. } | Dependency::PkgConfig { .. } | Dependency::Multi(_) | Dependency::File(_) => Ok(None),
+    }
+}
+
+fn collect_compile_dependency(dep: &Dependency, out: &mut Vec<Dependency>, context: &Beaver) -> crate::Result<()> {
+    if let Dependency::Multi(deps) = dep {
+        for dep in deps {
+            collect_compile_dependency(dep, out, context)?;
+        }
+        return Ok(());
+    }
+
+    if out.contains(dep) { return Ok(()); }
+    out.push(dep.clone());
+
+    let visibilities = |artifact| -> &'static [DependencyVisibility] {
+        if artifact == LibraryArtifactType::Module {
+            &[]
+        } else {
+            &[DependencyVisibility::Public, DependencyVisibility::Interface]
+        }
+    };
+    if let Some((deps, _)) = target_dependencies(dep, visibilities, context)? {
+        for dep in deps.iter() {
+            collect_compile_dependency(dep, out, context)?;
+        }
+    }
+
+    return Ok(());
+}
+
+/// Adds `dep` to `post_order` after the dependencies it passes on. Reversed, a dependency comes
+/// after everything depending on it. Dependencies are visited in reverse, so that siblings keep
+/// their order. Each dependency is only visited once, which also breaks cycles.
+fn collect_link_dependency(dep: &Dependency, post_order: &mut Vec<Dependency>, languages: &mut HashSet<Language>, visited: &mut HashSet<Dependency>, context: &Beaver) -> crate::Result<()> {
+    if let Dependency::Multi(deps) = dep {
+        for dep in deps.iter().rev() {
+            collect_link_dependency(dep, post_order, languages, visited, context)?;
+        }
+        return Ok(());
+    }
+
+    if !visited.insert(dep.clone()) { return Ok(()); }
+
+    let visibilities = |artifact| -> &'static [DependencyVisibility] {
+        if matches!(artifact, LibraryArtifactType::Staticlib | LibraryArtifactType::Objects) {
+            &DependencyVisibility::ALL
+        } else if artifact == LibraryArtifactType::Module {
+            &[]
+        } else {
+            &[DependencyVisibility::Public, DependencyVisibility::Interface]
+        }
+    };
+    if let Some((deps, language)) = target_dependencies(dep, visibilities, context)? {
+        languages.insert(language);
+        for dep in deps.iter().rev() {
+            collect_link_dependency(dep, post_order, languages, visited, context)?;
+        }
+    }
+    post_order.push(dep.clone());
+
+    return Ok(());
+}
+
+#[enum_dispatch(Target)]
+#[derive(Debug)]
+pub enum AnyTarget {
+    Library(AnyLibrary),
+    Executable(AnyExecutable),
+}
+
+impl AnyTarget {
+    pub(crate) fn as_library(&self) -> Option<&AnyLibrary> {
+        match self {
+            Self::Library(lib) => Some(lib),
+            _ => None
+        }
+    }
+
+    #[allow(unused)]
+    pub(crate) fn as_executable(&self) -> Option<&AnyExecutable> {
+        match self {
+            Self::Executable(exe) => Some(exe),
+            _ => None
+        }
+    }
+}
+
+impl From<target::spm::Library> for AnyTarget {
+    fn from(value: target::spm::Library) -> Self {
+        AnyTarget::Library(AnyLibrary::SPM(value))
+    }
+}
+
+impl From<target::spm::Executable> for AnyTarget {
+    fn from(value: target::spm::Executable) -> Self {
+        AnyTarget::Executable(AnyExecutable::SPM(value))
+    }
+}
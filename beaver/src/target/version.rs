@@ -0,0 +1,23 @@
+#[derive(Debug, Clone)]
+pub enum Version {
+    Any(String),
+    Semver(semver::Version),
+}
+
+impl Version {
+    pub fn parse(s: &str) -> Self {
+        match semver::Version::parse(s) {
+            Ok(semver) => Self::Semver(semver),
+            Err(_) => Self::Any(s.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Version::Any(version) => f.write_str(version),
+            Version::Semver(version) => version.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use itertools::Itertools;
+
+use crate::BeaverError;
+use super::{TestOutcome, TestResult};
+
+/// Write the results of a test run as a JUnit XML report. Tests are grouped in a test suite
+/// per project.
+pub fn write_junit(results: &[TestResult], duration: Duration, file: &Path) -> crate::Result<()> {
+    std::fs::write(file, junit_xml(results, duration))
+        .map_err(|err| BeaverError::io(err, format!("writing JUnit report {}", file.display())))
+}
+
+fn junit_xml(results: &[TestResult], duration: Duration) -> String {
+    let failures = |results: &[&TestResult]| results.iter()
+        .filter(|result| matches!(result.outcome, TestOutcome::Failed(_) | TestOutcome::TimedOut(_)))
+        .count();
+    let errors = |results: &[&TestResult]| results.iter()
+        .filter(|result| matches!(result.outcome, TestOutcome::Error(_)))
+        .count();
+
+    let all = results.iter().collect::<Vec<&TestResult>>();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    _ = writeln!(out, "<testsuites name=\"beaver\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+        results.len(), failures(&all), errors(&all), duration.as_secs_f64());
+
+    let suites = results.iter().into_group_map_by(|result| result.project.as_str());
+    for project in results.iter().map(|result| result.project.as_str()).unique() {
+        let suite = &suites[project];
+        let time: f64 = suite.iter().map(|result| result.duration.as_secs_f64()).sum();
+        _ = writeln!(out, "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">",
+            xml_escape(project), suite.len(), failures(suite), errors(suite), time);
+
+        for result in suite {
+            let name = result.name.strip_prefix(&format!("{}:", project)).unwrap_or(&result.name);
+            _ = write!(out, "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">",
+                xml_escape(name), xml_escape(project), result.duration.as_secs_f64());
+            match &result.outcome {
+                TestOutcome::Passed => {},
+                TestOutcome::Failed(Some(code)) => _ = write!(out, "\n      <failure message=\"exit code {}\"/>", code),
+                TestOutcome::Failed(None) => _ = write!(out, "\n      <failure message=\"terminated by a signal\"/>"),
+                TestOutcome::TimedOut(timeout) => _ = write!(out, "\n      <failure message=\"exceeded the timeout of {}s\"/>", timeout.as_secs_f64()),
+                TestOutcome::Error(err) => _ = write!(out, "\n      <error message=\"{}\"/>", xml_escape(err)),
+            }
+            if !result.stdout.is_empty() {
+                _ = write!(out, "\n      <system-out>{}</system-out>", xml_escape(&result.stdout));
+            }
+            if !result.stderr.is_empty() {
+                _ = write!(out, "\n      <system-err>{}</system-err>", xml_escape(&result.stderr));
+            }
+            if result.passed() && result.stdout.is_empty() && result.stderr.is_empty() {
+                out.push_str("</testcase>\n");
+            } else {
+                out.push_str("\n    </testcase>\n");
+            }
+        }
+
+        out.push_str("  </testsuite>\n");
+    }
+
+    out.push_str("</testsuites>\n");
+    return out;
+}
+
+fn xml_escape(str: &str) -> String {
+    let mut out = String::with_capacity(str.len());
+    for c in str.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters aren't allowed in XML 1.0
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {},
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn result(name: &str, outcome: TestOutcome, stdout: &str) -> TestResult {
+        TestResult {
+            name: format!("MyProject:{}", name),
+            project: "MyProject".to_string(),
+            outcome,
+            duration: Duration::from_millis(250),
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    #[test]
+    fn junit() {
+        let results = [
+            result("a", TestOutcome::Passed, ""),
+            result("b<c>", TestOutcome::Failed(Some(1)), "expected 1 & got 2\x07"),
+        ];
+        assert_eq!(junit_xml(&results, Duration::from_secs(1)), r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="beaver" tests="2" failures="1" errors="0" time="1.000">
+  <testsuite name="MyProject" tests="2" failures="1" errors="0" time="0.500">
+    <testcase name="a" classname="MyProject" time="0.250"></testcase>
+    <testcase name="b&lt;c&gt;" classname="MyProject" time="0.250">
+      <failure message="exit code 1"/>
+      <system-out>expected 1 &amp; got 2</system-out>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#);
+    }
+}
@@ -0,0 +1,5 @@
+use utils::moduse;
+
+moduse!(test_case);
+moduse!(runner);
+moduse!(junit);
@@ -0,0 +1,171 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use console::style;
+
+use super::TestCase;
+
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    /// Only run tests whose name contains this string
+    pub filter: Option<String>,
+    /// The maximum number of tests to run at the same time
+    pub jobs: usize,
+    /// Timeout for tests that don't specify one. `None` means tests can run indefinitely
+    pub timeout: Option<Duration>,
+    /// Write a JUnit XML report to this file
+    pub junit: Option<std::path::PathBuf>,
+}
+
+impl Default for TestOptions {
+    fn default() -> Self {
+        TestOptions {
+            filter: None,
+            jobs: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            timeout: None,
+            junit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    /// The test exited with a non-zero exit code, or was terminated by a signal (`None`)
+    Failed(Option<i32>),
+    TimedOut(Duration),
+    /// The test couldn't be started
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub project: String,
+    pub outcome: TestOutcome,
+    pub duration: Duration,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.outcome == TestOutcome::Passed
+    }
+}
+
+/// Run `tests` in parallel, reporting each result when it finishes. Results are returned in the
+/// same order as `tests`.
+pub(crate) fn run_tests(tests: &[TestCase], options: &TestOptions) -> Vec<TestResult> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, TestResult)>> = Mutex::new(Vec::with_capacity(tests.len()));
+    let jobs = options.jobs.clamp(1, tests.len().max(1));
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| {
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    if idx >= tests.len() { break; }
+
+                    let result = run_test(&tests[idx], options.timeout);
+                    let mut results = results.lock().unwrap();
+                    // Report while holding the lock, so that output of different tests isn't interleaved
+                    report(&result);
+                    results.push((idx, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    return results.into_iter().map(|(_, result)| result).collect();
+}
+
+fn run_test(test: &TestCase, default_timeout: Option<Duration>) -> TestResult {
+    let start = Instant::now();
+    let result = |outcome: TestOutcome, stdout: String, stderr: String| TestResult {
+        name: test.name.clone(),
+        project: test.project.clone(),
+        outcome,
+        duration: start.elapsed(),
+        stdout,
+        stderr,
+    };
+
+    let mut child = match Command::new(&test.program)
+        .args(&test.args)
+        .current_dir(&test.working_dir)
+        .envs(test.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => return result(TestOutcome::Error(format!("Couldn't start {}: {}", test.program.display(), err)), String::new(), String::new()),
+    };
+
+    let stdout = child.stdout.take().map(read_pipe);
+    let stderr = child.stderr.take().map(read_pipe);
+
+    let timeout = test.timeout.or(default_timeout);
+    let outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break if status.success() { TestOutcome::Passed } else { TestOutcome::Failed(status.code()) },
+            Ok(None) => {
+                if let Some(timeout) = timeout {
+                    if start.elapsed() >= timeout {
+                        _ = child.kill();
+                        _ = child.wait();
+                        break TestOutcome::TimedOut(timeout);
+                    }
+                }
+                thread::sleep(Duration::from_millis(5));
+            },
+            Err(err) => break TestOutcome::Error(err.to_string()),
+        }
+    };
+
+    // A process started by a test that timed out can keep the pipes open, so don't wait for them
+    let wait = !matches!(outcome, TestOutcome::TimedOut(_));
+    let collect = |handle: Option<thread::JoinHandle<String>>| match handle {
+        Some(handle) if wait || handle.is_finished() => handle.join().unwrap_or_default(),
+        _ => String::new(),
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+
+    return result(outcome, stdout, stderr);
+}
+
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        _ = pipe.read_to_end(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+fn report(result: &TestResult) {
+    let duration = format!("({:.2}s)", result.duration.as_secs_f64());
+    match &result.outcome {
+        TestOutcome::Passed => {
+            println!("{} {} {}", style("PASS").green().bold(), result.name, style(duration).dim());
+            return;
+        },
+        TestOutcome::Failed(Some(code)) => println!("{} {} {} exit code {}", style("FAIL").red().bold(), result.name, style(duration).dim(), code),
+        TestOutcome::Failed(None) => println!("{} {} {} terminated by a signal", style("FAIL").red().bold(), result.name, style(duration).dim()),
+        TestOutcome::TimedOut(timeout) => println!("{} {} {} exceeded the timeout of {}s", style("TIMEOUT").red().bold(), result.name, style(duration).dim(), timeout.as_secs_f64()),
+        TestOutcome::Error(err) => println!("{} {} {}", style("ERROR").red().bold(), result.name, err),
+    }
+
+    for line in result.stdout.lines().chain(result.stderr.lines()) {
+        println!("    {}", line);
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options for a target that is run by `beaver test`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestSettings {
+    /// Arguments passed to the test executable
+    pub args: Vec<String>,
+    /// Overrides the timeout passed to `beaver test`
+    pub timeout: Option<Duration>,
+}
+
+/// A single test that can be run by `beaver test`
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// The qualified name of the test, e.g. `MyProject:MyTest`
+    pub name: String,
+    pub project: String,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub working_dir: PathBuf,
+    pub env: Vec<(String, String)>,
+    /// When `None`, the default timeout is used
+    pub timeout: Option<Duration>,
+    /// The step that should be built before the test can be run
+    pub build_step: Option<String>,
+}
@@ -108,59 +108,70 @@ lazy_static! {
 
     pub static ref cmake: PathBuf = Tool { name: "cmake", ..Default::default() }.find();
 
+    pub static ref ctest: PathBuf = Tool { name: "ctest", ..Default::default() }.find();
+
     pub static ref cargo: PathBuf = Tool { name: "cargo", ..Default::default() }.find();
 
     pub static ref swift: PathBuf = Tool { name: "swift", ..Default::default() }.find();
 
     pub static ref meson: PathBuf = Tool { name: "meson", ..Default::default() }.find();
 
-    pub static ref netcat: PathBuf = Tool { name: "nc", ..Default::default() }.find();
-    pub static ref test: PathBuf = Tool { name: "test", ..Default::default() }.find();
-    pub static ref cat: PathBuf = Tool { name: "cat", ..Default::default() }.find();
-    pub static ref mkfifo: PathBuf = Tool { name: "mkfifo", ..Default::default() }.find();
+    pub static ref gcov: PathBuf = Tool { name: "gcov", aliases: None, env: Some("GCOV") }.find();
+    pub static ref llvm_cov: PathBuf = Tool { name: "llvm-cov", aliases: None, env: Some("LLVM_COV") }.find();
+    pub static ref lcov: PathBuf = Tool { name: "lcov", ..Default::default() }.find();
+    pub static ref genhtml: PathBuf = Tool { name: "genhtml", ..Default::default() }.find();
 
     // Tool version //
 
     /// CC
-    pub static ref cc_version: CCVersion = {
-        let mut proc = process::Command::new(cc.as_path())
-            .stdout(Stdio::piped())
-            .stdin(Stdio::piped())
-            // .args(["-dM", "-E", "-x", "c", "/dev/null"])
-            .args(["-E", "-x", "c", "-"])
-            .spawn()
-            // .output()
-            .unwrap();
-
-        let mut stdin = proc.stdin.take().expect("Failed to take stdin");
-        std::thread::spawn(move || {
-            stdin.write_all("#if defined(__EMSCRIPTEN__)\nemscripten\n__clang_major__.__clang_minor__.__clang_patchlevel__\n#elif defined(__clang_version__)\nclang\n__clang_major__.__clang_minor__.__clang_patchlevel__\n#elif defined(__INTEL_COMPILER)\nicc\n__INTEL_COMPILER\n#elif defined(__INTEL__LLVM_COMPILER)\nicx\n__INTEL_LLVM_COMPILER\n#else\ngcc\n__VERSION__\n#endif".as_bytes())
-                .expect("Failed to pipe");
-        });
-
-        let output = proc.wait_with_output().expect("Failed to read stdout");
-        let output = String::from_utf8(output.stdout).unwrap();
-
-        let version = output.split("\n")
-            .filter(|line| !line.starts_with("#"))
-            .map(|line| line.replace(" ", ""))
-            .collect::<Vec<String>>();
-        let mut version = version.iter()
-            .map(|line| line.trim())
-            .filter(|line| *line != "");
-
-        let ty = version.next().unwrap();
-        let version = version.next().unwrap();
-
-        match ty {
-            "clang" => CCVersion::Clang(semver::Version::parse(version).unwrap()),
-            "gcc" => CCVersion::Gcc(semver::Version::parse(version).unwrap()),
-            "emscripten" => CCVersion::Emscripten(semver::Version::parse(version).unwrap()),
-            "icc" => CCVersion::Icc(version.parse::<i32>().unwrap()),
-            "icx" => CCVersion::Icx(version.parse::<i32>().unwrap()),
-            _ => unreachable!()
-        }
-    };
+    pub static ref cc_version: CCVersion = compiler_version(&cc, cc_extra_args.unwrap_or(&[]), "c");
+    /// CXX, which may be a different compiler or version than CC
+    pub static ref cxx_version: CCVersion = compiler_version(&cxx, cxx_extra_args.unwrap_or(&[]), "c++");
+}
+
+/// Identify the compiler and its version by preprocessing a source file of `language`
+fn compiler_version(compiler: &Path, extra_args: &[&str], language: &str) -> CCVersion {
+    let mut proc = process::Command::new(compiler)
+        .args(extra_args)
+        .stdout(Stdio::piped())
+        .stdin(Stdio::piped())
+        // .args(["-dM", "-E", "-x", "c", "/dev/null"])
+        .args(["-E", "-x", language, "-"])
+        .spawn()
+        // .output()
+        .unwrap();
+
+    let mut stdin = proc.stdin.take().expect("Failed to take stdin");
+    std::thread::spawn(move || {
+        stdin.write_all("#if defined(__EMSCRIPTEN__)\nemscripten\n__clang_major__.__clang_minor__.__clang_patchlevel__\n#elif defined(__clang_version__)\nclang\n__clang_major__.__clang_minor__.__clang_patchlevel__\n#elif defined(__INTEL_COMPILER)\nicc\n__INTEL_COMPILER\n#elif defined(__INTEL__LLVM_COMPILER)\nicx\n__INTEL_LLVM_COMPILER\n#else\ngcc\n__VERSION__\n#endif".as_bytes())
+            .expect("Failed to pipe");
+    });
+
+    let output = proc.wait_with_output().expect("Failed to read stdout");
+    let output = String::from_utf8(output.stdout).unwrap();
+
+    let version = output.split("\n")
+        .filter(|line| !line.starts_with("#"))
+        .map(|line| line.replace(" ", ""))
+        .collect::<Vec<String>>();
+    let mut version = version.iter()
+        .map(|line| line.trim())
+        .filter(|line| *line != "");
+
+    let ty = version.next().unwrap();
+    let version = version.next().unwrap();
+
+    match ty {
+        "clang" => CCVersion::Clang(semver::Version::parse(version).unwrap()),
+        // __VERSION__ is a string literal, e.g. "12.2.0 20220819 (prerelease)"
+        "gcc" => CCVersion::Gcc(semver::Version::parse(&version.trim_matches('"').chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect::<String>()).unwrap()),
+        "emscripten" => CCVersion::Emscripten(semver::Version::parse(version).unwrap()),
+        "icc" => CCVersion::Icc(version.parse::<i32>().unwrap()),
+        "icx" => CCVersion::Icx(version.parse::<i32>().unwrap()),
+        _ => unreachable!()
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -176,3 +187,15 @@ pub enum CCVersion {
     Icc(i32),
     Icx(i32)
 }
+
+impl std::fmt::Display for CCVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CCVersion::Clang(version) => write!(f, "clang {}", version),
+            CCVersion::Gcc(version) => write!(f, "gcc {}", version),
+            CCVersion::Emscripten(version) => write!(f, "emscripten {}", version),
+            CCVersion::Icc(version) => write!(f, "icc {}", version),
+            CCVersion::Icx(version) => write!(f, "icx {}", version),
+        }
+    }
+}
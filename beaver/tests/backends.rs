@@ -0,0 +1,103 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use beaver::Beaver;
+use beaver::target::parameters::Headers;
+use beaver::target::{ExecutableArtifactType, LibraryArtifactType, TargetRef, c};
+use beaver::traits::{AnyTarget, MutableProject};
+
+#[derive(Clone, Copy)]
+enum TestBackend {
+    /// Build in-process with the native backend
+    Native,
+    /// Export a Makefile and build it using make
+    Makefile,
+}
+
+impl TestBackend {
+    fn build(self, beaver: &Arc<Beaver>, dir: &Path) {
+        match self {
+            TestBackend::Native => {
+                beaver.build_all(&[TargetRef { project: 0, target: 1 }]).unwrap();
+                assert!(beaver.get_build_dir().unwrap().join("build.native.json").exists());
+            },
+            TestBackend::Makefile => {
+                beaver.export_makefile(&dir.join("Makefile")).unwrap();
+                let output = Self::make(dir, &["MyProject:Main"]);
+                assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            }
+        }
+    }
+
+    /// Whether building again changes the executable `artifact`
+    fn rebuilds(self, beaver: &Arc<Beaver>, dir: &Path, artifact: &Path) -> bool {
+        match self {
+            TestBackend::Native => {
+                let mtime = fs::metadata(artifact).unwrap().modified().unwrap();
+                beaver.build_all(&[TargetRef { project: 0, target: 1 }]).unwrap();
+                fs::metadata(artifact).unwrap().modified().unwrap() != mtime
+            },
+            TestBackend::Makefile => !Self::make(dir, &["-q", "all"]).status.success()
+        }
+    }
+
+    fn make(dir: &Path, args: &[&str]) -> std::process::Output {
+        Command::new("make")
+            .arg("-f").arg(dir.join("Makefile"))
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+    }
+}
+
+/// Build a library and an executable, and check which changes rebuild them
+fn build_with(name: &str, backend: TestBackend) {
+    let tmpdir = common::tmpdir(name);
+    fs::create_dir_all(tmpdir.join("src")).unwrap();
+    fs::create_dir_all(tmpdir.join("include")).unwrap();
+    fs::write(tmpdir.join("include/hello.h"), "int hello(void);\n").unwrap();
+    fs::write(tmpdir.join("src/hello.c"), "#include <hello.h>\nint hello(void) { return 42; }\n").unwrap();
+    fs::write(tmpdir.join("main.c"), "#include <hello.h>\nint main(void) { return 0; }\n").unwrap();
+
+    let beaver = common::beaver(&tmpdir);
+    let project = common::project(&beaver, "MyProject", &tmpdir);
+    project.add_target(AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+        headers: Headers::new(vec![tmpdir.join("include")], Vec::new()),
+        ..common::descriptor("Hello", "src/*.c", vec![LibraryArtifactType::Staticlib], &tmpdir)
+    }).unwrap().into())).unwrap();
+    project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+        dependencies: vec![common::library_dependency(0, 0, LibraryArtifactType::Staticlib)],
+        ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+    }).unwrap().into())).unwrap();
+    beaver.add_project(project).unwrap();
+
+    backend.build(&beaver, &tmpdir);
+
+    let build_dir = beaver.get_build_dir().unwrap().to_path_buf();
+    assert!(build_dir.join("MyProject/artifacts/libHello.a").exists());
+    let artifact = build_dir.join("MyProject/artifacts/Main");
+    assert!(Command::new(&artifact).status().unwrap().success());
+
+    // Nothing changed, so nothing should be rebuilt
+    assert!(!backend.rebuilds(&beaver, &tmpdir, &artifact));
+
+    // Headers are tracked through depfiles
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    fs::write(tmpdir.join("include/hello.h"), "int hello(void);\nint goodbye(void);\n").unwrap();
+    assert!(backend.rebuilds(&beaver, &tmpdir, &artifact));
+}
+
+#[test]
+fn native_backend() {
+    common::isolated("native_backend", || build_with("native_backend", TestBackend::Native));
+}
+
+#[test]
+fn makefile_backend() {
+    common::isolated("makefile_backend", || build_with("makefile_backend", TestBackend::Makefile));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use beaver::BeaverError;
+use beaver::target::{Dependency, ExecutableArtifactType, TargetRef, c};
+use beaver::traits::{AnyTarget, Project};
+
+/// Generate a source file with a rule and compile it as part of an executable
+#[test]
+fn build_rule() {
+    let tmpdir = common::tmpdir("build_rule");
+    fs::create_dir_all(tmpdir.join("src")).unwrap();
+    fs::write(tmpdir.join("src/main.c"), "int value(void);\nint main(void) { return value() == 42 ? 0 : 1; }\n").unwrap();
+    fs::write(tmpdir.join("value.c.in"), "int value(void) { return @VALUE@; }\n").unwrap();
+
+    let beaver = common::beaver(&tmpdir);
+    beaver.add_project(common::project(&beaver, "MyProject", &tmpdir)).unwrap();
+
+    beaver.define_rule("configure", vec![
+        ("command".to_string(), "sed s/@VALUE@/$value/ $in > $out".to_string()),
+        ("description".to_string(), "configure $out".to_string()),
+    ]).unwrap();
+    beaver.define_rule("stamp", vec![("command".to_string(), "touch $out".to_string())]).unwrap();
+
+    assert!(matches!(beaver.define_rule("configure", vec![("command".to_string(), "true".to_string())]), Err(BeaverError::RuleAlreadyDefined(_))));
+    assert!(matches!(beaver.define_rule("other", vec![("commands".to_string(), "true".to_string())]), Err(BeaverError::InvalidRuleOption { .. })));
+    assert!(matches!(beaver.define_rule("other", Vec::new()), Err(BeaverError::RuleWithoutCommand(_))));
+    assert!(matches!(beaver.define_rule("a b", vec![("command".to_string(), "true".to_string())]), Err(BeaverError::InvalidRuleName(_))));
+
+    let outputs = beaver.add_build_step(
+        "configure",
+        &[PathBuf::from("value.c.in")],
+        &[PathBuf::from("generated/value.c")],
+        Vec::new(),
+        vec![("value".to_string(), "42".to_string())]
+    ).unwrap();
+    let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+    assert_eq!(outputs, vec![project_build_dir.join("generated/value.c")]);
+
+    // Outputs can be used as file dependencies
+    let stamp = beaver.add_build_step(
+        "stamp",
+        &[],
+        &[PathBuf::from("stamp")],
+        vec![Dependency::File(outputs[0].clone())],
+        Vec::new()
+    ).unwrap();
+
+    assert!(matches!(beaver.add_build_step("undefined", &[], &[PathBuf::from("out")], Vec::new(), Vec::new()), Err(BeaverError::UndefinedRule(_))));
+    assert!(matches!(beaver.add_build_step("stamp", &[], &[PathBuf::from("stamp")], Vec::new(), Vec::new()), Err(BeaverError::DuplicateBuildOutput(_))));
+    assert!(matches!(beaver.add_build_step("stamp", &[], &[], Vec::new(), Vec::new()), Err(BeaverError::BuildStepWithoutOutputs(_))));
+    assert!(matches!(beaver.add_build_step("stamp", &[], &[PathBuf::from("x")], Vec::new(), vec![("out".to_string(), "y".to_string())]), Err(BeaverError::ReservedBuildStepVariable(_))));
+
+    let mut descriptor = common::descriptor("Main", "src/*.c", vec![ExecutableArtifactType::Executable], &tmpdir);
+    descriptor.sources.extend_generated(outputs.clone());
+    descriptor.dependencies = vec![Dependency::Multi(stamp.into_iter().map(Dependency::File).collect())];
+    let executable = c::Executable::new_desc(descriptor).unwrap();
+    beaver.with_current_project_mut(|project| {
+        project.as_mutable().unwrap().add_target(AnyTarget::Executable(executable.into()))
+    }).unwrap();
+
+    beaver.build_all(&[TargetRef { project: 0, target: 0 }]).unwrap();
+
+    assert!(project_build_dir.join("generated/value.c").exists());
+    assert!(project_build_dir.join("stamp").exists());
+    assert!(project_build_dir.join("objects/Main/static/generated/generated/value.c.o").exists());
+    let artifact = project_build_dir.join("artifacts/Main");
+    assert!(std::process::Command::new(&artifact).status().unwrap().success());
+
+    // Changing the input of the rule regenerates the source
+    let mtime = fs::metadata(&artifact).unwrap().modified().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    fs::write(tmpdir.join("value.c.in"), "int value(void) { return @VALUE@ + 1; }\n").unwrap();
+    beaver.build_all(&[TargetRef { project: 0, target: 0 }]).unwrap();
+    assert!(fs::metadata(&artifact).unwrap().modified().unwrap() > mtime);
+    assert!(!std::process::Command::new(&artifact).status().unwrap().success());
+
+    assert!(matches!(beaver.define_rule("late", vec![("command".to_string(), "true".to_string())]), Err(BeaverError::AlreadyFinalized)));
+}
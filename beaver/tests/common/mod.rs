@@ -0,0 +1,85 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, sync::Arc};
+
+use beaver::backend::Backend;
+use beaver::project::beaver::Project as BeaverProject;
+use beaver::target::parameters::{DefaultArgument, Files, Flags, Headers};
+use beaver::target::{Dependency, Language, LibraryArtifactType, LibraryTargetDependency, TargetRef};
+use beaver::{Beaver, OptimizationMode, target::c};
+use target_lexicon::Triple;
+
+/// Set in the child process running a single test
+const ISOLATED_TEST_ENV: &str = "BEAVER_TEST_ISOLATED";
+
+/// Runs the test `name` of this test executable in its own process, as only one `Beaver` can be
+/// created per process. `test` is only called in that process.
+pub fn isolated(name: &str, test: impl FnOnce()) {
+    if env::var_os(ISOLATED_TEST_ENV).is_some() {
+        return test();
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args([name, "--exact"])
+        .env(ISOLATED_TEST_ENV, name)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    // The name filter could match no tests
+    assert!(stdout.contains("test result: ok. 1 passed"), "{}", stdout);
+}
+
+/// A new temporary directory for the test `name`
+pub fn tmpdir(name: &str) -> PathBuf {
+    tempdir::TempDir::new(&format!("be.jonaseveraert.beaver.tests.beaver.{}", name)).unwrap().into_path()
+}
+
+/// A debug `Beaver` for the host, building with the native backend in `dir/build`
+pub fn beaver(dir: &Path) -> Arc<Beaver> {
+    configured_beaver(dir, |_| {})
+}
+
+/// Like `beaver`, calling `configure` before the build directory is set (e.g. to enable
+/// sanitizers, which changes the build directory)
+pub fn configured_beaver(dir: &Path, configure: impl FnOnce(&mut Beaver)) -> Arc<Beaver> {
+    let mut beaver = Beaver::new(Some(false), OptimizationMode::Debug, false, false, Triple::host(), Backend::Native).unwrap();
+    configure(&mut beaver);
+    let beaver = Arc::new(beaver);
+    beaver.set_build_dir(dir.join("build")).unwrap();
+    return beaver;
+}
+
+pub fn project(beaver: &Beaver, name: &str, dir: &Path) -> BeaverProject {
+    BeaverProject::new(name.to_string(), dir.to_path_buf(), beaver.get_build_dir().unwrap()).unwrap()
+}
+
+/// A C target compiling the sources matching `sources` in `dir`, without flags, headers or
+/// dependencies
+pub fn descriptor<ArtifactType>(name: &str, sources: &str, artifacts: Vec<ArtifactType>, dir: &Path) -> c::TargetDescriptor<ArtifactType> {
+    c::TargetDescriptor {
+        name: name.to_string(),
+        description: None,
+        homepage: None,
+        version: None,
+        license: None,
+        language: Language::C,
+        sources: Files::from_pat(sources, dir).unwrap(),
+        cflags: Flags::new(Vec::new(), Vec::new()),
+        headers: Headers::new(Vec::new(), Vec::new()),
+        linker_flags: Vec::new(),
+        artifacts: DefaultArgument::Some(artifacts),
+        dependencies: Vec::new(),
+        settings: Vec::new()
+    }
+}
+
+pub fn library_dependency(project: usize, target: usize, artifact: LibraryArtifactType) -> Dependency {
+    Dependency::Library(LibraryTargetDependency {
+        target: TargetRef { project, target },
+        artifact
+    })
+}
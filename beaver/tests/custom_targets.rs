@@ -0,0 +1,174 @@
+mod common;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use std::{env, fs, sync::Arc};
+
+use beaver::BeaverError;
+use beaver::target::custom::{self, BuildCommand, BuildOutput};
+use beaver::target::parameters::Files;
+use beaver::target::{ExecutableArtifactType, Language, TargetRef};
+use beaver::traits::{AnyExecutable, AnyTarget, MutableProject};
+
+/// The directory of the project built by a child process
+const PROJECT_DIR_ENV: &str = "BEAVER_TEST_PROJECT_DIR";
+/// The definition of the custom target in the child process
+const DEFINITION_ENV: &str = "BEAVER_TEST_DEFINITION";
+
+fn custom_executable(name: &str, output: PathBuf, run: impl Fn(BuildOutput) -> beaver::Result<()> + Send + Sync + 'static) -> AnyTarget {
+    AnyTarget::Executable(AnyExecutable::Custom(custom::Executable::new(
+        name.to_string(),
+        None, None, None, None,
+        Language::C,
+        Vec::new(),
+        None,
+        HashMap::from([(ExecutableArtifactType::Executable, output)]),
+        BuildCommand { run: Box::new(run), definition: name.to_string() },
+    )))
+}
+
+/// Define the project in `dir` and build it. Every run of the build command is appended to `runs`.
+fn build(dir: &Path, definition: String) {
+    let beaver = common::beaver(dir);
+    let project = common::project(&beaver, "MyProject", dir);
+    let output = dir.join("generated");
+    let runs = dir.join("runs");
+    project.add_target(AnyTarget::Executable(AnyExecutable::Custom(custom::Executable::new(
+        "Gen".to_string(),
+        None, None, None, None,
+        Language::C,
+        Vec::new(),
+        Some(Files::from_pat("*.proto", dir).unwrap()),
+        HashMap::from([(ExecutableArtifactType::Executable, output.clone())]),
+        BuildCommand {
+            run: Box::new(move |_| {
+                let count = fs::read_to_string(&runs).unwrap_or_default();
+                fs::write(&runs, format!("{}x", count))?;
+                fs::write(&output, "").map_err(BeaverError::from)
+            }),
+            definition,
+        },
+    )))).unwrap();
+    beaver.add_project(project).unwrap();
+
+    beaver.build_all(&[TargetRef { project: 0, target: 0 }]).unwrap();
+}
+
+/// Every build runs in its own process, as only one `Beaver` can be created per process
+fn build_in_child(dir: &Path, definition: &str) -> usize {
+    let status = Command::new(env::current_exe().unwrap())
+        .args(["rebuild_custom_target", "--exact", "--nocapture"])
+        .env(PROJECT_DIR_ENV, dir)
+        .env(DEFINITION_ENV, definition)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    fs::read_to_string(dir.join("runs")).unwrap().len()
+}
+
+/// The build command of a custom target only runs when its outputs are older than its inputs,
+/// or when its definition changed
+#[test]
+fn rebuild_custom_target() {
+    // The build command calls back into this executable as `<exe> callback build <target>`,
+    // which runs this test again. It's the only test whose name matches those arguments.
+    if env::var_os(beaver::callback::SOCKET_ENV).is_some() {
+        let request = env::args().skip(2).collect::<Vec<String>>().join(" ");
+        std::process::exit(beaver::callback::request(&request).unwrap());
+    }
+    if let Some(dir) = env::var_os(PROJECT_DIR_ENV) {
+        build(&PathBuf::from(dir), env::var(DEFINITION_ENV).unwrap());
+        return;
+    }
+
+    let tmpdir = common::tmpdir("rebuild_custom_target");
+    fs::write(tmpdir.join("message.proto"), "").unwrap();
+
+    assert_eq!(build_in_child(&tmpdir, "a"), 1);
+    assert!(tmpdir.join("generated").exists());
+
+    // The output is newer than the input
+    assert_eq!(build_in_child(&tmpdir, "a"), 1);
+
+    std::thread::sleep(Duration::from_millis(10));
+    fs::write(tmpdir.join("message.proto"), "message A {}").unwrap();
+    assert_eq!(build_in_child(&tmpdir, "a"), 2);
+    assert_eq!(build_in_child(&tmpdir, "a"), 2);
+
+    assert_eq!(build_in_child(&tmpdir, "b"), 3);
+    assert_eq!(build_in_child(&tmpdir, "b"), 3);
+}
+
+/// Build commands are requested over the socket of the beaver process, which streams their
+/// output and exit status back. Requests are handled concurrently.
+#[test]
+fn socket_requests() {
+    common::isolated("socket_requests", || {
+        let tmpdir = common::tmpdir("socket_requests");
+        let beaver = common::beaver(&tmpdir);
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let output = tmpdir.join("generated");
+        let output2 = output.clone();
+        project.add_target(custom_executable("gen", output.clone(), move |mut out| {
+            writeln!(out.stdout, "generating").unwrap();
+            writeln!(out.stderr, "a warning").unwrap();
+            fs::write(&output2, "").map_err(BeaverError::from)
+        })).unwrap();
+        project.add_target(custom_executable("fail", tmpdir.join("never"), |_| {
+            Err(BeaverError::AnyError("the command failed".to_string()))
+        })).unwrap();
+        // Only finish when they are built at the same time
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        for name in ["a", "b"] {
+            let barrier = barrier.clone();
+            project.add_target(custom_executable(name, tmpdir.join(name), move |_| {
+                barrier.wait();
+                Ok(())
+            })).unwrap();
+        }
+        beaver.add_project(project).unwrap();
+
+        assert!(beaver.communication_socket().is_none());
+        beaver.create_build_file().unwrap();
+        let socket = beaver.communication_socket().unwrap().to_path_buf();
+        assert!(socket.exists());
+
+        let build_file = fs::read_to_string(beaver.get_build_dir().unwrap().join("build.native.json")).unwrap();
+        assert!(build_file.contains("callback build 0:0"));
+        assert!(!build_file.contains(socket.to_str().unwrap()));
+
+        let request = |request: &str| {
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let status = program_communicator::socket::request(&socket, request, &mut stdout, &mut stderr).unwrap();
+            (status, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+        };
+
+        assert_eq!(request("build 0:0"), (0, "generating\n".to_string(), "a warning\n".to_string()));
+        assert!(output.exists());
+
+        let (status, stdout, stderr) = request("build 0:1");
+        assert_eq!(status, 1);
+        assert_eq!(stdout, "");
+        assert!(stderr.contains("the command failed"), "{}", stderr);
+
+        let (status, _, stderr) = request("build 0");
+        assert_eq!(status, 1);
+        assert!(stderr.contains("Invalid callback request"), "{}", stderr);
+
+        let concurrent = ["build 0:2", "build 0:3"].map(|request| {
+            let socket = socket.clone();
+            std::thread::spawn(move || program_communicator::socket::request(&socket, request, &mut Vec::new(), &mut Vec::new()).unwrap())
+        });
+        for request in concurrent {
+            assert_eq!(request.join().unwrap(), 0);
+        }
+
+        // The socket is removed when beaver is dropped
+        drop(beaver);
+        assert!(!socket.exists());
+    });
+}
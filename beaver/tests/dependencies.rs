@@ -0,0 +1,278 @@
+mod common;
+
+use std::fs;
+
+use beaver::BeaverError;
+use beaver::target::parameters::{DefaultArgument, Dependencies, Files, Flags, Headers};
+use beaver::target::{Dependency, ExecutableArtifactType, LibraryArtifactType, LibraryTargetDependency, TargetRef, c};
+use beaver::traits::{AnyTarget, MutableProject};
+use pkgconfig_parser::PkgConfig;
+
+fn interface_descriptor(sources: Files, tmpdir: &std::path::Path) -> c::TargetDescriptor<LibraryArtifactType> {
+    c::TargetDescriptor {
+        sources,
+        cflags: Flags::new(vec!["-DHEADER_VALUE=4".to_string()], Vec::new()),
+        headers: Headers::new(vec![tmpdir.join("include")], Vec::new()),
+        linker_flags: vec!["-lm".to_string()],
+        ..common::descriptor("Header", "", vec![LibraryArtifactType::PkgConfig], tmpdir)
+    }
+}
+
+/// An executable is compiled with the public and interface dependencies of its dependencies,
+/// and links against the private dependencies of a static library
+#[test]
+fn dependency_visibility() {
+    common::isolated("dependency_visibility", || {
+        let tmpdir = common::tmpdir("dependency_visibility");
+        fs::write(tmpdir.join("b.c"), "#ifndef B_PUBLIC\n#error\n#endif\nint b(void) { return 2; }\n").unwrap();
+        fs::write(tmpdir.join("a.c"), "#ifndef B_PUBLIC\n#error\n#endif\n#ifdef FROM_INTERFACE\n#error\n#endif\nint b(void);\nint a(void) { return b() + 1; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "#ifdef B_PUBLIC\n#error\n#endif\n#ifndef FROM_INTERFACE\n#error\n#endif\nint a(void);\nint main(void) { return a(); }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+
+        let artifacts = vec![LibraryArtifactType::Staticlib, LibraryArtifactType::PkgConfig];
+        let b = c::Library::new_desc(c::TargetDescriptor {
+            cflags: Flags::new(vec!["-DB_PUBLIC".to_string()], Vec::new()),
+            ..common::descriptor("B", "b.c", artifacts.clone(), &tmpdir)
+        }).unwrap();
+        let mut a = c::Library::new_desc(common::descriptor("A", "a.c", artifacts, &tmpdir)).unwrap();
+        a.set_dependencies(Dependencies::new(
+            Vec::new(),
+            vec![
+                common::library_dependency(0, 0, LibraryArtifactType::Staticlib),
+                Dependency::PkgConfig {
+                    name: "zlib".to_string(),
+                    version_requirement: None,
+                    cflags: Vec::new(),
+                    linker_flags: Vec::new()
+                }
+            ],
+            vec![Dependency::Flags { cflags: Some(vec!["-DFROM_INTERFACE".to_string()]), linker_flags: None, headers: None }]
+        ));
+        let main = c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![common::library_dependency(0, 1, LibraryArtifactType::Staticlib)],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap();
+        project.add_target(AnyTarget::Library(b.into())).unwrap();
+        project.add_target(AnyTarget::Library(a.into())).unwrap();
+        project.add_target(AnyTarget::Executable(main.into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all_named(&["MyProject:Main", "MyProject:A:pkgconfig"]).unwrap();
+
+        let artifacts = beaver.get_build_dir().unwrap().join("MyProject/artifacts");
+        assert_eq!(std::process::Command::new(artifacts.join("Main")).status().unwrap().code(), Some(3));
+
+        // Consumers of A are not compiled with its private dependencies
+        let pkgconfig = fs::read_to_string(artifacts.join("A.pc")).unwrap();
+        let pkgconfig = PkgConfig::parse(&pkgconfig).unwrap();
+        assert_eq!(pkgconfig.requires(), &None);
+        assert_eq!(pkgconfig.requires_private().as_deref(), Some("B, zlib"));
+        assert_eq!(pkgconfig.cflags().as_deref(), Some("-DFROM_INTERFACE"));
+    });
+}
+
+/// A header-only library is not compiled, but passes on its headers and flags
+#[test]
+fn interface_library() {
+    common::isolated("interface_library", || {
+        let tmpdir = common::tmpdir("interface_library");
+        fs::create_dir_all(tmpdir.join("include")).unwrap();
+        fs::write(tmpdir.join("include/header.h"), "static inline int header_value(void) { return HEADER_VALUE; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "#include <header.h>\nint main(void) { return header_value(); }\n").unwrap();
+
+        // Interface libraries can't compile anything
+        assert!(matches!(
+            c::Library::new_interface_desc(interface_descriptor(Files::from_pat("main.c", &tmpdir).unwrap(), &tmpdir)),
+            Err(BeaverError::InterfaceLibraryWithSources(_))
+        ));
+        let mut descriptor = interface_descriptor(Files::from_generated(Vec::new()), &tmpdir);
+        descriptor.artifacts = DefaultArgument::Some(vec![LibraryArtifactType::Staticlib]);
+        assert!(matches!(c::Library::new_interface_desc(descriptor), Err(BeaverError::InterfaceLibraryArtifact(_, LibraryArtifactType::Staticlib))));
+
+        let beaver = common::beaver(&tmpdir);
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let header = c::Library::new_interface_desc(interface_descriptor(Files::from_generated(Vec::new()), &tmpdir)).unwrap();
+        project.add_target(AnyTarget::Library(header.into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        // Referring to the library by name links against its interface
+        let dependency = beaver.parse_target_ref("Header").unwrap();
+        let artifact = beaver.with_project_and_target::<_, BeaverError>(&dependency, |_, target| {
+            use beaver::traits::Library;
+            match target {
+                AnyTarget::Library(library) => Ok(library.default_library_artifact()),
+                AnyTarget::Executable(_) => panic!("Header is a library")
+            }
+        }).unwrap();
+        assert_eq!(artifact, Some(LibraryArtifactType::Interface));
+
+        let main = c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![Dependency::Library(LibraryTargetDependency { target: dependency, artifact: artifact.unwrap() })],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap();
+        beaver.with_current_project_mut(|project| {
+            use beaver::traits::Project;
+            project.as_mutable().unwrap().add_target(AnyTarget::Executable(main.into()))
+        }).unwrap();
+
+        beaver.build_all(&[TargetRef { project: 0, target: 0 }, TargetRef { project: 0, target: 1 }]).unwrap();
+
+        let artifacts = beaver.get_build_dir().unwrap().join("MyProject/artifacts");
+        assert_eq!(std::process::Command::new(artifacts.join("Main")).status().unwrap().code(), Some(4));
+        assert!(!beaver.get_build_dir().unwrap().join("MyProject/objects/Header").exists());
+
+        let pkgconfig = fs::read_to_string(artifacts.join("Header.pc")).unwrap();
+        let pkgconfig = PkgConfig::parse(&pkgconfig).unwrap();
+        assert_eq!(pkgconfig.libs().as_deref(), Some("-lm"));
+        assert_eq!(pkgconfig.cflags().as_deref(), Some(format!("'-DHEADER_VALUE=4' -I{}", tmpdir.join("include").display()).as_str()));
+    });
+}
+
+/// Object files are linked as a whole, so unreferenced objects aren't dropped like in an archive
+#[test]
+fn object_library() {
+    common::isolated("object_library", || {
+        let tmpdir = common::tmpdir("object_library");
+        fs::create_dir_all(tmpdir.join("plugins")).unwrap();
+        fs::write(tmpdir.join("plugins/register.c"), "extern int value;\n__attribute__((constructor)) static void init(void) { value = 7; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "int value = 0;\nint main(void) { return value; }\n").unwrap();
+        fs::write(tmpdir.join("shared.c"), "int value = 0;\nint shared_value(void) { return value; }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        project.add_target(AnyTarget::Library(c::Library::new_desc(
+            common::descriptor("Plugins", "plugins/*.c", vec![LibraryArtifactType::Objects], &tmpdir)
+        ).unwrap().into())).unwrap();
+        let executable = |name: &str| AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![common::library_dependency(0, 0, LibraryArtifactType::Objects)],
+            ..common::descriptor(name, "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into());
+        project.add_target(executable("First")).unwrap();
+        project.add_target(executable("Second")).unwrap();
+        // The objects are position independent, so they can be linked into a dynamic library
+        project.add_target(AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+            dependencies: vec![common::library_dependency(0, 0, LibraryArtifactType::Objects)],
+            ..common::descriptor("Shared", "shared.c", vec![LibraryArtifactType::Dynlib], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all(&[
+            TargetRef { project: 0, target: 1 },
+            TargetRef { project: 0, target: 2 },
+            TargetRef { project: 0, target: 3 },
+        ]).unwrap();
+
+        // Both executables use the same objects, and no library is created
+        assert!(project_build_dir.join("objects/Plugins/static/plugins/register.c.o").exists());
+        assert!(!project_build_dir.join("artifacts/libPlugins.a").exists());
+        assert_eq!(std::process::Command::new(project_build_dir.join("artifacts/First")).status().unwrap().code(), Some(7));
+        assert_eq!(std::process::Command::new(project_build_dir.join("artifacts/Second")).status().unwrap().code(), Some(7));
+    });
+}
+
+/// Every static library of a deep diamond-shaped graph is linked once, after the libraries
+/// depending on it
+#[test]
+fn diamond_dependencies() {
+    common::isolated("diamond_dependencies", || {
+        const LAYERS: usize = 24;
+
+        let tmpdir = common::tmpdir("diamond_dependencies");
+        fs::write(tmpdir.join("lib.c"), "int lib(void) { return 0; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "int main(void) { return 0; }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        // Both libraries of a layer depend on both libraries of the next layer
+        for layer in 0..LAYERS {
+            for side in ["L", "R"] {
+                let dependencies = if layer + 1 == LAYERS {
+                    Vec::new()
+                } else {
+                    vec![
+                        common::library_dependency(0, 2 * layer + 2, LibraryArtifactType::Staticlib),
+                        common::library_dependency(0, 2 * layer + 3, LibraryArtifactType::Staticlib)
+                    ]
+                };
+                project.add_target(AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+                    dependencies,
+                    ..common::descriptor(&format!("{}{}", side, layer), "lib.c", vec![LibraryArtifactType::Staticlib], &tmpdir)
+                }).unwrap().into())).unwrap();
+            }
+        }
+        project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![
+                common::library_dependency(0, 0, LibraryArtifactType::Staticlib),
+                common::library_dependency(0, 1, LibraryArtifactType::Staticlib)
+            ],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.create_build_file().unwrap();
+        let build_file = fs::read_to_string(beaver.get_build_dir().unwrap().join("build.native.json")).unwrap();
+        let build_file: serde_json::Value = serde_json::from_str(&build_file).unwrap();
+        let main = beaver.get_build_dir().unwrap().join("MyProject/artifacts/Main");
+        let implicit = build_file["edges"].as_array().unwrap().iter()
+            .find(|edge| edge["outputs"][0] == main.to_str().unwrap())
+            .map(|edge| edge["implicit"].clone())
+            .unwrap();
+        let expected: Vec<String> = (0..LAYERS)
+            .flat_map(|layer| ["L", "R"].map(|side| format!("MyProject:{}{}:staticlib", side, layer)))
+            .collect();
+        assert_eq!(implicit, serde_json::json!(expected));
+    });
+}
+
+/// Generate a pkg-config file for a library depending on another beaver library and a pkg-config library
+#[test]
+fn pkgconfig_artifact() {
+    common::isolated("pkgconfig_artifact", || {
+        let tmpdir = common::tmpdir("pkgconfig_artifact");
+        fs::create_dir_all(tmpdir.join("include")).unwrap();
+        fs::write(tmpdir.join("A.c"), "int a(void) { return 1; }\n").unwrap();
+        fs::write(tmpdir.join("B.c"), "int b(void) { return 2; }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let library = |name: &str, headers: Vec<std::path::PathBuf>, dependencies: Vec<Dependency>| AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+            description: Some(format!("The {} library", name)),
+            version: Some(beaver::target::Version::parse("1.2.3")),
+            cflags: Flags::new(vec!["-DPUBLIC".to_string()], vec!["-DPRIVATE".to_string()]),
+            headers: Headers::new(headers, Vec::new()),
+            linker_flags: vec!["-lm".to_string()],
+            dependencies,
+            ..common::descriptor(name, &format!("{}.c", name), vec![LibraryArtifactType::Staticlib, LibraryArtifactType::PkgConfig], &tmpdir)
+        }).unwrap().into());
+        project.add_target(library("A", Vec::new(), Vec::new())).unwrap();
+        project.add_target(library("B", vec![tmpdir.join("include")], vec![
+            common::library_dependency(0, 0, LibraryArtifactType::Staticlib),
+            Dependency::PkgConfig {
+                name: "zlib".to_string(),
+                version_requirement: Some(">=1.2".to_string()),
+                cflags: Vec::new(),
+                linker_flags: vec!["-lz".to_string()]
+            }
+        ])).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all_named(&["MyProject:B:pkgconfig"]).unwrap();
+
+        let artifacts = beaver.get_build_dir().unwrap().join("MyProject/artifacts");
+        assert!(artifacts.join("libB.a").exists());
+
+        let contents = fs::read_to_string(artifacts.join("B.pc")).unwrap();
+        let pkgconfig = PkgConfig::parse(&contents).unwrap();
+        assert_eq!(pkgconfig.name().as_deref(), Some("B"));
+        assert_eq!(pkgconfig.description().as_deref(), Some("The B library"));
+        assert_eq!(pkgconfig.version().as_deref(), Some("1.2.3"));
+        assert_eq!(pkgconfig.requires().as_deref(), Some("A, zlib >= 1.2"));
+        assert_eq!(pkgconfig.libs().as_deref(), Some(format!("-L{} -lB -lm", artifacts.display()).as_str()));
+        assert_eq!(pkgconfig.cflags().as_deref(), Some(format!("-DPUBLIC -I{}", tmpdir.join("include").display()).as_str()));
+        assert!(artifacts.join("A.pc").exists());
+    });
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use beaver::install::InstallOptions;
+use beaver::phase_hook::Phase;
+use beaver::target::parameters::{Flags, Headers};
+use beaver::target::{ExecutableArtifactType, LibraryArtifactType, c};
+use beaver::testing::TestSettings;
+use beaver::traits::{AnyTarget, MutableProject};
+use pkgconfig_parser::PkgConfig;
+
+fn mode(file: &Path) -> u32 {
+    fs::metadata(file).unwrap().permissions().mode() & 0o777
+}
+
+static HOOK_RAN: AtomicBool = AtomicBool::new(false);
+
+/// Install a library with headers and a pkg-config file and an executable into a staged prefix,
+/// then uninstall them again
+#[test]
+fn install() {
+    let tmpdir = common::tmpdir("install");
+    fs::create_dir_all(tmpdir.join("include/greet")).unwrap();
+    fs::write(tmpdir.join("include/greet.h"), "#include \"greet/detail.h\"\nint greet(void);\n").unwrap();
+    fs::write(tmpdir.join("include/greet/detail.h"), "#define GREET_DETAIL 1\n").unwrap();
+    fs::write(tmpdir.join("include/README.md"), "Not a header\n").unwrap();
+    fs::write(tmpdir.join("Greet.c"), "#include <greet.h>\nint greet(void) { return GREET_DETAIL; }\n").unwrap();
+    fs::write(tmpdir.join("hello.c"), "int main(void) { return 0; }\n").unwrap();
+    fs::write(tmpdir.join("check.c"), "int main(void) { return 0; }\n").unwrap();
+
+    let beaver = common::beaver(&tmpdir);
+    let project = common::project(&beaver, "MyProject", &tmpdir);
+    let library = c::Library::new_desc(c::TargetDescriptor {
+        version: Some(beaver::target::Version::parse("1.0.0")),
+        cflags: Flags::new(vec!["-DPUBLIC".to_string()], Vec::new()),
+        headers: Headers::new(vec![tmpdir.join("include")], Vec::new()),
+        ..common::descriptor("Greet", "Greet.c", vec![LibraryArtifactType::Dynlib, LibraryArtifactType::Staticlib, LibraryArtifactType::PkgConfig], &tmpdir)
+    }).unwrap();
+    let executable_desc = |name: &str| common::descriptor(name, &format!("{}.c", name), vec![ExecutableArtifactType::Executable], &tmpdir);
+    let hello = c::Executable::new_desc(executable_desc("hello")).unwrap();
+    let check = c::Executable::new_test_desc(executable_desc("check"), TestSettings::default()).unwrap();
+    project.add_target(AnyTarget::Library(library.into())).unwrap();
+    project.add_target(AnyTarget::Executable(hello.into())).unwrap();
+    project.add_target(AnyTarget::Executable(check.into())).unwrap();
+    beaver.add_project(project).unwrap();
+
+    beaver.add_phase_hook(Phase::Install, Box::new(|| {
+        HOOK_RAN.store(true, Ordering::SeqCst);
+        Ok(())
+    })).unwrap();
+
+    let mut options = InstallOptions::new("/opt/greet".into());
+    options.destdir = Some(tmpdir.join("stage"));
+    beaver.install_current_project(&options).unwrap();
+    assert!(HOOK_RAN.load(Ordering::SeqCst));
+
+    let prefix = tmpdir.join("stage/opt/greet");
+    let dynlib = prefix.join("lib/libGreet.so");
+    let staticlib = prefix.join("lib/libGreet.a");
+    let executable = prefix.join("bin/hello");
+    let header = prefix.join("include/greet.h");
+    let nested_header = prefix.join("include/greet/detail.h");
+    let pkgconfig = prefix.join("lib/pkgconfig/Greet.pc");
+    assert_eq!(mode(&dynlib), 0o755);
+    assert_eq!(mode(&staticlib), 0o644);
+    assert_eq!(mode(&executable), 0o755);
+    assert_eq!(mode(&header), 0o644);
+    assert_eq!(mode(&nested_header), 0o644);
+    assert_eq!(mode(&pkgconfig), 0o644);
+    assert!(!prefix.join("include/README.md").exists());
+    // The dynamic library is installed with its version and soname links
+    let versioned_dynlib = prefix.join("lib/libGreet.so.1.0.0");
+    let soname = prefix.join("lib/libGreet.so.1");
+    assert!(versioned_dynlib.symlink_metadata().unwrap().is_file());
+    assert_eq!(fs::read_link(&soname).unwrap(), Path::new("libGreet.so.1.0.0"));
+    assert_eq!(fs::read_link(&dynlib).unwrap(), Path::new("libGreet.so.1"));
+    // Tests aren't installed
+    assert!(!prefix.join("bin/check").exists());
+
+    // The pkg-config file refers to the prefix, not to the staging directory
+    let contents = fs::read_to_string(&pkgconfig).unwrap();
+    assert!(contents.starts_with("prefix=/opt/greet\nlibdir=${prefix}/lib\nincludedir=${prefix}/include\n"));
+    let parsed = PkgConfig::parse(&contents).unwrap();
+    assert_eq!(parsed.libs().as_deref(), Some("-L/opt/greet/lib -lGreet"));
+    assert_eq!(parsed.cflags().as_deref(), Some("-DPUBLIC -I/opt/greet/include"));
+
+    // Installing again overwrites the files
+    beaver.install_current_project(&options).unwrap();
+    assert!(executable.exists());
+
+    beaver.uninstall().unwrap();
+    for file in [&dynlib, &soname, &versioned_dynlib, &staticlib, &executable, &header, &nested_header, &pkgconfig] {
+        assert!(file.symlink_metadata().is_err(), "{} wasn't removed", file.display());
+    }
+    assert!(beaver.cache().unwrap().installed_files().unwrap().is_empty());
+}
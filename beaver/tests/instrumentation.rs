@@ -0,0 +1,96 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use beaver::coverage::{CoverageFormat, CoverageOptions};
+use beaver::target::{ExecutableArtifactType, c};
+use beaver::testing::{TestOptions, TestSettings};
+use beaver::traits::{AnyTarget, MutableProject};
+use beaver::{BeaverError, Sanitizer};
+use target_lexicon::Triple;
+
+/// Build an executable with AddressSanitizer and UndefinedBehaviorSanitizer in its own build directory
+#[test]
+fn sanitizers() {
+    common::isolated("sanitizers", || {
+        let tmpdir = common::tmpdir("sanitizers");
+        fs::write(tmpdir.join("overflow.c"), "#include <stdlib.h>\nint main(int argc, char** argv) {\n  int* values = malloc(4 * sizeof(int));\n  int value = values[argc + 3];\n  free(values);\n  return value;\n}\n").unwrap();
+
+        let beaver = common::configured_beaver(&tmpdir, |beaver| {
+            assert!(matches!(
+                beaver.set_sanitizers(vec![Sanitizer::Thread, Sanitizer::Address]),
+                Err(BeaverError::IncompatibleSanitizers(Sanitizer::Address, Sanitizer::Thread))
+            ));
+            beaver.set_sanitizers(vec![Sanitizer::Undefined, Sanitizer::Address, Sanitizer::Undefined]).unwrap();
+            assert_eq!(beaver.sanitizers(), &[Sanitizer::Address, Sanitizer::Undefined]);
+        });
+        assert_eq!(beaver.get_build_dir().unwrap(), tmpdir.join("build").join(Triple::host().to_string()).join("debug-asan-ubsan"));
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let executable = c::Executable::new_desc(common::descriptor("overflow", "overflow.c", vec![ExecutableArtifactType::Executable], &tmpdir)).unwrap();
+        project.add_target(AnyTarget::Executable(executable.into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all_named(&["MyProject"]).unwrap();
+
+        let output = Command::new(beaver.get_build_dir().unwrap().join("MyProject/artifacts/overflow")).output().unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("AddressSanitizer: heap-buffer-overflow"), "{}", stderr);
+    });
+}
+
+#[test]
+fn sanitizer_names() {
+    for sanitizer in Sanitizer::ALL {
+        assert_eq!(Sanitizer::try_from(sanitizer.to_string().as_str()).unwrap(), sanitizer);
+        assert_eq!(Sanitizer::try_from(sanitizer.short_name()).unwrap(), sanitizer);
+    }
+    assert!(Sanitizer::try_from("memory-leak").is_err());
+    assert_eq!(
+        Sanitizer::flags(&[Sanitizer::Address, Sanitizer::Leak]),
+        vec!["-fsanitize=address,leak".to_string(), "-fno-omit-frame-pointer".to_string()]
+    );
+    assert!(Sanitizer::flags(&[]).is_empty());
+}
+
+/// Run a test built with coverage instrumentation and write a text summary of the covered lines
+#[test]
+fn coverage() {
+    common::isolated("coverage", || {
+        let tmpdir = common::tmpdir("coverage");
+        fs::create_dir(tmpdir.join("src")).unwrap();
+        fs::write(tmpdir.join("src/math.c"), "int add(int a, int b) {\n  return a + b;\n}\n\nint sub(int a, int b) {\n  return a - b;\n}\n").unwrap();
+        fs::write(tmpdir.join("src/test.c"), "int add(int a, int b);\n\nint main(void) {\n  return add(1, 2) == 3 ? 0 : 1;\n}\n").unwrap();
+
+        let beaver = common::configured_beaver(&tmpdir, |beaver| beaver.set_coverage(true));
+        assert_eq!(beaver.get_build_dir().unwrap(), tmpdir.join("build").join(Triple::host().to_string()).join("debug-coverage"));
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let test = c::Executable::new_test_desc(
+            common::descriptor("math-test", "src/*.c", vec![ExecutableArtifactType::Executable], &tmpdir),
+            TestSettings::default()
+        ).unwrap();
+        project.add_target(AnyTarget::Executable(test.into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        let report = tmpdir.join("coverage.txt");
+        let options = CoverageOptions { format: CoverageFormat::Text, output: Some(report.clone()) };
+        beaver.coverage(&TestOptions::default(), &options).unwrap();
+
+        let summary = fs::read_to_string(&report).unwrap();
+        let line = |file: &str| summary.lines()
+            .find(|line| line.starts_with(file))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .unwrap_or_else(|| panic!("{} not in summary:\n{}", file, summary));
+        // `sub` is never called. The number of executable lines depends on the compiler.
+        assert_eq!(line("src/math.c")[3], "50.00%");
+        assert_eq!(line("src/test.c")[3], "100.00%");
+        assert!(line("TOTAL")[3] != "100.00%");
+
+        // Counters of a previous run are reset
+        beaver.coverage(&TestOptions::default(), &options).unwrap();
+        assert_eq!(fs::read_to_string(&report).unwrap(), summary);
+    });
+}
@@ -4,6 +4,7 @@ use beaver::target::parameters::{DefaultArgument, Files, Flags, Headers};
 use beaver::target::{Dependency, Language, LibraryArtifactType};
 use beaver::project::beaver::Project as BeaverProject;
 use beaver::traits::{AnyTarget, MutableProject, Project, Target};
+use beaver::backend::Backend;
 use beaver::{Beaver, OptimizationMode, target::c};
 use target_lexicon::Triple;
 
@@ -15,7 +16,7 @@ fn adding() {
 
     dbg!(&tmpdir);
 
-    let beaver = Beaver::new(Some(true), OptimizationMode::Debug, true, false, Triple::host()).unwrap();
+    let beaver = Beaver::new(Some(true), OptimizationMode::Debug, true, false, Triple::host(), Backend::Ninja).unwrap();
     beaver.set_build_dir(tmpdir.join("build")).unwrap();
     let project = BeaverProject::new(
         String::from("MyProject"),
@@ -0,0 +1,286 @@
+mod common;
+
+use std::fs;
+
+use beaver::target::parameters::Flags;
+use beaver::target::{ExecutableArtifactType, LibraryArtifactType, TargetRef, c};
+use beaver::traits::{AnyTarget, MutableProject};
+
+/// The value of the dynamic section entry `tag`, e.g. `SONAME`
+#[cfg(target_os = "linux")]
+fn dynamic_entry(file: &std::path::Path, tag: &str) -> Option<String> {
+    let output = std::process::Command::new("readelf").arg("-d").arg(file).output().unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    let line = output.lines().find(|line| line.contains(&format!("({})", tag)))?;
+    return Some(line[line.find('[').unwrap() + 1..line.find(']').unwrap()].to_string());
+}
+
+/// The symbols exported by the dynamic library `file`
+#[cfg(target_os = "linux")]
+fn exported_symbols(file: &std::path::Path) -> Vec<String> {
+    let output = std::process::Command::new("nm").args(["-D", "--defined-only"]).arg(file).output().unwrap();
+    return String::from_utf8(output.stdout).unwrap().lines()
+        .filter_map(|line| line.split_whitespace().last().map(|symbol| symbol.to_string()))
+        .collect();
+}
+
+#[cfg(target_os = "linux")]
+fn runpath(file: &std::path::Path) -> String {
+    dynamic_entry(file, "RUNPATH").or_else(|| dynamic_entry(file, "RPATH")).unwrap()
+}
+
+/// A plugin resolving symbols of the executable loading it, which is only built before its host
+#[test]
+fn module_artifact() {
+    common::isolated("module_artifact", || {
+        let tmpdir = common::tmpdir("module_artifact");
+        fs::create_dir_all(tmpdir.join("plugin")).unwrap();
+        fs::write(tmpdir.join("plugin/plugin.c"), "int host_value(void);\nint plugin_value(void) { return host_value() + 1; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), concat!(
+            "#include <dlfcn.h>\n",
+            "int host_value(void) { return 5; }\n",
+            "int main(int argc, char** argv) {\n",
+            "    void* plugin = dlopen(argv[1], RTLD_NOW);\n",
+            "    if (!plugin) return 1;\n",
+            "    int (*plugin_value)(void) = (int (*)(void)) dlsym(plugin, \"plugin_value\");\n",
+            "    return plugin_value ? plugin_value() : 2;\n",
+            "}\n"
+        )).unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        project.add_target(AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+            cflags: Flags::new(vec!["-DPLUGIN_PUBLIC".to_string()], Vec::new()),
+            linker_flags: vec!["-lm".to_string()],
+            ..common::descriptor("Plugin", "plugin/*.c", vec![LibraryArtifactType::Module], &tmpdir)
+        }).unwrap().into())).unwrap();
+        project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            linker_flags: vec!["-rdynamic".to_string(), "-ldl".to_string()],
+            dependencies: vec![common::library_dependency(0, 0, LibraryArtifactType::Module)],
+            ..common::descriptor("Host", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.create_build_file().unwrap();
+        let build_file = fs::read_to_string(beaver.get_build_dir().unwrap().join("build.native.json")).unwrap();
+        let build_file: serde_json::Value = serde_json::from_str(&build_file).unwrap();
+        let edges = build_file["edges"].as_array().unwrap();
+        let edge = |output: &str| edges.iter()
+            .find(|edge| edge["outputs"].as_array().unwrap().iter().any(|out| out == output))
+            .unwrap_or_else(|| panic!("No edge for {}", output));
+
+        // The host is built after the plugin, without its flags
+        let host = edge(project_build_dir.join("artifacts/Host").to_str().unwrap());
+        assert!(host["implicit"].as_array().unwrap().iter().any(|dep| dep == "MyProject:Plugin:module"));
+        assert!(!host["options"]["linkerFlags"].as_str().unwrap().contains("-lm"));
+        let host_object = edge(project_build_dir.join("objects/Host/static/main.c.o").to_str().unwrap());
+        assert!(!host_object["options"]["cflags"].as_str().unwrap().contains("PLUGIN_PUBLIC"));
+
+        beaver.build_all(&[TargetRef { project: 0, target: 1 }]).unwrap();
+
+        // Modules don't have a `lib` prefix
+        let plugin = project_build_dir.join("artifacts/Plugin.so");
+        assert!(plugin.exists());
+        let status = std::process::Command::new(project_build_dir.join("artifacts/Host")).arg(&plugin).status().unwrap();
+        assert_eq!(status.code(), Some(6));
+    });
+}
+
+/// Versioned dynamic libraries get a soname and a chain of symbolic links
+#[cfg(target_os = "linux")]
+#[test]
+fn dynlib_version() {
+    use std::path::Path;
+    use beaver::target::Version;
+
+    common::isolated("dynlib_version", || {
+        let tmpdir = common::tmpdir("dynlib_version");
+        fs::write(tmpdir.join("Foo.c"), "int foo(void) { return 3; }\n").unwrap();
+        fs::write(tmpdir.join("Bar.c"), "int bar(void) { return 4; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "int foo(void);\nint bar(void);\nint main(void) { return foo() + bar(); }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let artifacts = beaver.get_build_dir().unwrap().join("MyProject/artifacts");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let library = |name: &str| c::Library::new_desc(c::TargetDescriptor {
+            version: Some(Version::parse("1.2.3")),
+            ..common::descriptor(name, &format!("{}.c", name), vec![LibraryArtifactType::Dynlib], &tmpdir)
+        }).unwrap();
+        let mut bar = library("Bar");
+        bar.set_abi_version("7".to_string());
+        project.add_target(AnyTarget::Library(library("Foo").into())).unwrap();
+        project.add_target(AnyTarget::Library(bar.into())).unwrap();
+        project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![
+                common::library_dependency(0, 0, LibraryArtifactType::Dynlib),
+                common::library_dependency(0, 1, LibraryArtifactType::Dynlib)
+            ],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all(&[TargetRef { project: 0, target: 2 }]).unwrap();
+
+        assert!(artifacts.join("libFoo.so.1.2.3").symlink_metadata().unwrap().is_file());
+        assert_eq!(fs::read_link(artifacts.join("libFoo.so.1")).unwrap(), Path::new("libFoo.so.1.2.3"));
+        assert_eq!(fs::read_link(artifacts.join("libFoo.so")).unwrap(), Path::new("libFoo.so.1"));
+        assert_eq!(dynamic_entry(&artifacts.join("libFoo.so.1.2.3"), "SONAME").as_deref(), Some("libFoo.so.1"));
+
+        // The ABI version overrides the major version in the soname
+        assert_eq!(fs::read_link(artifacts.join("libBar.so.7")).unwrap(), Path::new("libBar.so.1.2.3"));
+        assert_eq!(dynamic_entry(&artifacts.join("libBar.so.1.2.3"), "SONAME").as_deref(), Some("libBar.so.7"));
+
+        // The executable loads the libraries by their soname
+        let status = std::process::Command::new(artifacts.join("Main"))
+            .env("LD_LIBRARY_PATH", &artifacts)
+            .status().unwrap();
+        assert_eq!(status.code(), Some(7));
+    });
+}
+
+/// Executables find the dynamic libraries they link against in the build directory and in the
+/// install prefix, without setting the library search path
+#[cfg(target_os = "linux")]
+#[test]
+fn rpath() {
+    use beaver::install::InstallOptions;
+
+    common::isolated("rpath", || {
+        let tmpdir = common::tmpdir("rpath");
+        fs::create_dir_all(tmpdir.join("other")).unwrap();
+        fs::write(tmpdir.join("Local.c"), "int local(void) { return 3; }\n").unwrap();
+        fs::write(tmpdir.join("other/Other.c"), "int other(void) { return 4; }\n").unwrap();
+        fs::write(tmpdir.join("main.c"), "int local(void);\nint other(void);\nint main(void) { return local() + other(); }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let library = |name: &str, dir: &std::path::Path| AnyTarget::Library(c::Library::new_desc(
+            common::descriptor(name, &format!("{}.c", name), vec![LibraryArtifactType::Dynlib], dir)
+        ).unwrap().into());
+
+        let other = common::project(&beaver, "Other", &tmpdir.join("other"));
+        other.add_target(library("Other", &tmpdir.join("other"))).unwrap();
+        beaver.add_project(other).unwrap();
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        project.add_target(library("Local", &tmpdir)).unwrap();
+        project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            linker_flags: vec!["-Wl,-rpath,/usr/lib/custom".to_string()],
+            dependencies: vec![
+                common::library_dependency(1, 0, LibraryArtifactType::Dynlib),
+                common::library_dependency(0, 0, LibraryArtifactType::Dynlib)
+            ],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        let main = TargetRef { project: 1, target: 1 };
+        beaver.build(main).unwrap();
+
+        // The rpaths are relative to the executable
+        let executable = beaver.get_build_dir().unwrap().join("MyProject/artifacts/Main");
+        let rpaths: Vec<String> = runpath(&executable).split(':').map(|rpath| rpath.trim_end_matches('/').to_string()).collect();
+        assert_eq!(rpaths, ["/usr/lib/custom", "$ORIGIN", "$ORIGIN/../../Other/artifacts"]);
+        let status = std::process::Command::new(&executable).env_remove("LD_LIBRARY_PATH").status().unwrap();
+        assert_eq!(status.code(), Some(7));
+
+        // Installing replaces the build rpaths, but keeps the ones passed by the user
+        let mut options = InstallOptions::new(tmpdir.join("prefix"));
+        beaver.install(&[TargetRef { project: 0, target: 0 }, TargetRef { project: 1, target: 0 }, main], &options).unwrap();
+        let installed = tmpdir.join("prefix/bin/Main");
+        assert_eq!(runpath(&installed), "/usr/lib/custom:$ORIGIN/../lib");
+        let status = std::process::Command::new(&installed).env_remove("LD_LIBRARY_PATH").status().unwrap();
+        assert_eq!(status.code(), Some(7));
+
+        // The install rpath can be configured
+        options.rpath = Some(tmpdir.join("prefix/lib").display().to_string());
+        beaver.install(&[main], &options).unwrap();
+        assert_eq!(runpath(&installed), format!("/usr/lib/custom:{}", tmpdir.join("prefix/lib").display()));
+        assert_eq!(std::process::Command::new(&installed).status().unwrap().code(), Some(7));
+    });
+}
+
+/// Only symbols marked with the export macro, or listed in `exports`, are exported. The static
+/// library and objects, and their consumers, define `<NAME>_STATIC` instead.
+#[cfg(target_os = "linux")]
+#[test]
+fn symbol_visibility() {
+    common::isolated("symbol_visibility", || {
+        let tmpdir = common::tmpdir("symbol_visibility");
+        fs::write(tmpdir.join("Hidden.c"), concat!(
+            "#include <hidden_export.h>\n",
+            "#if defined(HIDDEN_BUILDING) == defined(HIDDEN_STATIC)\n#error\n#endif\n",
+            "HIDDEN_API int visible(void) { return 5; }\n",
+            "int internal(void) { return 1; }\n"
+        )).unwrap();
+        fs::write(tmpdir.join("Listed.c"), "int listed(void) { return 2; }\nint unlisted(void) { return 3; }\n").unwrap();
+        fs::write(tmpdir.join("Defined.c"), "int defined(void) { return 4; }\nint undefined(void) { return 5; }\n").unwrap();
+        fs::write(tmpdir.join("exports.def"), "LIBRARY Defined\nEXPORTS\n    defined @1 ; the only export\n").unwrap();
+        fs::write(tmpdir.join("Main.c"), concat!(
+            "#include <hidden_export.h>\n",
+            "#if defined(HIDDEN_BUILDING) || defined(HIDDEN_STATIC)\n#error\n#endif\n",
+            "HIDDEN_API int visible(void);\n",
+            "int main(void) { return visible(); }\n"
+        )).unwrap();
+        // Consumers of the static library and objects don't import the symbols
+        for name in ["Static", "Objects"] {
+            fs::write(tmpdir.join(format!("{}.c", name)), concat!(
+                "#include <hidden_export.h>\n",
+                "#if defined(HIDDEN_BUILDING) || !defined(HIDDEN_STATIC)\n#error\n#endif\n",
+                "HIDDEN_API int visible(void);\n",
+                "int main(void) { return visible(); }\n"
+            )).unwrap();
+        }
+
+        let beaver = common::beaver(&tmpdir);
+        let artifacts = beaver.get_build_dir().unwrap().join("MyProject/artifacts");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let library = |name: &str| common::descriptor(name, &format!("{}.c", name), vec![LibraryArtifactType::Dynlib, LibraryArtifactType::Staticlib, LibraryArtifactType::Objects], &tmpdir);
+        let hidden = c::Library::new_desc(c::TargetDescriptor {
+            settings: vec![c::Setting::HiddenVisibility],
+            ..library("Hidden")
+        }).unwrap();
+        let mut listed = c::Library::new_desc(library("Listed")).unwrap();
+        listed.set_exports(c::Exports::Symbols(vec!["listed".to_string()]));
+        let mut defined = c::Library::new_desc(library("Defined")).unwrap();
+        defined.set_exports(c::Exports::File("exports.def".into()));
+        let executable = |name: &str, artifact| AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![common::library_dependency(0, 0, artifact)],
+            ..common::descriptor(name, &format!("{}.c", name), vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into());
+        project.add_target(AnyTarget::Library(hidden.into())).unwrap();
+        project.add_target(AnyTarget::Library(listed.into())).unwrap();
+        project.add_target(AnyTarget::Library(defined.into())).unwrap();
+        project.add_target(executable("Main", LibraryArtifactType::Dynlib)).unwrap();
+        project.add_target(executable("Static", LibraryArtifactType::Staticlib)).unwrap();
+        project.add_target(executable("Objects", LibraryArtifactType::Objects)).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all(&[
+            TargetRef { project: 0, target: 1 },
+            TargetRef { project: 0, target: 2 },
+            TargetRef { project: 0, target: 3 },
+            TargetRef { project: 0, target: 4 },
+            TargetRef { project: 0, target: 5 },
+        ]).unwrap();
+
+        let hidden = exported_symbols(&artifacts.join("libHidden.so"));
+        assert!(hidden.contains(&"visible".to_string()));
+        assert!(!hidden.contains(&"internal".to_string()));
+
+        let listed = exported_symbols(&artifacts.join("libListed.so"));
+        assert!(listed.contains(&"listed".to_string()));
+        assert!(!listed.contains(&"unlisted".to_string()));
+
+        let defined = exported_symbols(&artifacts.join("libDefined.so"));
+        assert!(defined.contains(&"defined".to_string()));
+        assert!(!defined.contains(&"undefined".to_string()));
+
+        assert_eq!(std::process::Command::new(artifacts.join("Main")).status().unwrap().code(), Some(5));
+        assert_eq!(std::process::Command::new(artifacts.join("Static")).status().unwrap().code(), Some(5));
+        assert_eq!(std::process::Command::new(artifacts.join("Objects")).status().unwrap().code(), Some(5));
+    });
+}
@@ -0,0 +1,27 @@
+use std::fs;
+
+use beaver::target::{Dependency, PkgconfigFlagOption, PkgconfigOption};
+
+/// Resolve a pkg-config dependency and its requirements without invoking pkgconf
+#[test]
+fn pkgconfig_dependency() {
+    let tmpdir = tempdir::TempDir::new("beaver").unwrap();
+    let dir = tmpdir.path();
+    fs::write(dir.join("mylib.pc"), "prefix=/opt/mylib\n\nName: mylib\nDescription: My library\nVersion: 2.4.1\nRequires: mydep >= 1.0\nLibs: -L${prefix}/lib -lmylib\nLibs.private: -lpthread\nCflags: -I${prefix}/include\n").unwrap();
+    fs::write(dir.join("mydep.pc"), "Name: mydep\nDescription: A dependency\nVersion: 1.3\nLibs: -lmydep\nCflags: -DMYDEP\n").unwrap();
+
+    let path = dir.to_str().unwrap();
+    let Dependency::PkgConfig { cflags, linker_flags, .. } = Dependency::pkgconfig("mylib", Some(">= 2.4"), &[PkgconfigOption::WithPath(path)], &[]).unwrap() else {
+        panic!("Expected a pkg-config dependency");
+    };
+    assert_eq!(cflags, vec!["-I/opt/mylib/include", "-DMYDEP"]);
+    assert_eq!(linker_flags, vec!["-L/opt/mylib/lib", "-lmylib", "-lmydep"]);
+
+    let Dependency::PkgConfig { linker_flags, .. } = Dependency::pkgconfig("mylib", None, &[PkgconfigOption::WithPath(path)], &[PkgconfigFlagOption::PreferStatic]).unwrap() else {
+        panic!("Expected a pkg-config dependency");
+    };
+    assert_eq!(linker_flags, vec!["-L/opt/mylib/lib", "-lmylib", "-lpthread", "-lmydep"]);
+
+    assert!(Dependency::pkgconfig("mylib", Some("!= 2.4.1"), &[PkgconfigOption::WithPath(path)], &[]).is_err());
+    assert!(Dependency::pkgconfig("mylib", Some("~> 2"), &[PkgconfigOption::WithPath(path)], &[]).is_err());
+}
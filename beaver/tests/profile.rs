@@ -0,0 +1,64 @@
+mod common;
+
+use std::{fs, sync::Arc};
+
+use beaver::backend::Backend;
+use beaver::target::ExecutableArtifactType;
+use beaver::traits::{AnyTarget, MutableProject};
+use beaver::{Beaver, BeaverError, OptimizationMode, Profile, target::c};
+use target_lexicon::Triple;
+
+/// Build with a profile defined by the script, in its own build directory
+#[test]
+fn custom_profile() {
+    let tmpdir = common::tmpdir("profile");
+    fs::write(tmpdir.join("main.c"), "#ifndef PROFILE_FLAG\n#error PROFILE_FLAG not defined\n#endif\n#ifndef NDEBUG\n#error NDEBUG not defined\n#endif\nint main(void) { return 0; }\n").unwrap();
+
+    // The profile is selected before the `Beaver` is shared
+    let mut beaver = Beaver::new(Some(false), OptimizationMode::Debug, false, false, Triple::host(), Backend::Native).unwrap();
+    assert!(matches!(beaver.select_profile("../escape"), Err(BeaverError::InvalidProfileName(_))));
+    beaver.select_profile("checked").unwrap();
+    let beaver = Arc::new(beaver);
+
+    assert!(matches!(beaver.opt_mode(), Err(BeaverError::UndefinedProfile(_))));
+    assert!(matches!(
+        beaver.define_profile(Profile::new("release".to_string(), OptimizationMode::Debug, Vec::new(), Vec::new())),
+        Err(BeaverError::ProfileShadowsOptimizationMode(_))
+    ));
+    // Profiles which aren't selected are ignored
+    beaver.define_profile(Profile::new("other".to_string(), OptimizationMode::Debug, Vec::new(), Vec::new())).unwrap();
+    beaver.define_profile(Profile::new(
+        "checked".to_string(),
+        OptimizationMode::RelWithDebInfo,
+        vec!["-DPROFILE_FLAG".to_string()],
+        Vec::new()
+    )).unwrap();
+    assert!(matches!(
+        beaver.define_profile(Profile::new("checked".to_string(), OptimizationMode::Debug, Vec::new(), Vec::new())),
+        Err(BeaverError::ProfileAlreadyDefined(_))
+    ));
+    assert_eq!(beaver.opt_mode().unwrap(), OptimizationMode::RelWithDebInfo);
+
+    beaver.set_build_dir(tmpdir.join("build")).unwrap();
+    assert_eq!(beaver.get_build_dir().unwrap(), tmpdir.join("build").join(Triple::host().to_string()).join("checked"));
+
+    let project = common::project(&beaver, "MyProject", &tmpdir);
+    let executable = c::Executable::new_desc(common::descriptor("main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)).unwrap();
+    project.add_target(AnyTarget::Executable(executable.into())).unwrap();
+    beaver.add_project(project).unwrap();
+
+    beaver.build_all_named(&["MyProject"]).unwrap();
+    assert!(beaver.get_build_dir().unwrap().join("MyProject/artifacts/main").exists());
+}
+
+#[test]
+fn optimization_mode_names() {
+    for mode in OptimizationMode::ALL {
+        assert_eq!(OptimizationMode::try_from(mode.to_string().as_str()).unwrap(), mode);
+    }
+    assert_eq!(OptimizationMode::try_from("RelWithDebInfo").unwrap(), OptimizationMode::RelWithDebInfo);
+    assert_eq!(OptimizationMode::MinSize.cmake_name(), "MinSizeRel");
+    assert_eq!(OptimizationMode::RelWithDebInfo.meson_buildtype(), "debugoptimized");
+    assert_eq!(OptimizationMode::Size.cargo_name(), "size");
+    assert_eq!(OptimizationMode::Fast.cargo_flags(), &["--release"]);
+}
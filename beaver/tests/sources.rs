@@ -0,0 +1,266 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+
+use beaver::BeaverError;
+use beaver::target::custom::{self, BuildCommand, GeneratedKind};
+use beaver::target::parameters::{Files, Flags};
+use beaver::target::{ExecutableArtifactType, Language, LibraryArtifactType, TargetRef, c};
+use beaver::traits::{AnyLibrary, AnyTarget, MutableProject, Project};
+
+/// Targets compiling the same sources with different flags each have their own objects
+#[test]
+fn object_dirs() {
+    common::isolated("object_dirs", || {
+        let tmpdir = common::tmpdir("object_dirs");
+        fs::create_dir_all(tmpdir.join("src")).unwrap();
+        fs::write(tmpdir.join("src/main.c"), "int main(void) { return VALUE; }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let executable = |name: &str, value: &str| AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            cflags: Flags::new(Vec::new(), vec![format!("-DVALUE={}", value)]),
+            ..common::descriptor(name, "src/*.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into());
+        project.add_target(executable("Zero", "0")).unwrap();
+        project.add_target(executable("One", "1")).unwrap();
+        project.add_target(AnyTarget::Library(c::Library::new_desc(c::TargetDescriptor {
+            cflags: Flags::new(Vec::new(), vec!["-DVALUE=2".to_string()]),
+            ..common::descriptor("Lib", "src/*.c", vec![LibraryArtifactType::Staticlib, LibraryArtifactType::Dynlib], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.build_all(&[
+            TargetRef { project: 0, target: 0 },
+            TargetRef { project: 0, target: 1 },
+            TargetRef { project: 0, target: 2 },
+        ]).unwrap();
+
+        assert!(project_build_dir.join("objects/Zero/static/src/main.c.o").exists());
+        assert!(project_build_dir.join("objects/One/static/src/main.c.o").exists());
+        assert!(std::process::Command::new(project_build_dir.join("artifacts/Zero")).status().unwrap().success());
+        assert_eq!(std::process::Command::new(project_build_dir.join("artifacts/One")).status().unwrap().code(), Some(1));
+
+        // The static and dynamic library are compiled with different export macros
+        assert!(project_build_dir.join("objects/Lib/static/src/main.c.o").exists());
+        assert!(project_build_dir.join("objects/Lib/dynamic/src/main.c.o").exists());
+    });
+}
+
+/// Sources are compiled as the language of their extension, and linked as the strongest language
+#[test]
+fn mixed_languages() {
+    common::isolated("mixed_languages", || {
+        let tmpdir = common::tmpdir("mixed_languages");
+        fs::create_dir_all(tmpdir.join("src")).unwrap();
+        fs::write(tmpdir.join("src/one.c"), "int one(void) { return 1; }\n").unwrap();
+        fs::write(tmpdir.join("src/vec.cpp"), "#include <vector>\nextern \"C\" int two(void) { std::vector<int> v{1, 1}; return (int)v.size(); }\n").unwrap();
+        fs::write(tmpdir.join("src/three.S"), "#define THREE 3\n.globl three\n.data\n.balign 4\nthree:\n.long THREE\n.section .note.GNU-stack,\"\",%progbits\n").unwrap();
+        fs::write(tmpdir.join("src/legacy.c"), "extern \"C\" int legacy(void) { return static_cast<int>(sizeof(int)); }\n").unwrap();
+        fs::write(tmpdir.join("src/five.s"), ".globl five\n.data\n.balign 4\nfive:\n.long 5\n.section .note.GNU-stack,\"\",%progbits\n").unwrap();
+        fs::write(tmpdir.join("main.c"), concat!(
+            "int one(void);\nint two(void);\nint legacy(void);\nextern int three;\nextern int five;\n",
+            "int main(void) { return one() + two() + three + legacy() + five; }\n"
+        )).unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let mut library = c::Library::new_desc(c::TargetDescriptor {
+            settings: vec![c::Setting::parse("cstd=11").unwrap(), c::Setting::parse("cxxstd=17").unwrap()],
+            ..common::descriptor("Mixed", "src/*", vec![LibraryArtifactType::Staticlib, LibraryArtifactType::Dynlib], &tmpdir)
+        }).unwrap();
+        library.set_source_languages(vec![(Files::from_pat("src/legacy.c", &tmpdir).unwrap(), Language::CXX)]).unwrap();
+        assert!(library.set_source_languages(vec![(Files::from_pat("*.rs", &tmpdir).unwrap(), Language::Rust)]).is_err());
+        project.add_target(AnyTarget::Library(library.into())).unwrap();
+        project.add_target(AnyTarget::Executable(c::Executable::new_desc(c::TargetDescriptor {
+            dependencies: vec![common::library_dependency(0, 0, LibraryArtifactType::Staticlib)],
+            ..common::descriptor("Main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap().into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        beaver.create_build_file().unwrap();
+        let build_file = fs::read_to_string(beaver.get_build_dir().unwrap().join("build.native.json")).unwrap();
+        let build_file: serde_json::Value = serde_json::from_str(&build_file).unwrap();
+        let edges = build_file["edges"].as_array().unwrap();
+        let edge = |output: &str| edges.iter()
+            .find(|edge| edge["outputs"].as_array().unwrap().iter().any(|out| out == project_build_dir.join(output).to_str().unwrap()))
+            .unwrap_or_else(|| panic!("No edge for {}", output));
+
+        // Each source is compiled with the rule and standard of its language
+        let objects = "objects/Mixed/static/src";
+        for (source, rule) in [("one.c", "cc"), ("vec.cpp", "cxx"), ("three.S", "asm"), ("five.s", "asm"), ("legacy.c", "cxx")] {
+            let object = edge(&format!("{}/{}.o", objects, source));
+            assert_eq!(object["rule"], rule, "{}", source);
+            let cflags = object["options"]["cflags"].as_str().unwrap();
+            assert_eq!(cflags.contains("-std=c11"), rule == "cc", "{}", source);
+            assert_eq!(cflags.contains("-std=c++17"), rule == "cxx", "{}", source);
+        }
+        assert_eq!(edge("artifacts/libMixed.so")["rule"], "linkxx");
+        // A C executable linking the library links the C++ standard library
+        assert_eq!(edge("artifacts/Main")["rule"], "link");
+        assert!(edge("artifacts/Main")["options"]["linkerFlags"].as_str().unwrap().contains("-lstdc++"));
+
+        let compile_commands = fs::read_to_string(beaver.get_build_dir().unwrap().join("compile_commands.json")).unwrap();
+        let compile_commands: serde_json::Value = serde_json::from_str(&compile_commands).unwrap();
+        let compiler = |source: &str| compile_commands.as_array().unwrap().iter()
+            .find(|entry| entry["file"] == tmpdir.join("src").join(source).to_str().unwrap())
+            .map(|entry| entry["arguments"][0].clone())
+            .unwrap();
+        assert_eq!(compiler("legacy.c"), compiler("vec.cpp"));
+        assert_ne!(compiler("one.c"), compiler("vec.cpp"));
+
+        beaver.build_all(&[TargetRef { project: 0, target: 0 }, TargetRef { project: 0, target: 1 }]).unwrap();
+
+        let status = std::process::Command::new(project_build_dir.join("artifacts/Main")).status().unwrap();
+        assert_eq!(status.code(), Some(15));
+    });
+}
+
+/// Sources matching a pattern are compiled with additional cflags
+#[test]
+fn source_flags() {
+    common::isolated("source_flags", || {
+        let tmpdir = common::tmpdir("source_flags");
+        fs::create_dir_all(tmpdir.join("src")).unwrap();
+        fs::create_dir_all(tmpdir.join("vendor")).unwrap();
+        fs::write(tmpdir.join("src/main.c"), "int hot(void);\nint vendored(void);\nint main(void) { return hot() + vendored(); }\n").unwrap();
+        fs::write(tmpdir.join("src/hot.c"), "int hot(void) { return HOT; }\n").unwrap();
+        // Fails with -Werror
+        fs::write(tmpdir.join("vendor/vendored.c"), "int vendored(void) { int unused; return 2; }\n").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let build_dir = beaver.get_build_dir().unwrap().to_path_buf();
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let mut executable = c::Executable::new_desc(c::TargetDescriptor {
+            sources: Files::from_pats(&["src/*.c", "vendor/*.c"], &tmpdir).unwrap(),
+            cflags: Flags::new(Vec::new(), vec!["-Wall".to_string(), "-Werror".to_string()]),
+            ..common::descriptor("Main", "src/*.c", vec![ExecutableArtifactType::Executable], &tmpdir)
+        }).unwrap();
+        executable.set_source_flags(vec![
+            (Files::from_pat("vendor/*.c", &tmpdir).unwrap(), vec!["-w".to_string()]),
+            (Files::from_pat("src/hot.c", &tmpdir).unwrap(), vec!["-DHOT=3".to_string()]),
+            (Files::from_pat("src/*.c", &tmpdir).unwrap(), vec!["-DPATTERN".to_string()]),
+        ]);
+        project.add_target(AnyTarget::Executable(executable.into())).unwrap();
+        beaver.add_project(project).unwrap();
+
+        // The flags are added to the command compiling the object, so it is rebuilt when they change
+        beaver.create_build_file().unwrap();
+        let build_file = fs::read_to_string(build_dir.join("build.native.json")).unwrap();
+        let build_file: serde_json::Value = serde_json::from_str(&build_file).unwrap();
+        let cflags = |object: &str| build_file["edges"].as_array().unwrap().iter()
+            .find(|edge| edge["outputs"][0] == build_dir.join("MyProject/objects/Main/static").join(object).to_str().unwrap())
+            .map(|edge| edge["options"]["cflags"].as_str().unwrap().to_string())
+            .unwrap();
+        assert!(cflags("vendor/vendored.c.o").ends_with("\"-w\" "));
+        assert!(cflags("src/hot.c.o").ends_with("\"-DHOT=3\" \"-DPATTERN\" "));
+        assert!(cflags("src/main.c.o").ends_with("\"-Werror\" \"-DPATTERN\" "));
+
+        // The flags are part of the compile commands of the matching sources only
+        let compile_commands = fs::read_to_string(build_dir.join("compile_commands.json")).unwrap();
+        let compile_commands: serde_json::Value = serde_json::from_str(&compile_commands).unwrap();
+        let arguments = |source: &str| compile_commands.as_array().unwrap().iter()
+            .find(|entry| entry["file"] == tmpdir.join(source).to_str().unwrap())
+            .map(|entry| entry["arguments"].as_array().unwrap().clone())
+            .unwrap();
+        assert!(arguments("vendor/vendored.c").contains(&serde_json::json!("-w")));
+        assert!(arguments("src/hot.c").contains(&serde_json::json!("-DHOT=3")));
+        assert!(!arguments("src/main.c").iter().any(|arg| arg == "-w" || arg == "-DHOT=3"));
+        assert!(arguments("src/main.c").contains(&serde_json::json!("-DPATTERN")));
+
+        beaver.build_all(&[TargetRef { project: 0, target: 0 }]).unwrap();
+        let executable = build_dir.join("MyProject/artifacts/Main");
+        assert_eq!(std::process::Command::new(&executable).status().unwrap().code(), Some(5));
+    });
+}
+
+/// Sources and include directories generated by a custom target are compiled by a C target,
+/// whose compile steps depend on the generator
+#[test]
+fn generated_sources() {
+    common::isolated("generated_sources", || {
+        let tmpdir = common::tmpdir("generated_sources");
+        fs::create_dir_all(tmpdir.join("src")).unwrap();
+        fs::write(tmpdir.join("src/main.c"), "#include <proto.h>\nint main(void) { return proto(); }\n").unwrap();
+        fs::write(tmpdir.join("message.proto"), "").unwrap();
+
+        let beaver = common::beaver(&tmpdir);
+        let project_build_dir = beaver.get_build_dir().unwrap().join("MyProject");
+
+        let project = common::project(&beaver, "MyProject", &tmpdir);
+        let generated_source = project_build_dir.join("proto/proto.c");
+        let generated_headers = project_build_dir.join("proto/include");
+        project.add_target(AnyTarget::Library(AnyLibrary::Custom(custom::Library::new(
+            "Proto".to_string(),
+            None, None, None, None,
+            Language::C,
+            Vec::new(),
+            Some(Files::from_pat("*.proto", &tmpdir).unwrap()),
+            HashMap::new(),
+            vec![generated_source.clone()],
+            vec![generated_headers.clone()],
+            Vec::new(),
+            Vec::new(),
+            BuildCommand { run: Box::new(|_| Ok(())), definition: "proto".to_string() },
+        )))).unwrap();
+        project.add_target(AnyTarget::Library(AnyLibrary::Custom(custom::Library::new(
+            "Prebuilt".to_string(),
+            None, None, None, None,
+            Language::C,
+            Vec::new(),
+            None,
+            HashMap::from([(LibraryArtifactType::Staticlib, tmpdir.join("libprebuilt.a"))]),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            BuildCommand { run: Box::new(|_| Ok(())), definition: "prebuilt".to_string() },
+        )))).unwrap();
+        beaver.add_project(project).unwrap();
+
+        let proto = beaver.parse_target_ref("Proto").unwrap();
+        assert_eq!(beaver.generated_files(&proto, GeneratedKind::Sources).unwrap(), vec![generated_source.clone()]);
+        assert_eq!(beaver.generated_files(&proto, GeneratedKind::Headers).unwrap(), vec![generated_headers.clone()]);
+        let prebuilt = beaver.parse_target_ref("Prebuilt").unwrap();
+        assert!(matches!(beaver.generated_files(&prebuilt, GeneratedKind::Sources), Err(BeaverError::NoGeneratedFiles { .. })));
+
+        let mut descriptor = common::descriptor("Main", "src/*.c", vec![ExecutableArtifactType::Executable], &tmpdir);
+        descriptor.sources.extend_generated(beaver.generated_files(&proto, GeneratedKind::Sources).unwrap());
+        descriptor.headers.extend_generated(beaver.generated_files(&proto, GeneratedKind::Headers).unwrap(), false);
+        let executable = c::Executable::new_desc(descriptor).unwrap();
+        beaver.with_current_project_mut(|project| {
+            project.as_mutable().unwrap().add_target(AnyTarget::Executable(executable.into()))
+        }).unwrap();
+
+        beaver.create_build_file().unwrap();
+        let build_file = fs::read_to_string(beaver.get_build_dir().unwrap().join("build.native.json")).unwrap();
+        let build_file: serde_json::Value = serde_json::from_str(&build_file).unwrap();
+        let edges = build_file["edges"].as_array().unwrap();
+        let edge = |output: &str| edges.iter()
+            .find(|edge| edge["outputs"].as_array().unwrap().iter().any(|out| out == output))
+            .unwrap_or_else(|| panic!("No edge for {}", output));
+        let generated_source = generated_source.to_str().unwrap();
+        let generated_headers = generated_headers.to_str().unwrap();
+
+        // The generator only runs when its inputs change
+        let generator = edge(generated_source);
+        assert_eq!(generator["rule"], "custom_command");
+        assert_eq!(generator["always"], false);
+        // The include directory is a node depending on the generator
+        assert_eq!(edge(generated_headers)["inputs"], serde_json::json!(["MyProject:Proto"]));
+
+        let main_object = project_build_dir.join("objects/Main/static/src/main.c.o");
+        let main_object = edge(main_object.to_str().unwrap());
+        assert!(main_object["implicit"].as_array().unwrap().iter().any(|dep| dep == generated_headers));
+        assert!(main_object["options"]["cflags"].as_str().unwrap().contains(&format!("-I{}", generated_headers)));
+
+        let generated_object = project_build_dir.join("objects/Main/static/generated/proto/proto.c.o");
+        assert_eq!(edge(generated_object.to_str().unwrap())["inputs"], serde_json::json!([generated_source]));
+    });
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+use std::time::Duration;
+
+use beaver::BeaverError;
+use beaver::target::{ArtifactType, ExecutableArtifactType, c};
+use beaver::testing::{TestOptions, TestSettings};
+use beaver::traits::{AnyTarget, MutableProject, Project, Target};
+use target_lexicon::Triple;
+
+fn test_target(name: &str, dir: &std::path::Path, test: TestSettings) -> c::Executable {
+    c::Executable::new_test_desc(
+        common::descriptor(name, &format!("{}.c", name), vec![ExecutableArtifactType::Executable], dir),
+        test
+    ).unwrap()
+}
+
+/// Test targets aren't built with the project, but are built and run by `Beaver::test`
+#[test]
+fn test_command() {
+    let tmpdir = common::tmpdir("test_command");
+    fs::write(tmpdir.join("main.c"), "int main(void) { return 0; }\n").unwrap();
+    fs::write(tmpdir.join("passes.c"), "#include <string.h>\nint main(int argc, char** argv) { return argc == 2 && strcmp(argv[1], \"arg\") == 0 ? 0 : 1; }\n").unwrap();
+    fs::write(tmpdir.join("fails.c"), "#include <stdio.h>\nint main(void) { printf(\"expected 1 & got 2\\n\"); return 3; }\n").unwrap();
+    fs::write(tmpdir.join("hangs.c"), "#include <unistd.h>\nint main(void) { sleep(10); return 0; }\n").unwrap();
+
+    let beaver = common::beaver(&tmpdir);
+    let project = common::project(&beaver, "MyProject", &tmpdir);
+    let executable = c::Executable::new_desc(common::descriptor("main", "main.c", vec![ExecutableArtifactType::Executable], &tmpdir)).unwrap();
+    let passes = test_target("passes", &tmpdir, TestSettings { args: vec!["arg".to_string()], timeout: None });
+    let fails = test_target("fails", &tmpdir, TestSettings::default());
+    let hangs = test_target("hangs", &tmpdir, TestSettings { args: Vec::new(), timeout: Some(Duration::from_millis(200)) });
+    let passes_file = passes.artifact_file(project.build_dir(), ArtifactType::Executable(ExecutableArtifactType::Executable), &Triple::host()).unwrap();
+    project.add_target(AnyTarget::Executable(executable.into())).unwrap();
+    project.add_target(AnyTarget::Executable(passes.into())).unwrap();
+    project.add_target(AnyTarget::Executable(fails.into())).unwrap();
+    project.add_target(AnyTarget::Executable(hangs.into())).unwrap();
+    beaver.add_project(project).unwrap();
+
+    // The project doesn't include its tests
+    beaver.build_all_named(&["MyProject"]).unwrap();
+    assert!(!passes_file.exists());
+    assert!(beaver.with_project(0, |project| project.default_executable()).is_ok());
+
+    let junit = tmpdir.join("junit.xml");
+    let options = TestOptions {
+        filter: Some("MyProject:".to_string()),
+        jobs: 2,
+        timeout: Some(Duration::from_secs(60)),
+        junit: Some(junit.clone()),
+    };
+    match beaver.test(&options) {
+        Err(BeaverError::TestsFailed { failed, total }) => {
+            assert_eq!(failed, 2);
+            assert_eq!(total, 3);
+        },
+        result => panic!("Expected tests to fail, got {:?}", result)
+    }
+    assert!(passes_file.exists());
+
+    let report = fs::read_to_string(&junit).unwrap();
+    assert!(report.contains(r#"<testsuite name="MyProject" tests="3" failures="2" errors="0""#), "{}", report);
+    assert!(report.contains(r#"<failure message="exit code 3"/>"#), "{}", report);
+    assert!(report.contains("<system-out>expected 1 &amp; got 2\n</system-out>"), "{}", report);
+    assert!(report.contains(r#"<failure message="exceeded the timeout of 0.2s"/>"#), "{}", report);
+
+    let options = TestOptions { filter: Some("passes".to_string()), ..TestOptions::default() };
+    beaver.test(&options).unwrap();
+}
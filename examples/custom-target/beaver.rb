@@ -6,6 +6,7 @@ Custom::Library(
   name: "SomeLibrary",
   language: :c,
   cflags: ["-std=c11", "-DHAVE_SOME_LIB"],
+  inputs: "lib.c",
   artifacts: {
     staticlib: "build/libsomelib.a"
   },
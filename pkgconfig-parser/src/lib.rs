@@ -4,13 +4,24 @@ use std::collections::HashMap;
 use regex::{Captures, Regex};
 use log::*;
 
-#[derive(Default, Debug)]
+mod version;
+pub use version::*;
+mod resolver;
+pub use resolver::*;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct PkgConfig<'a> {
+    /// Variables in the order they were defined. Values are stored unexpanded.
+    variables: Vec<(Cow<'a, str>, Cow<'a, str>)>,
     name: Option<Cow<'a, str>>,
     description: Option<Cow<'a, str>>,
+    url: Option<Cow<'a, str>>,
     version: Option<Cow<'a, str>>,
     requires: Option<Cow<'a, str>>,
+    requires_private: Option<Cow<'a, str>>,
+    conflicts: Option<Cow<'a, str>>,
     libs: Option<Cow<'a, str>>,
+    libs_private: Option<Cow<'a, str>>,
     cflags: Option<Cow<'a, str>>
 }
 
@@ -22,18 +33,25 @@ fn get_capture<'a, 'b>(captures: &'b Captures<'a>, capture_name: &str, str: &'a
 
 impl<'a> PkgConfig<'a> {
     pub fn parse(file_contents: &'a str) -> crate::Result<PkgConfig<'a>> {
+        Self::parse_with_variables(file_contents, &[])
+    }
+
+    /// Parse a pkg-config file, with `predefined` variables which aren't defined in the file
+    /// itself (e.g. `pcfiledir`)
+    pub fn parse_with_variables(file_contents: &'a str, predefined: &[(&'a str, &'a str)]) -> crate::Result<PkgConfig<'a>> {
         let mut pkg_config = PkgConfig::default();
 
-        let variable_regex = Regex::new(r"(?<variable_name>\w+)=(?<variable_value>.*)").unwrap();
-        let param_regex = Regex::new(r"(?<param_name>\w+):\s*(?<param_value>.*)").unwrap();
+        let variable_regex = Regex::new(r"^(?<variable_name>\w+)=(?<variable_value>.*)").unwrap();
+        let param_regex = Regex::new(r"^(?<param_name>[\w.]+):\s*(?<param_value>.*)").unwrap();
 
-        let mut variables = HashMap::new();
+        let mut variables: HashMap<&str, &str> = predefined.iter().copied().collect();
         for line in file_contents.split("\n") {
             if let Some(captures) = variable_regex.captures(line) {
                 let var_name = get_capture(&captures, "variable_name", line);
                 let var_val = get_capture(&captures, "variable_value", line);
 
                 variables.insert(var_name, var_val);
+                pkg_config.variables.push((Cow::Borrowed(var_name), Cow::Borrowed(var_val)));
             } else if let Some(captures) = param_regex.captures(line) {
                 let param_name = get_capture(&captures, "param_name", line);
                 let param_value = get_capture(&captures, "param_value", line);
@@ -41,10 +59,14 @@ impl<'a> PkgConfig<'a> {
                 match param_name {
                     "Name" => pkg_config.name = Some(Self::parse_value(param_value, &variables)?),
                     "Description" => pkg_config.description = Some(Self::parse_value(param_value, &variables)?),
+                    "URL" => pkg_config.url = Some(Self::parse_value(param_value, &variables)?),
                     "Version" => pkg_config.version = Some(Self::parse_value(param_value, &variables)?),
                     "Libs" => pkg_config.libs = Some(Self::parse_value(param_value, &variables)?),
-                    "Cflags" => pkg_config.cflags = Some(Self::parse_value(param_value, &variables)?),
+                    "Libs.private" => pkg_config.libs_private = Some(Self::parse_value(param_value, &variables)?),
+                    "Cflags" | "CFlags" => pkg_config.cflags = Some(Self::parse_value(param_value, &variables)?),
                     "Requires" => pkg_config.requires = Some(Self::parse_value(param_value, &variables)?),
+                    "Requires.private" => pkg_config.requires_private = Some(Self::parse_value(param_value, &variables)?),
+                    "Conflicts" => pkg_config.conflicts = Some(Self::parse_value(param_value, &variables)?),
                     _ => {
                         info!("Unparsed pkg-config parameter '{}: {}'", param_name, param_value);
                     }
@@ -83,6 +105,10 @@ impl<'a> PkgConfig<'a> {
 
 // Accessors //
 impl<'a> PkgConfig<'a> {
+    pub fn variables(&self) -> &[(Cow<'a, str>, Cow<'a, str>)] {
+        &self.variables
+    }
+
     pub fn name(&self) -> &Option<Cow<'a, str>> {
         &self.name
     }
@@ -91,6 +117,10 @@ impl<'a> PkgConfig<'a> {
         &self.description
     }
 
+    pub fn url(&self) -> &Option<Cow<'a, str>> {
+        &self.url
+    }
+
     pub fn version(&self) -> &Option<Cow<'a, str>> {
         &self.version
     }
@@ -99,15 +129,107 @@ impl<'a> PkgConfig<'a> {
         &self.requires
     }
 
+    pub fn requires_private(&self) -> &Option<Cow<'a, str>> {
+        &self.requires_private
+    }
+
+    pub fn conflicts(&self) -> &Option<Cow<'a, str>> {
+        &self.conflicts
+    }
+
     pub fn libs(&self) -> &Option<Cow<'a, str>> {
         &self.libs
     }
 
+    pub fn libs_private(&self) -> &Option<Cow<'a, str>> {
+        &self.libs_private
+    }
+
     pub fn cflags(&self) -> &Option<Cow<'a, str>> {
         &self.cflags
     }
 }
 
+// Builder //
+impl<'a> PkgConfig<'a> {
+    pub fn new(name: impl Into<Cow<'a, str>>, description: impl Into<Cow<'a, str>>, version: impl Into<Cow<'a, str>>) -> PkgConfig<'a> {
+        PkgConfig {
+            name: Some(name.into()),
+            description: Some(description.into()),
+            version: Some(version.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Define a variable, which can be referenced in values as `${name}`
+    pub fn add_variable(&mut self, name: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) {
+        self.variables.push((name.into(), value.into()));
+    }
+
+    pub fn set_url(&mut self, url: impl Into<Cow<'a, str>>) {
+        self.url = Some(url.into());
+    }
+
+    pub fn set_requires(&mut self, requires: impl Into<Cow<'a, str>>) {
+        self.requires = Some(requires.into());
+    }
+
+    pub fn set_requires_private(&mut self, requires: impl Into<Cow<'a, str>>) {
+        self.requires_private = Some(requires.into());
+    }
+
+    pub fn set_conflicts(&mut self, conflicts: impl Into<Cow<'a, str>>) {
+        self.conflicts = Some(conflicts.into());
+    }
+
+    pub fn set_libs(&mut self, libs: impl Into<Cow<'a, str>>) {
+        self.libs = Some(libs.into());
+    }
+
+    pub fn set_libs_private(&mut self, libs: impl Into<Cow<'a, str>>) {
+        self.libs_private = Some(libs.into());
+    }
+
+    pub fn set_cflags(&mut self, cflags: impl Into<Cow<'a, str>>) {
+        self.cflags = Some(cflags.into());
+    }
+}
+
+// Writing //
+
+/// Writes the pkg-config file. Values are written as they are stored, so variables in values
+/// written using the builder methods are expanded when the file is parsed.
+impl<'a> std::fmt::Display for PkgConfig<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.variables {
+            writeln!(f, "{}={}", name, value)?;
+        }
+        if self.variables.len() > 0 {
+            writeln!(f)?;
+        }
+
+        let fields = [
+            ("Name", &self.name),
+            ("Description", &self.description),
+            ("URL", &self.url),
+            ("Version", &self.version),
+            ("Requires", &self.requires),
+            ("Requires.private", &self.requires_private),
+            ("Conflicts", &self.conflicts),
+            ("Libs", &self.libs),
+            ("Libs.private", &self.libs_private),
+            ("Cflags", &self.cflags),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                writeln!(f, "{}: {}", field, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 // Errors //
 pub type Result<T> = std::result::Result<T, crate::Error>;
 
@@ -115,6 +237,24 @@ pub type Result<T> = std::result::Result<T, crate::Error>;
 pub enum Error {
     #[error("{0}")]
     UnknownVariable(String),
+    #[error("Malformed package requirement '{0}'")]
+    MalformedRequirement(String),
+    #[error("Malformed flags '{0}'")]
+    MalformedFlags(String),
+    #[error("Error reading {0:?}: {1}")]
+    Io(std::path::PathBuf, String),
+
+    // Resolving //
+    #[error("Package '{0}' was not found in the pkg-config search path")]
+    PackageNotFound(String),
+    #[error("Package '{0}', required by '{1}', was not found in the pkg-config search path")]
+    RequiredPackageNotFound(String, String),
+    #[error("Requested '{requirement}'{}, but version {found} was found", .required_by.as_ref().map(|name| format!(" (required by '{}')", name)).unwrap_or_default())]
+    VersionMismatch { requirement: String, found: String, required_by: Option<String> },
+    #[error("Package '{package}' conflicts with '{conflict}' (found version {found})")]
+    Conflict { package: String, conflict: String, found: String },
+    #[error("Dependency cycle in pkg-config files involving '{0}'")]
+    DependencyCycle(String),
 }
 
 #[cfg(test)]
@@ -143,4 +283,33 @@ Cflags: -I${prefix} -I${includedir}
         assert_eq!(pkg_config.libs.unwrap().as_ref(), "-L/usr/local/lib/mylib/lib -lmylib");
         assert_eq!(pkg_config.cflags.unwrap().as_ref(), "-I/usr/local/lib/mylib -I/usr/local/lib/mylib/include");
     }
+
+    #[test]
+    fn round_trip() {
+        let mut pkg_config = PkgConfig::new("mylib", "This is my library", "1.2.3");
+        pkg_config.set_url("https://example.com");
+        pkg_config.set_requires("zlib >= 1.2, libpng");
+        pkg_config.set_requires_private("libjpeg");
+        pkg_config.set_conflicts("oldlib < 2");
+        pkg_config.set_libs("-L/usr/lib -lmylib");
+        pkg_config.set_libs_private("-lm");
+        pkg_config.set_cflags("-I/usr/include/mylib -DMYLIB");
+
+        let written = pkg_config.to_string();
+        let parsed = PkgConfig::parse(&written).unwrap();
+        assert_eq!(parsed, pkg_config);
+        assert_eq!(parsed.to_string(), written);
+    }
+
+    #[test]
+    fn round_trip_variables() {
+        let contents = "prefix=/usr\nlibdir=${prefix}/lib\n\nName: mylib\nDescription: desc\nVersion: 1.0\nLibs: -L${libdir} -lmylib\n";
+        let parsed = PkgConfig::parse(contents).unwrap();
+        assert_eq!(parsed.variables().len(), 2);
+        assert_eq!(parsed.libs().as_ref().unwrap().as_ref(), "-L/usr/lib -lmylib");
+
+        let reparsed_contents = parsed.to_string();
+        let reparsed = PkgConfig::parse(&reparsed_contents).unwrap();
+        assert_eq!(reparsed, parsed);
+    }
 }
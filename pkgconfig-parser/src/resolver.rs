@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+use crate::{PkgConfig, Requirement};
+
+/// Finds pkg-config files and collects the flags of a package and its dependencies,
+/// without invoking `pkg-config`.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    search_path: Vec<PathBuf>,
+    prefer_static: bool,
+    /// Keep system include and library directories (e.g. `-I/usr/include`) in the output
+    allow_system_flags: bool,
+}
+
+/// The flags needed to use a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    pub name: String,
+    pub version: String,
+    pub cflags: Vec<String>,
+    pub libs: Vec<String>,
+}
+
+/// A parsed pkg-config file
+#[derive(Debug)]
+struct Package {
+    name: String,
+    version: String,
+    requires: Vec<Requirement>,
+    requires_private: Vec<Requirement>,
+    conflicts: Vec<Requirement>,
+    cflags: Vec<String>,
+    libs: Vec<String>,
+    libs_private: Vec<String>,
+}
+
+const SYSTEM_INCLUDE_DIRS: &[&str] = &["/usr/include"];
+const SYSTEM_LIBRARY_DIRS: &[&str] = &["/usr/lib", "/usr/lib64", "/lib", "/lib64"];
+
+impl Resolver {
+    /// A resolver using the same search path as pkg-config: the directories in `PKG_CONFIG_PATH`,
+    /// followed by `PKG_CONFIG_LIBDIR` or the default directories when it isn't set.
+    pub fn new() -> Resolver {
+        let mut search_path: Vec<PathBuf> = std::env::var_os("PKG_CONFIG_PATH")
+            .map(|paths| std::env::split_paths(&paths).filter(|path| !path.as_os_str().is_empty()).collect())
+            .unwrap_or_default();
+
+        match std::env::var_os("PKG_CONFIG_LIBDIR") {
+            Some(paths) => search_path.extend(std::env::split_paths(&paths).filter(|path| !path.as_os_str().is_empty())),
+            None => search_path.extend(Self::default_search_path()),
+        }
+
+        Resolver {
+            search_path,
+            prefer_static: false,
+            allow_system_flags: std::env::var_os("PKG_CONFIG_ALLOW_SYSTEM_CFLAGS").is_some(),
+        }
+    }
+
+    pub fn with_search_path(search_path: Vec<PathBuf>) -> Resolver {
+        Resolver { search_path, prefer_static: false, allow_system_flags: false }
+    }
+
+    fn default_search_path() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let prefixes = ["/usr/local", "/usr", "/opt/homebrew", "/opt/local"];
+        for prefix in prefixes {
+            let prefix = Path::new(prefix);
+            paths.push(prefix.join("lib/pkgconfig"));
+            paths.push(prefix.join("lib64/pkgconfig"));
+            // Multiarch directories (e.g. /usr/lib/x86_64-linux-gnu/pkgconfig)
+            if let Ok(entries) = std::fs::read_dir(prefix.join("lib")) {
+                let mut multiarch = entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.contains("-linux-")))
+                    .map(|path| path.join("pkgconfig"))
+                    .collect::<Vec<PathBuf>>();
+                multiarch.sort();
+                paths.extend(multiarch);
+            }
+            paths.push(prefix.join("share/pkgconfig"));
+        }
+        return paths.into_iter().filter(|path| path.is_dir()).collect();
+    }
+
+    /// Search `path` before all other directories
+    pub fn prepend_search_path(&mut self, path: impl Into<PathBuf>) {
+        self.search_path.insert(0, path.into());
+    }
+
+    pub fn search_path(&self) -> &[PathBuf] {
+        &self.search_path
+    }
+
+    /// Follow `Requires.private` and include `Libs.private` when collecting linker flags,
+    /// as needed when linking statically
+    pub fn set_prefer_static(&mut self, prefer_static: bool) {
+        self.prefer_static = prefer_static;
+    }
+
+    /// Find the pkg-config file of the package `name`
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        let filename = format!("{}.pc", name);
+        self.search_path.iter()
+            .map(|dir| dir.join(&filename))
+            .find(|file| file.is_file())
+    }
+
+    /// Resolve the package `name` and all its dependencies
+    pub fn resolve(&self, name: &str, constraint: Option<&str>) -> crate::Result<Resolved> {
+        let constraint = constraint.map(Requirement::parse_constraint).transpose()?;
+        self.resolve_requirement(&Requirement { name: name.to_string(), constraint })
+    }
+
+    pub fn resolve_requirement(&self, requirement: &Requirement) -> crate::Result<Resolved> {
+        let Some(file) = self.find(&requirement.name) else {
+            return Err(crate::Error::PackageNotFound(requirement.name.clone()));
+        };
+        self.resolve_file_requirement(&file, requirement)
+    }
+
+    /// Resolve the package described by the pkg-config file `file`, the dependencies of the package
+    /// are searched in the search path
+    pub fn resolve_file(&self, file: &Path) -> crate::Result<Resolved> {
+        let name = file.file_stem().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+        self.resolve_file_requirement(file, &Requirement { name, constraint: None })
+    }
+
+    fn resolve_file_requirement(&self, file: &Path, requirement: &Requirement) -> crate::Result<Resolved> {
+        let mut packages: HashMap<String, Package> = HashMap::new();
+        // Packages in the order their libraries should be linked: dependents before dependencies
+        let mut link_order: Vec<String> = Vec::new();
+        // Packages whose cflags are needed, in the order they were visited
+        let mut cflags_order: Vec<String> = Vec::new();
+
+        let root = self.load(file)?;
+        Self::check_version(&root, requirement, None)?;
+        let root_name = requirement.name.clone();
+        packages.insert(root_name.clone(), root);
+        self.visit(&root_name, true, &mut packages, &mut Vec::new(), &mut link_order, &mut cflags_order)?;
+        self.check_conflicts(&packages)?;
+
+        let mut cflags: Vec<String> = Vec::new();
+        for name in &cflags_order {
+            for flag in &packages[name].cflags {
+                if !cflags.contains(flag) && !self.is_system_flag(flag) {
+                    cflags.push(flag.clone());
+                }
+            }
+        }
+
+        // Keep the last occurrence of each library, so that it comes after all libraries using it
+        let mut libs: Vec<String> = Vec::new();
+        for name in link_order.iter().rev() {
+            let package = &packages[name];
+            let package_libs = package.libs.iter()
+                .chain(package.libs_private.iter().filter(|_| self.prefer_static));
+            for flag in package_libs.rev() {
+                if !libs.contains(flag) && !self.is_system_flag(flag) {
+                    libs.push(flag.clone());
+                }
+            }
+        }
+        libs.reverse();
+
+        let root = &packages[&root_name];
+        return Ok(Resolved { name: root.name.clone(), version: root.version.clone(), cflags, libs });
+    }
+
+    /// Visit the dependencies of `name` depth first. `link` is false for packages which are only
+    /// needed for their cflags (`Requires.private` when not linking statically).
+    fn visit(
+        &self,
+        name: &str,
+        link: bool,
+        packages: &mut HashMap<String, Package>,
+        stack: &mut Vec<String>,
+        link_order: &mut Vec<String>,
+        cflags_order: &mut Vec<String>
+    ) -> crate::Result<()> {
+        if stack.iter().any(|visiting| visiting == name) {
+            return Err(crate::Error::DependencyCycle(name.to_string()));
+        }
+        if !cflags_order.iter().any(|visited| visited == name) {
+            cflags_order.push(name.to_string());
+        }
+        if link {
+            // A package used by multiple dependents is linked after the last one
+            link_order.retain(|visited| visited != name);
+            link_order.push(name.to_string());
+        }
+
+        let requirements = {
+            let package = &packages[name];
+            package.requires.iter().map(|requirement| (requirement.clone(), link))
+                .chain(package.requires_private.iter().map(|requirement| (requirement.clone(), link && self.prefer_static)))
+                .collect::<Vec<(Requirement, bool)>>()
+        };
+
+        stack.push(name.to_string());
+        for (requirement, link) in requirements {
+            match packages.get(&requirement.name) {
+                Some(package) => Self::check_version(package, &requirement, Some(name))?,
+                None => {
+                    let Some(file) = self.find(&requirement.name) else {
+                        return Err(crate::Error::RequiredPackageNotFound(requirement.name.clone(), name.to_string()));
+                    };
+                    let package = self.load(&file)?;
+                    Self::check_version(&package, &requirement, Some(name))?;
+                    packages.insert(requirement.name.clone(), package);
+                }
+            }
+            self.visit(&requirement.name, link, packages, stack, link_order, cflags_order)?;
+        }
+        stack.pop();
+
+        return Ok(());
+    }
+
+    fn check_version(package: &Package, requirement: &Requirement, required_by: Option<&str>) -> crate::Result<()> {
+        if requirement.matches(&package.version) {
+            return Ok(());
+        }
+        return Err(crate::Error::VersionMismatch {
+            requirement: requirement.to_string(),
+            found: package.version.clone(),
+            required_by: required_by.map(|name| name.to_string()),
+        });
+    }
+
+    fn check_conflicts(&self, packages: &HashMap<String, Package>) -> crate::Result<()> {
+        for (name, package) in packages {
+            for conflict in &package.conflicts {
+                let Some(conflicting) = packages.get(&conflict.name) else { continue };
+                if conflict.matches(&conflicting.version) {
+                    return Err(crate::Error::Conflict {
+                        package: name.clone(),
+                        conflict: conflict.to_string(),
+                        found: conflicting.version.clone(),
+                    });
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn is_system_flag(&self, flag: &str) -> bool {
+        if self.allow_system_flags {
+            return false;
+        }
+        if let Some(dir) = flag.strip_prefix("-I") {
+            return SYSTEM_INCLUDE_DIRS.contains(&dir.trim_end_matches('/'));
+        }
+        if let Some(dir) = flag.strip_prefix("-L") {
+            return SYSTEM_LIBRARY_DIRS.contains(&dir.trim_end_matches('/'));
+        }
+        return false;
+    }
+
+    fn load(&self, file: &Path) -> crate::Result<Package> {
+        trace!("Loading pkg-config file {:?}", file);
+        let contents = std::fs::read_to_string(file)
+            .map_err(|err| crate::Error::Io(file.to_path_buf(), err.to_string()))?;
+        let pcfiledir = file.parent().and_then(|dir| dir.to_str()).unwrap_or_default();
+        let pkg_config = PkgConfig::parse_with_variables(&contents, &[("pcfiledir", pcfiledir)])?;
+
+        let split = |flags: &Option<std::borrow::Cow<str>>| -> crate::Result<Vec<String>> {
+            match flags {
+                Some(flags) => shlex::split(flags).ok_or_else(|| crate::Error::MalformedFlags(flags.to_string())),
+                None => Ok(Vec::new())
+            }
+        };
+        let requirements = |requirements: &Option<std::borrow::Cow<str>>| -> crate::Result<Vec<Requirement>> {
+            match requirements {
+                Some(requirements) => Requirement::parse_list(requirements),
+                None => Ok(Vec::new())
+            }
+        };
+
+        Ok(Package {
+            name: pkg_config.name().as_ref().map(|name| name.to_string()).unwrap_or_default(),
+            version: pkg_config.version().as_ref().map(|version| version.to_string()).unwrap_or_default(),
+            requires: requirements(pkg_config.requires())?,
+            requires_private: requirements(pkg_config.requires_private())?,
+            conflicts: requirements(pkg_config.conflicts())?,
+            cflags: split(pkg_config.cflags())?,
+            libs: split(pkg_config.libs())?,
+            libs_private: split(pkg_config.libs_private())?,
+        })
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::Resolver;
+
+    fn search_path(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pkgconfig-parser-tests-{}-{}", name, std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            fs::write(dir.join(format!("{}.pc", name)), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn resolve() {
+        let dir = search_path("resolve", &[
+            ("a", "prefix=/opt/a\nName: a\nDescription: a\nVersion: 1.2.0\nRequires: b >= 2, c\nRequires.private: d\nLibs: -L${prefix}/lib -la\nLibs.private: -lm\nCflags: -I${prefix}/include\n"),
+            ("b", "Name: b\nDescription: b\nVersion: 2.1\nRequires: c\nLibs: -lb\nCflags: -DB -I/usr/include\n"),
+            ("c", "Name: c\nDescription: c\nVersion: 0.1\nLibs: -L/usr/lib -lc_\nCflags: -I${pcfiledir}/include\n"),
+            ("d", "Name: d\nDescription: d\nVersion: 3\nLibs: -ld\nCflags: -DD\n"),
+        ]);
+
+        let mut resolver = Resolver::with_search_path(vec![dir.clone()]);
+        let resolved = resolver.resolve("a", Some(">=1.1")).unwrap();
+        assert_eq!(resolved.version, "1.2.0");
+        assert_eq!(resolved.cflags, vec!["-I/opt/a/include".to_string(), "-DB".to_string(), format!("-I{}/include", dir.display()), "-DD".to_string()]);
+        assert_eq!(resolved.libs, vec!["-L/opt/a/lib", "-la", "-lb", "-lc_"]);
+
+        resolver.set_prefer_static(true);
+        let resolved = resolver.resolve("a", None).unwrap();
+        assert_eq!(resolved.libs, vec!["-L/opt/a/lib", "-la", "-lm", "-lb", "-lc_", "-ld"]);
+
+        assert!(matches!(resolver.resolve("a", Some("< 1.2")), Err(crate::Error::VersionMismatch { .. })));
+        assert!(matches!(resolver.resolve("e", None), Err(crate::Error::PackageNotFound(_))));
+    }
+
+    #[test]
+    fn conflicts() {
+        let dir = search_path("conflicts", &[
+            ("a", "Name: a\nDescription: a\nVersion: 1\nRequires: b, c\nLibs: -la\n"),
+            ("b", "Name: b\nDescription: b\nVersion: 1\nConflicts: c < 2\nLibs: -lb\n"),
+            ("c", "Name: c\nDescription: c\nVersion: 1.5\nLibs: -lc_\n"),
+            ("d", "Name: d\nDescription: d\nVersion: 1\nRequires: b >= 2\n"),
+        ]);
+
+        let resolver = Resolver::with_search_path(vec![dir]);
+        assert!(matches!(resolver.resolve("a", None), Err(crate::Error::Conflict { .. })));
+        assert!(matches!(resolver.resolve("d", None), Err(crate::Error::VersionMismatch { required_by: Some(_), .. })));
+    }
+}
@@ -0,0 +1,211 @@
+use std::cmp::Ordering;
+
+/// Compare two versions the way pkg-config does (rpmvercmp).
+///
+/// Versions are split in segments of digits or letters, other characters only separate
+/// segments. Numeric segments are compared numerically and are newer than alphabetic ones.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let mut a = a;
+    let mut b = b;
+    loop {
+        a = a.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+        b = b.trim_start_matches(|c: char| !c.is_ascii_alphanumeric());
+        if a.is_empty() || b.is_empty() {
+            break;
+        }
+
+        let numeric = a.starts_with(|c: char| c.is_ascii_digit());
+        let segment_end = |str: &str| str.find(|c: char| if numeric { !c.is_ascii_digit() } else { !c.is_ascii_alphabetic() }).unwrap_or(str.len());
+        let a_len = segment_end(a);
+        let b_len = segment_end(b);
+        let (a_segment, b_segment) = (&a[..a_len], &b[..b_len]);
+
+        // Segments of different types
+        if b_segment.is_empty() {
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+
+        let ordering = if numeric {
+            let a_segment = a_segment.trim_start_matches('0');
+            let b_segment = b_segment.trim_start_matches('0');
+            a_segment.len().cmp(&b_segment.len()).then(a_segment.cmp(b_segment))
+        } else {
+            a_segment.cmp(b_segment)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        a = &a[a_len..];
+        b = &b[b_len..];
+    }
+
+    // The version with segments left is newer
+    a.len().cmp(&b.len())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl VersionOperator {
+    pub fn parse(str: &str) -> Option<VersionOperator> {
+        match str {
+            "=" | "==" => Some(VersionOperator::Equal),
+            "!=" => Some(VersionOperator::NotEqual),
+            "<" => Some(VersionOperator::Less),
+            "<=" => Some(VersionOperator::LessEqual),
+            ">" => Some(VersionOperator::Greater),
+            ">=" => Some(VersionOperator::GreaterEqual),
+            _ => None
+        }
+    }
+
+    /// Whether `version` satisfies `version <op> required`
+    pub fn matches(&self, version: &str, required: &str) -> bool {
+        let ordering = compare_versions(version, required);
+        match self {
+            VersionOperator::Equal => ordering == Ordering::Equal,
+            VersionOperator::NotEqual => ordering != Ordering::Equal,
+            VersionOperator::Less => ordering == Ordering::Less,
+            VersionOperator::LessEqual => ordering != Ordering::Greater,
+            VersionOperator::Greater => ordering == Ordering::Greater,
+            VersionOperator::GreaterEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl std::fmt::Display for VersionOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VersionOperator::Equal => "=",
+            VersionOperator::NotEqual => "!=",
+            VersionOperator::Less => "<",
+            VersionOperator::LessEqual => "<=",
+            VersionOperator::Greater => ">",
+            VersionOperator::GreaterEqual => ">=",
+        })
+    }
+}
+
+/// A package with an optional version constraint, as found in `Requires` and `Conflicts`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub name: String,
+    pub constraint: Option<(VersionOperator, String)>,
+}
+
+impl Requirement {
+    /// Parses a constraint like `>=1.2` or `>= 1.2`
+    pub fn parse_constraint(constraint: &str) -> crate::Result<(VersionOperator, String)> {
+        let constraint = constraint.trim();
+        let operator_len = constraint.find(|c: char| !"<>=!".contains(c)).unwrap_or(constraint.len());
+        let Some(operator) = VersionOperator::parse(&constraint[..operator_len]) else {
+            return Err(crate::Error::MalformedRequirement(constraint.to_string()));
+        };
+        let version = constraint[operator_len..].trim();
+        if version.is_empty() {
+            return Err(crate::Error::MalformedRequirement(constraint.to_string()));
+        }
+        return Ok((operator, version.to_string()));
+    }
+
+    /// Parses a list of requirements, e.g. `glib-2.0 >= 2.50, gobject-2.0`. Packages can be
+    /// separated by commas or whitespace.
+    pub fn parse_list(str: &str) -> crate::Result<Vec<Requirement>> {
+        let mut tokens = str.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .flat_map(|token| {
+                // Split operators attached to a name or version (e.g. `zlib>=1.2`)
+                let mut parts = Vec::new();
+                let mut rest = token;
+                while let Some(start) = rest.find(|c: char| "<>=!".contains(c)) {
+                    let end = rest[start..].find(|c: char| !"<>=!".contains(c)).map(|end| start + end).unwrap_or(rest.len());
+                    if start > 0 { parts.push(&rest[..start]); }
+                    parts.push(&rest[start..end]);
+                    rest = &rest[end..];
+                }
+                if !rest.is_empty() { parts.push(rest); }
+                parts
+            })
+            .peekable();
+
+        let mut requirements = Vec::new();
+        while let Some(name) = tokens.next() {
+            if VersionOperator::parse(name).is_some() {
+                return Err(crate::Error::MalformedRequirement(str.to_string()));
+            }
+            let constraint = match tokens.peek().and_then(|token| VersionOperator::parse(token)) {
+                Some(operator) => {
+                    _ = tokens.next();
+                    let Some(version) = tokens.next() else {
+                        return Err(crate::Error::MalformedRequirement(str.to_string()));
+                    };
+                    Some((operator, version.to_string()))
+                },
+                None => None
+            };
+            requirements.push(Requirement { name: name.to_string(), constraint });
+        }
+
+        return Ok(requirements);
+    }
+
+    /// Whether `version` satisfies the constraint of this requirement
+    pub fn matches(&self, version: &str) -> bool {
+        match &self.constraint {
+            Some((operator, required)) => operator.matches(version, required),
+            None => true
+        }
+    }
+}
+
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.constraint {
+            Some((operator, version)) => write!(f, "{} {} {}", self.name, operator, version),
+            None => f.write_str(&self.name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    #[test]
+    fn compare() {
+        assert_eq!(compare_versions("1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.02", "1.2"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0a", "2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn requirements() {
+        let requirements = Requirement::parse_list("glib-2.0 >= 2.50, gobject-2.0,zlib>=1.2 libpng != 1.6.0").unwrap();
+        assert_eq!(requirements, vec![
+            Requirement { name: "glib-2.0".to_string(), constraint: Some((VersionOperator::GreaterEqual, "2.50".to_string())) },
+            Requirement { name: "gobject-2.0".to_string(), constraint: None },
+            Requirement { name: "zlib".to_string(), constraint: Some((VersionOperator::GreaterEqual, "1.2".to_string())) },
+            Requirement { name: "libpng".to_string(), constraint: Some((VersionOperator::NotEqual, "1.6.0".to_string())) },
+        ]);
+        assert!(Requirement::parse_list("zlib >=").is_err());
+        assert!(requirements[0].matches("2.80.0"));
+        assert!(!requirements[3].matches("1.6.0"));
+    }
+}
@@ -1,6 +0,0 @@
-use utils::moduse;
-
-#[cfg(unix)]
-pub mod unix;
-
-moduse!(wrapper);
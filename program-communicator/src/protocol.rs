@@ -0,0 +1,131 @@
+//! A small framed request/response protocol.
+//!
+//! A client sends a single [`Frame::Request`]. The server answers with any number of
+//! [`Frame::Stdout`] and [`Frame::Stderr`] frames, followed by a [`Frame::Exit`] frame.
+//!
+//! Every frame is encoded as a one byte kind, followed by the length of the payload as a
+//! big endian `u32` and the payload itself. Payloads are at most [`MAX_PAYLOAD_LEN`] bytes.
+
+use std::io::{self, Read, Write};
+
+const REQUEST: u8 = 0;
+const STDOUT: u8 = 1;
+const STDERR: u8 = 2;
+const EXIT: u8 = 3;
+
+/// The maximum length of the payload of a frame, so that a malformed frame can't make the
+/// reader allocate an arbitrary amount of memory
+pub const MAX_PAYLOAD_LEN: u32 = 1 << 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    Request(String),
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    /// The exit status of the request. This is the last frame sent by the server
+    Exit(i32),
+}
+
+impl Frame {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let exit_status;
+        let (kind, payload): (u8, &[u8]) = match self {
+            Frame::Request(request) => (REQUEST, request.as_bytes()),
+            Frame::Stdout(data) => (STDOUT, data),
+            Frame::Stderr(data) => (STDERR, data),
+            Frame::Exit(status) => {
+                exit_status = status.to_be_bytes();
+                (EXIT, &exit_status)
+            },
+        };
+        let len = u32::try_from(payload.len()).ok()
+            .filter(|len| *len <= MAX_PAYLOAD_LEN)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame payload is too large"))?;
+
+        // Written at once, so that frames sent from multiple threads don't interleave
+        let mut buf = Vec::with_capacity(5 + payload.len());
+        buf.push(kind);
+        buf.extend(len.to_be_bytes());
+        buf.extend(payload);
+        writer.write_all(&buf)?;
+        writer.flush()
+    }
+
+    /// Read the next frame. Returns `None` when the stream is closed before a new frame starts
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Option<Frame>> {
+        let mut kind = [0u8; 1];
+        loop {
+            match reader.read(&mut kind) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len);
+        if len > MAX_PAYLOAD_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("frame payload of {} bytes is too large", len)));
+        }
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload)?;
+
+        let frame = match kind[0] {
+            REQUEST => Frame::Request(String::from_utf8(payload)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?),
+            STDOUT => Frame::Stdout(payload),
+            STDERR => Frame::Stderr(payload),
+            EXIT => Frame::Exit(i32::from_be_bytes(payload.as_slice().try_into()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid exit status frame"))?)),
+            kind => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid frame kind {}", kind))),
+        };
+        return Ok(Some(frame));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Frame, MAX_PAYLOAD_LEN};
+
+    #[test]
+    fn roundtrip() {
+        let frames = [
+            Frame::Request("build 0:1".to_string()),
+            Frame::Stdout(b"hello\n".to_vec()),
+            Frame::Stderr(Vec::new()),
+            Frame::Exit(-3),
+        ];
+
+        let mut buf = Vec::new();
+        for frame in frames.iter() {
+            frame.write_to(&mut buf).unwrap();
+        }
+
+        let mut reader = buf.as_slice();
+        for frame in frames.iter() {
+            assert_eq!(Frame::read_from(&mut reader).unwrap().as_ref(), Some(frame));
+        }
+        assert_eq!(Frame::read_from(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut buf = Vec::new();
+        Frame::Stdout(b"hello".to_vec()).write_to(&mut buf).unwrap();
+        buf.pop();
+        assert!(Frame::read_from(&mut buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn too_large() {
+        let mut buf = vec![1u8];
+        buf.extend((MAX_PAYLOAD_LEN + 1).to_be_bytes());
+        let err = Frame::read_from(&mut buf.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let frame = Frame::Stdout(vec![0u8; MAX_PAYLOAD_LEN as usize + 1]);
+        assert_eq!(frame.write_to(&mut Vec::new()).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+}
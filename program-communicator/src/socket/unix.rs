@@ -1,10 +1,14 @@
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 
 use log::{error, trace};
 
+use crate::protocol::Frame;
+
 use super::ReceiveResult;
 
 #[derive(thiserror::Error)]
@@ -29,85 +33,142 @@ pub struct Socket {
 }
 
 impl Socket {
-    pub fn sh_write_str_netcat_and_wait(&self, mkfifo: &Path, cat: &Path, netcat: &Path, netcat_send_data: &str, response_file: &Path, escape_dollar: bool, out: &mut String) -> std::fmt::Result {
-        use std::fmt::Write;
-
-        let response_file = response_file.to_str().unwrap();
-        let response_result_file = format!("{}_result", response_file);
-        let mkfifo = mkfifo.to_str().unwrap();
-        let cat = cat.to_str().unwrap();
-        let dollar = if escape_dollar { "$$" } else { "$" };
-
-        // Set up waiting for response
-        out.write_fmt(format_args!("{} {} && {} {} > {} & beaver_cat_pid={}! && ",
-            mkfifo, response_file,
-            cat, response_file, &response_result_file,
-            dollar
-        ))?;
-
-        // Send message
-        self.sh_write_str_netcat(netcat, netcat_send_data, out)?;
-
-        // wait for response proces to end
-        out.write_fmt(format_args!(" && wait {}beaver_cat_pid && test {}({} {}) -eq 0", dollar, dollar, cat, response_result_file))
+    /// Send a request without waiting for a response
+    pub fn send(&self, request: &str) -> io::Result<()> {
+        let mut stream = UnixStream::connect(&self.socket_file)?;
+        Frame::Request(request.to_string()).write_to(&mut stream)
     }
+}
 
-    pub fn sh_write_str_netcat(&self, netcat: &Path, data: &str, out: &mut String) -> std::fmt::Result {
-        use std::fmt::Write;
-
-        let netcat = netcat.to_str().unwrap();
+/// A connection accepted by the socket. Frames can be sent from multiple threads.
+#[derive(Clone, Debug)]
+pub struct Connection {
+    stream: Arc<Mutex<UnixStream>>,
+}
 
-        // let mut args: Vec<&str> = Vec::new();
-        // #[cfg(any(target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly", target_os = "netbsd"))] {
-            // args.push("-U");
-        // }
-        let socket_file = self.socket_file.to_str().unwrap();
-        // args.push(socket_file);
-        let args = ["-U", socket_file];
+impl Connection {
+    fn lock(&self) -> io::Result<std::sync::MutexGuard<'_, UnixStream>> {
+        self.stream.lock()
+            .map_err(|err| io::Error::other(err.to_string()))
+    }
 
-        out.write_fmt(format_args!("echo \"{}\" | {} {}",
-            data.replace("\"", "\\\""),
-            netcat,
-            args.iter().map(|str| format!("\"{str}\"")).collect::<Vec<String>>().join(" "),
-        ))
+    pub fn read_frame(&self) -> io::Result<Option<Frame>> {
+        Frame::read_from(&mut *self.lock()?)
     }
 
-    pub fn send(&self, cmd: &str) -> io::Result<()> {
-        let mut stream = UnixStream::connect(&self.socket_file)?;
+    pub fn send(&self, frame: &Frame) -> io::Result<()> {
+        frame.write_to(&mut *self.lock()?)
+    }
 
-        stream.write(cmd.as_bytes())?;
+    /// Send everything read from `reader` as frames created by `frame`, until `reader` is closed
+    pub fn forward(&self, mut reader: impl Read, frame: fn(Vec<u8>) -> Frame) -> io::Result<()> {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => self.send(&frame(buf[..n].to_vec()))?,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
 
-        Ok(())
+/// Send `request` to the socket at `socket_file` and write the output of the request to
+/// `stdout` and `stderr`. Returns the exit status of the request.
+pub fn request(socket_file: &Path, request: &str, stdout: &mut dyn Write, stderr: &mut dyn Write) -> io::Result<i32> {
+    let mut stream = UnixStream::connect(socket_file)?;
+    Frame::Request(request.to_string()).write_to(&mut stream)?;
+
+    loop {
+        match Frame::read_from(&mut stream)? {
+            Some(Frame::Stdout(data)) => {
+                stdout.write_all(&data)?;
+                stdout.flush()?;
+            },
+            Some(Frame::Stderr(data)) => {
+                stderr.write_all(&data)?;
+                stderr.flush()?;
+            },
+            Some(Frame::Exit(status)) => return Ok(status),
+            Some(Frame::Request(_)) => return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected request frame")),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed before an exit status was received")),
+        }
     }
 }
 
+/// Each socket gets its own file, so that multiple processes (or multiple sockets in one
+/// process) don't clobber each other
+fn socket_file(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("{}_{}_{}.socket", name, std::process::id(), n))
+}
+
+/// Listen for connections on a new socket. Every connection is handled on its own thread by
+/// `receive_cb`. When it returns [`ReceiveResult::Close`] or an error, the socket stops accepting
+/// connections and its thread finishes once all connections have been handled.
 pub fn listen(
     name: &str,
-    receive_cb: impl Fn(&mut UnixStream) -> Result<ReceiveResult, Box<dyn std::error::Error + Send>> + Send + 'static
+    receive_cb: impl Fn(&Connection) -> Result<ReceiveResult, Box<dyn std::error::Error + Send>> + Send + Sync + 'static
 ) -> Result<Socket, io::Error> {
-    let file = std::env::temp_dir().join(name.to_string() + ".socket");
+    let file = socket_file(name);
     if file.exists() {
         std::fs::remove_file(&file)?;
     }
 
     let listener = UnixListener::bind(&file)?;
 
-    trace!("Listening on unix socket {:?}", &file);
+    trace!("Listening on unix socket {:?}", file);
 
+    let listener_file = file.clone();
     let handle = std::thread::spawn(move || -> Result<(), Error> {
-        for stream in listener.incoming() {
-            let mut stream = stream?;
-            match receive_cb(&mut stream) {
-                Ok(ReceiveResult::Close) => break,
-                Ok(_) => {},
-                Err(err) => {
-                    error!("{}", err);
-                    return Err(Error::Boxed(err));
-                },
+        let receive_cb = Arc::new(receive_cb);
+        let closing = Arc::new(AtomicBool::new(false));
+        let handler_error: Arc<Mutex<Option<Error>>> = Arc::new(Mutex::new(None));
+        let mut handlers: Vec<JoinHandle<()>> = Vec::new();
+
+        let result = (|| -> Result<(), Error> {
+            for stream in listener.incoming() {
+                if closing.load(Ordering::SeqCst) {
+                    break;
+                }
+                let connection = Connection { stream: Arc::new(Mutex::new(stream?)) };
+
+                handlers.retain(|handler| !handler.is_finished());
+                let receive_cb = receive_cb.clone();
+                let closing = closing.clone();
+                let handler_error = handler_error.clone();
+                let socket_file = listener_file.clone();
+                handlers.push(std::thread::spawn(move || {
+                    let close = match receive_cb(&connection) {
+                        Ok(ReceiveResult::Close) => true,
+                        Ok(_) => false,
+                        Err(err) => {
+                            error!("{}", err);
+                            handler_error.lock().unwrap().get_or_insert(Error::Boxed(err));
+                            true
+                        },
+                    };
+                    if close && !closing.swap(true, Ordering::SeqCst) {
+                        // Wake up the listener, which is waiting for the next connection
+                        _ = UnixStream::connect(&socket_file);
+                    }
+                }));
             }
-        }
 
-        Ok(())
+            Ok(())
+        })();
+
+        for handler in handlers {
+            _ = handler.join();
+        }
+        _ = std::fs::remove_file(&listener_file);
+        result?;
+        match handler_error.lock().unwrap().take() {
+            Some(err) => Err(err),
+            None => Ok(())
+        }
     });
 
     Ok(Socket {
@@ -115,3 +176,70 @@ pub fn listen(
         socket_file: file
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::Frame;
+    use crate::socket::ReceiveResult;
+
+    #[test]
+    fn request_response() {
+        let socket = super::listen("program_communicator_test", |connection| {
+            let Some(Frame::Request(request)) = connection.read_frame().unwrap() else {
+                panic!("expected a request");
+            };
+            if request == "close" {
+                return Ok(ReceiveResult::Close);
+            }
+            connection.forward(request.as_bytes(), Frame::Stdout).unwrap();
+            connection.send(&Frame::Stderr(b"err".to_vec())).unwrap();
+            connection.send(&Frame::Exit(request.len() as i32)).unwrap();
+            Ok(ReceiveResult::Continue)
+        }).unwrap();
+        let other = super::listen("program_communicator_test", |_| Ok(ReceiveResult::Close)).unwrap();
+        assert_ne!(socket.socket_file, other.socket_file);
+        other.send("close").unwrap();
+        other.thread_handle.join().unwrap().unwrap();
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let status = super::request(&socket.socket_file, "hello", &mut stdout, &mut stderr).unwrap();
+        assert_eq!(status, 5);
+        assert_eq!(stdout, b"hello");
+        assert_eq!(stderr, b"err");
+
+        socket.send("close").unwrap();
+        let file = socket.socket_file.clone();
+        socket.thread_handle.join().unwrap().unwrap();
+        assert!(!file.exists());
+    }
+
+    /// Both requests only finish when they are handled at the same time
+    #[test]
+    fn concurrent_connections() {
+        let barrier = std::sync::Barrier::new(2);
+        let socket = super::listen("program_communicator_test", move |connection| {
+            let Some(Frame::Request(request)) = connection.read_frame().unwrap() else {
+                panic!("expected a request");
+            };
+            if request == "close" {
+                return Ok(ReceiveResult::Close);
+            }
+            barrier.wait();
+            connection.send(&Frame::Exit(0)).unwrap();
+            Ok(ReceiveResult::Continue)
+        }).unwrap();
+
+        let file = socket.socket_file.clone();
+        let clients = (0..2).map(|_| {
+            let file = file.clone();
+            std::thread::spawn(move || super::request(&file, "wait", &mut Vec::new(), &mut Vec::new()).unwrap())
+        }).collect::<Vec<_>>();
+        for client in clients {
+            assert_eq!(client.join().unwrap(), 0);
+        }
+
+        socket.send("close").unwrap();
+        socket.thread_handle.join().unwrap().unwrap();
+    }
+}
@@ -1,5 +1,4 @@
 use std::error::Error;
-use std::io;
 
 use super::ReceiveResult;
 
@@ -9,9 +8,9 @@ mod unix {
     use std::io;
     use std::path::Path;
 
+    pub use super::super::unix::{request, Connection};
+
     pub trait SocketUnixExt {
-        fn sh_write_str_netcat_and_wait(&self, mkfifo: &Path, cat: &Path, netcat: &Path, netcat_send_data: &str, response_file: impl AsRef<Path>, escape_dollar: bool, out: &mut String) -> std::fmt::Result;
-        fn sh_write_str_netcat(&self, netcat: &Path, data: &str, out: &mut String) -> std::fmt::Result;
         fn wait(self) -> Result<(), Box<dyn Error + Send>>;
     }
 
@@ -20,38 +19,40 @@ mod unix {
     pub struct Socket(pub(crate) super::super::unix::Socket);
 
     impl Socket {
+        /// Send a request without waiting for a response
         pub fn send(&self, cmd: &str) -> io::Result<()> {
             self.0.send(cmd)
         }
+
+        /// The file clients connect to
+        pub fn path(&self) -> &Path {
+            &self.0.socket_file
+        }
     }
 
     #[cfg(unix)]
     impl SocketUnixExt for Socket {
-        fn sh_write_str_netcat_and_wait(&self, mkfifo: &Path, cat: &Path, netcat: &Path, netcat_send_data: &str, response_file: impl AsRef<Path>, escape_dollar: bool, out: &mut String) -> std::fmt::Result {
-            self.0.sh_write_str_netcat_and_wait(mkfifo, cat, netcat, netcat_send_data, response_file.as_ref(), escape_dollar, out)
-        }
-
-        fn sh_write_str_netcat(&self, netcat: &Path, data: &str, out: &mut String) -> std::fmt::Result {
-            self.0.sh_write_str_netcat(netcat, data, out)
-        }
-
         fn wait(self) -> Result<(), Box<dyn Error + Send>> {
-            self.0.thread_handle.join().unwrap()
-                .map_err(|err| Box::new(err) as Box<dyn Error + Send>)
+            match self.0.thread_handle.join() {
+                Ok(result) => result.map_err(|err| Box::new(err) as Box<dyn Error + Send>),
+                Err(_) => Err(Box::new(io::Error::other("the socket's listener thread panicked"))),
+            }
         }
     }
 }
 #[cfg(unix)]
 pub use unix::*;
 
+/// Listen on a new socket. `name` is used as a prefix for the socket's file, every call
+/// gets its own file. Connections are handled concurrently.
 pub fn listen(
     name: &str,
-    receive_cb: impl Fn(&mut dyn io::Read) -> Result<ReceiveResult, Box<dyn Error + Send>> + Send + 'static
+    receive_cb: impl Fn(&Connection) -> Result<ReceiveResult, Box<dyn Error + Send>> + Send + Sync + 'static
 ) -> Result<Socket, Box<dyn Error>> {
     #[cfg(unix)] {
-        super::unix::listen(name, move |stream| {
-            receive_cb(stream)
-        })  .map(|socket| Socket(socket))
+        super::unix::listen(name, move |connection| {
+            receive_cb(connection)
+        })  .map(Socket)
             .map_err(Into::into)
     }
 }
@@ -41,3 +41,32 @@ pub fn symlink_dir(
 ) -> io::Result<()> {
     std::os::wasi::symlink_path(original, link)
 }
+
+#[inline]
+#[cfg(unix)]
+pub fn symlink_file(
+    original: impl AsRef<Path>,
+    link: impl AsRef<Path>
+) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+/// # Limitations
+/// See [`symlink_dir`]
+#[inline]
+#[cfg(windows)]
+pub fn symlink_file(
+    original: impl AsRef<Path>,
+    link: impl AsRef<Path>
+) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+#[inline]
+#[cfg(target_os = "wasi")]
+pub fn symlink_file(
+    original: impl AsRef<Path>,
+    link: impl AsRef<Path>
+) -> io::Result<()> {
+    std::os::wasi::symlink_path(original, link)
+}